@@ -17,13 +17,14 @@
 //! Substrate chain configurations.
 
 use sc_chain_spec::ChainSpecExtension;
-use sp_core::{Pair, Public, crypto::UncheckedInto, sr25519};
+use sp_core::{Pair, Public, H512, crypto::UncheckedInto, ed25519, sr25519};
 use serde::{Serialize, Deserialize};
 use node_runtime::{
 	AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, CouncilConfig, DemocracyConfig,
 	GrandpaConfig, ImOnlineConfig, IndicesConfig, SessionConfig, SessionKeys, StakerStatus, StakingConfig, SudoConfig,
-	SystemConfig, TechnicalCommitteeConfig, DatCollectiveConfig, WASM_BINARY,
+	SystemConfig, TechnicalCommitteeConfig, DatCollectiveConfig, TrustedSeedersConfig, WASM_BINARY,
 };
+use pallet_dat_verify::{ParentHashInRoot, RootHashPayload, hashing};
 use node_runtime::Block;
 use node_runtime::constants::currency::*;
 use sc_service;
@@ -36,7 +37,7 @@ use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use sp_runtime::{Perbill, traits::{Verify, IdentifyAccount}};
 
 pub use node_primitives::{AccountId, Balance, Signature};
-pub use node_runtime::GenesisConfig;
+pub use node_runtime::{GenesisConfig, DatVerifyConfig};
 
 type AccountPublic = <Signature as Verify>::Signer;
 
@@ -148,6 +149,7 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
 		root_key,
 		Some(endowed_accounts),
 		false,
+		Default::default(),
 	)
 }
 
@@ -199,12 +201,31 @@ pub fn get_authority_keys_from_seed(seed: &str) -> (
 	)
 }
 
+/// Helper function to generate a single chunk archive, keyed and signed by the ed25519 key of
+/// `seed`, as it is registered
+pub fn single_chunk_archive(seed: &str, chunk: &[u8]) -> (ed25519::Public, RootHashPayload, H512) {
+	let pair = ed25519::Pair::from_string(&format!("//{}", seed), None)
+		.expect("static values are valid; qed");
+	let root = ParentHashInRoot {
+		hash: hashing::leaf(chunk),
+		hash_number: 0,
+		total_length: chunk.len() as u64,
+	};
+	let signature = pair.sign(hashing::tree(&[root]).as_bytes());
+	(
+		pair.public(),
+		RootHashPayload { hash_type: hashing::ROOT_TYPE, children: vec![root] },
+		H512::from(signature),
+	)
+}
+
 /// Helper function to create GenesisConfig for testing
 pub fn testnet_genesis(
 	initial_authorities: Vec<(AccountId, AccountId, GrandpaId, BabeId, ImOnlineId, AuthorityDiscoveryId)>,
 	root_key: AccountId,
 	endowed_accounts: Option<Vec<AccountId>>,
 	enable_println: bool,
+	dat_verify: DatVerifyConfig,
 ) -> GenesisConfig {
 	let endowed_accounts: Vec<AccountId> = endowed_accounts.unwrap_or_else(|| {
 		vec![
@@ -291,6 +312,26 @@ pub fn testnet_genesis(
 			authorities: vec![],
 		}),
 		pallet_membership_Instance1: Some(Default::default()),
+		pallet_dat_verify: Some(dat_verify),
+		pallet_scored_pool: Some(TrustedSeedersConfig {
+			pool: vec![],
+			member_count: 16,
+			members: vec![],
+			phantom: Default::default(),
+		}),
+	}
+}
+
+/// Dat Verify genesis with a demo archive published by Alice and seeded by Bob
+fn development_dat_verify_genesis() -> DatVerifyConfig {
+	let archive = single_chunk_archive("DatdotDemo", b"Hello, datdot!");
+	DatVerifyConfig {
+		seeders: vec![
+			(get_account_id_from_seed::<sr25519::Public>("Bob"), vec![archive.0]),
+		],
+		archives: vec![
+			(get_account_id_from_seed::<sr25519::Public>("Alice"), archive),
+		],
 	}
 }
 
@@ -302,6 +343,7 @@ fn development_config_genesis() -> GenesisConfig {
 		get_account_id_from_seed::<sr25519::Public>("Alice"),
 		None,
 		true,
+		development_dat_verify_genesis(),
 	)
 }
 
@@ -328,6 +370,7 @@ fn local_testnet_genesis() -> GenesisConfig {
 		get_account_id_from_seed::<sr25519::Public>("Alice"),
 		None,
 		false,
+		Default::default(),
 	)
 }
 
@@ -350,6 +393,7 @@ pub(crate) mod tests {
 	use super::*;
 	use crate::service::{new_full, new_light};
 	use sc_service_test;
+	use sp_runtime::BuildStorage;
 
 	fn local_testnet_genesis_instant_single() -> GenesisConfig {
 		testnet_genesis(
@@ -359,6 +403,7 @@ pub(crate) mod tests {
 			get_account_id_from_seed::<sr25519::Public>("Alice"),
 			None,
 			false,
+			Default::default(),
 		)
 	}
 
@@ -390,6 +435,16 @@ pub(crate) mod tests {
 		)
 	}
 
+	#[test]
+	fn test_create_development_chain_spec() {
+		development_config().build_storage().unwrap();
+	}
+
+	#[test]
+	fn test_create_local_testnet_chain_spec() {
+		local_testnet_config().build_storage().unwrap();
+	}
+
 	#[test]
 	#[ignore]
 	fn test_connectivity() {
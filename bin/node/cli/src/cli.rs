@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::path::PathBuf;
 use sc_cli::{SharedParams, ImportParams, RunCmd};
 use structopt::StructOpt;
 
@@ -31,6 +32,10 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub run: RunCmd,
+	/// Answer the dat_verify challenges of the seeder keys in the keystore from the hypercore
+	/// feeds in this directory. Enables the offchain worker.
+	#[structopt(long = "dat-dir", parse(from_os_str))]
+	pub dat_dir: Option<PathBuf>,
 }
 
 #[allow(missing_docs)]
@@ -42,10 +47,17 @@ pub enum Subcommand {
 	/// The custom factory subcommmand for manufacturing transactions.
 	#[structopt(
 		name = "factory",
-		about = "Manufactures num transactions from Alice to random accounts. \
-		Only supported for development or local testnet."
+		about = "Manufactures num transactions from Alice to random accounts, or dat archives \
+		published, seeded and proved by Alice. Only supported for development or local testnet."
 	)]
 	Factory(FactoryCmd),
+	/// The datdot subcommands publishing, seeding and proving archives through a node.
+	#[structopt(
+		name = "datdot",
+		about = "Publishes, seeds and proves dat archives by submitting dat_verify extrinsics \
+		to the RPC endpoint of a node."
+	)]
+	Datdot(DatdotCmd),
 }
 
 /// The `factory` command used to generate transactions.
@@ -72,7 +84,12 @@ pub struct FactoryCmd {
 	///               B -> E, C -> F, D -> G, ...{n}
 	///               ... x `rounds`
 	///
-	/// These three modes control manufacturing.
+	/// Datdot: Register `num` synthetic dat archives from the master account,
+	///         each with a storage deal, seed them from the master account,
+	///         then answer every challenge it gets for `num` blocks with a
+	///         valid proof.
+	///
+	/// These four modes control manufacturing.
 	#[structopt(long="mode", default_value = "MasterToN")]
 	pub mode: node_transaction_factory::Mode,
 
@@ -81,6 +98,10 @@ pub struct FactoryCmd {
 	#[structopt(long="num", default_value = "8")]
 	pub num: u64,
 
+	/// Where mode `Datdot` writes its synthetic feeds, a temporary directory by default.
+	#[structopt(long="feed-dir", parse(from_os_str))]
+	pub feed_dir: Option<PathBuf>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -89,3 +110,98 @@ pub struct FactoryCmd {
 	#[structopt(flatten)]
 	pub import_params: ImportParams,
 }
+
+/// The `datdot` command, submitting dat_verify extrinsics signed with a `--suri` or keystore key.
+#[derive(Debug, StructOpt, Clone)]
+pub struct DatdotCmd {
+	#[allow(missing_docs)]
+	#[structopt(subcommand)]
+	pub action: DatdotAction,
+
+	/// The HTTP RPC endpoint of the node.
+	#[structopt(long = "url", default_value = "http://localhost:9933", global = true)]
+	pub url: String,
+
+	/// The secret URI of the sr25519 key signing the extrinsics, e.g. `//Alice`.
+	#[structopt(long = "suri", conflicts_with = "keystore-path", global = true)]
+	pub suri: Option<String>,
+
+	/// Sign with the `datv` seeder key in the keystore at this path.
+	#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str), global = true)]
+	pub keystore_path: Option<PathBuf>,
+
+	/// The password of the keystore.
+	#[structopt(long = "password", global = true)]
+	pub password: Option<String>,
+}
+
+/// What the `datdot` command does.
+#[derive(Debug, StructOpt, Clone)]
+pub enum DatdotAction {
+	/// Register the hypercore feed, or the hyperdrive, in a directory, or update its
+	/// registration.
+	Publish {
+		/// The directory of the feed, holding its `key`, `tree`, `signatures` and `data`, or of
+		/// the drive, holding its `metadata` and `content` feeds.
+		#[structopt(parse(from_os_str))]
+		feed_dir: PathBuf,
+
+		/// Register the feed as it was at this many chunks, rather than at its current length.
+		/// Drives are registered at their current length.
+		#[structopt(long = "length")]
+		length: Option<u64>,
+
+		/// Register the feed privately, by a registration key derived from its secret key, keeping
+		/// its key off chain. Its seeders are then handed the key off chain, see `deliver_key`.
+		#[structopt(long = "private")]
+		private: bool,
+	},
+	/// Have the archive published from a directory assigned only to trusted seeders, those
+	/// with the most reputation, or to any seeder again. Its current seeders keep it.
+	Trust {
+		/// The directory of the feed, or of the drive, as for `publish`.
+		#[structopt(parse(from_os_str))]
+		feed_dir: PathBuf,
+
+		/// The feed was registered privately, by its registration key.
+		#[structopt(long = "private")]
+		private: bool,
+
+		/// Have the archive assigned to any seeder again.
+		#[structopt(long = "any")]
+		any: bool,
+	},
+	/// Ask to be assigned archives to seed, reserving the seeder bond.
+	Seed {
+		/// Seed up to this many archives.
+		#[structopt(long = "archives", default_value = "1")]
+		archives: u32,
+
+		/// Seed as many archives as fit in this many bytes instead.
+		#[structopt(long = "capacity")]
+		capacity: Option<u64>,
+	},
+	/// Declare, or update, the bytes of archives the signing account has room for, bonding
+	/// for every gigabyte of it. Archives are then only assigned within what is left of it.
+	Capacity {
+		/// The declared capacity, in bytes.
+		bytes: u64,
+	},
+	/// Stop seeding, releasing what is left of the seeder bond.
+	Unseed,
+	/// Answer a challenge of the signing account.
+	Prove {
+		/// The index of the challenge.
+		challenge: u64,
+
+		/// The directory holding the challenged feed, in a directory named after the hex encoded
+		/// key of its archive, as for `--dat-dir`.
+		#[structopt(long = "dat-dir", parse(from_os_str))]
+		dat_dir: PathBuf,
+	},
+	/// Print the archives hosted by, and the open challenges of, the signing account.
+	Status,
+	/// Print the custom types of the datdot modules as Polkadot.js takes them, which is
+	/// `bin/node/runtime/types.json`. Needs no node nor key.
+	Types,
+}
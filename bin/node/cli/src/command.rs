@@ -36,7 +36,7 @@ where
 			config,
 			opt.run,
 			service::new_light,
-			service::new_full,
+			move |config| service::new_full_with_dat_dir(config, opt.dat_dir),
 			load_spec,
 			&version,
 		),
@@ -55,11 +55,14 @@ where
 				_ => panic!("Factory is only supported for development and local testnet."),
 			}
 
-			let factory_state = FactoryState::new(
+			let mut factory_state = FactoryState::new(
 				cli_args.mode.clone(),
 				cli_args.num,
 				cli_args.rounds,
 			);
+			if let Some(feed_dir) = cli_args.feed_dir.clone() {
+				factory_state.set_feed_dir(feed_dir);
+			}
 
 			let service_builder = new_full_start!(config).0;
 			node_transaction_factory::factory::<FactoryState<_>, _, _, _, _, _>(
@@ -71,6 +74,7 @@ where
 
 			Ok(())
 		},
+		Some(Subcommand::Datdot(cli_args)) => crate::datdot::run(cli_args),
 		Some(Subcommand::Base(subcommand)) => sc_cli::run_subcommand(
 			config,
 			subcommand,
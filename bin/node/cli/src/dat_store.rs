@@ -0,0 +1,196 @@
+//! Serving the chunk requests of the dat_verify offchain worker from hypercore feeds on disk.
+//!
+//! The worker queues its requests in the offchain storage, see `pallet_dat_verify::offchain`.
+//! Every feed is read with `dat_proof_builder` from the directory named after the hex encoded
+//! key of its archive.
+
+use std::{io, path::PathBuf, time::Duration};
+use codec::{Codec, Decode, Encode};
+use sp_core::{hexdisplay::HexDisplay, offchain::OffchainStorage};
+use sc_offchain::STORAGE_PREFIX;
+use dat_proof_builder::Feed;
+use node_runtime::constants::time::MILLISECS_PER_BLOCK;
+use pallet_dat_verify::offchain::{ChunkRequest, ChunkResponse, REQUESTS_KEY, RESPONSES_KEY};
+
+// responses the worker did not use, for challenges that expired, are dropped past this many.
+const MAX_RESPONSES: usize = 64;
+// the worker of a block queues its requests, and looks for the responses from the worker of the
+// next block on, so the requests are looked for many times a block.
+const POLLS_PER_BLOCK: u64 = 20;
+const POLL_INTERVAL: Duration = Duration::from_millis(MILLISECS_PER_BLOCK / POLLS_PER_BLOCK);
+
+/// The hypercore feeds in a directory.
+pub struct DatStore {
+	dir: PathBuf,
+}
+
+impl DatStore {
+	/// The feeds in `dir`.
+	pub fn new(dir: PathBuf) -> Self {
+		DatStore { dir }
+	}
+
+	/// Read the chunks and nodes `request` asks for.
+	pub fn read(&self, request: &ChunkRequest) -> io::Result<ChunkResponse> {
+		let dir = self.dir.join(HexDisplay::from(&request.archive.0).to_string());
+		let feed = Feed::new(dir, request.archive);
+		let chunks = request.leaves.iter()
+			.map(|leaf| feed.chunk(*leaf))
+			.collect::<io::Result<Vec<_>>>()?;
+		let nodes = request.nodes.iter()
+			.map(|index| feed.node(*index))
+			.collect::<io::Result<Vec<_>>>()?;
+		Ok(ChunkResponse {
+			archive: request.archive,
+			leaves: request.leaves.clone(),
+			chunks,
+			nodes,
+		})
+	}
+
+	/// Answer the requests queued in `db`, dropping those that cannot be read.
+	pub fn answer_requests<S: OffchainStorage>(&self, db: &mut S) {
+		let requests: Vec<ChunkRequest> = match db.get(STORAGE_PREFIX, REQUESTS_KEY)
+			.and_then(|encoded| Decode::decode(&mut &encoded[..]).ok())
+		{
+			Some(requests) => requests,
+			None => return,
+		};
+		if requests.is_empty() {
+			return;
+		}
+		let responses: Vec<ChunkResponse> = requests.iter()
+			.filter_map(|request| self.read(request).map_err(|error| log::warn!(
+				target: "dat-store",
+				"Cannot read leaves {:?} of {}: {}",
+				request.leaves,
+				HexDisplay::from(&request.archive.0),
+				error,
+			)).ok())
+			.collect();
+		mutate(db, RESPONSES_KEY, |stored: &mut Vec<ChunkResponse>| {
+			stored.extend(responses.iter().cloned());
+			let excess = stored.len().saturating_sub(MAX_RESPONSES);
+			stored.drain(..excess);
+		});
+		mutate(db, REQUESTS_KEY, |stored: &mut Vec<ChunkRequest>| {
+			stored.retain(|request| !requests.contains(request));
+		});
+	}
+}
+
+/// Answer the requests queued in `db` from `store`, for as long as the node runs.
+pub async fn serve<S: OffchainStorage>(store: DatStore, mut db: S) {
+	loop {
+		futures_timer::Delay::new(POLL_INTERVAL).await;
+		store.answer_requests(&mut db);
+	}
+}
+
+/// Change the value at `key` in `db` with `f`, retrying until no one changed it meanwhile.
+fn mutate<S: OffchainStorage, T: Codec + Default>(db: &mut S, key: &[u8], f: impl Fn(&mut T)) {
+	loop {
+		let old = db.get(STORAGE_PREFIX, key);
+		let mut value = old.as_ref()
+			.and_then(|encoded| T::decode(&mut &encoded[..]).ok())
+			.unwrap_or_default();
+		f(&mut value);
+		if db.compare_and_set(STORAGE_PREFIX, key, old.as_deref(), &value.encode()) {
+			return;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::{fs, path::Path};
+	use sp_core::{H256, ed25519, offchain::storage::InMemOffchainStorage};
+	use pallet_dat_verify::Node;
+
+	// the header of the `tree` and `signatures` files.
+	const HEADER_SIZE: usize = 32;
+
+	/// Write a feed of `chunks` under `dir`, with nodes hashed from their index and a signature
+	/// made of the length it signs.
+	fn write_feed(dir: &Path, archive: &ed25519::Public, chunks: &[&[u8]]) {
+		let feed = dir.join(HexDisplay::from(&archive.0).to_string());
+		fs::create_dir_all(&feed).unwrap();
+		fs::write(feed.join("data"), chunks.concat()).unwrap();
+
+		let mut sizes = vec![0u64; 2 * chunks.len()];
+		for (i, chunk) in chunks.iter().enumerate() {
+			sizes[2 * i] = chunk.len() as u64;
+		}
+		// parents span the leaves under them, and are only written once complete.
+		for index in (0..sizes.len() as u64).filter(|index| index % 2 == 1) {
+			let (left, right) = flat_tree::spans(index);
+			if (right as usize) < sizes.len() {
+				sizes[index as usize] = (left..=right).step_by(2).map(|leaf| sizes[leaf as usize]).sum();
+			}
+		}
+		let mut tree = vec![0u8; HEADER_SIZE];
+		for (index, size) in sizes.iter().enumerate() {
+			tree.extend_from_slice(H256::repeat_byte(index as u8).as_bytes());
+			tree.extend_from_slice(&size.to_be_bytes());
+		}
+		fs::write(feed.join("tree"), tree).unwrap();
+
+		let mut signatures = vec![0u8; HEADER_SIZE];
+		for length in 1..=chunks.len() {
+			signatures.extend_from_slice(&[length as u8; 64]);
+		}
+		fs::write(feed.join("signatures"), signatures).unwrap();
+	}
+
+	/// The request for the chunks at `leaves` of `archive`, a feed of `length` chunks.
+	fn request(archive: ed25519::Public, leaves: &[u64], length: u64) -> ChunkRequest {
+		ChunkRequest {
+			archive,
+			leaves: leaves.to_vec(),
+			nodes: pallet_dat_verify::offchain::proof_nodes(leaves, length),
+		}
+	}
+
+	#[test]
+	fn reads_the_chunks_and_nodes() {
+		let dir = tempfile::tempdir().unwrap();
+		let archive = ed25519::Public::from_raw([1; 32]);
+		write_feed(dir.path(), &archive, &[b"hello", b"world", b"this is"]);
+		let store = DatStore::new(dir.path().to_path_buf());
+
+		let response = store.read(&request(archive, &[4], 3)).unwrap();
+		assert_eq!(response.leaves, vec![4]);
+		assert_eq!(response.chunks, vec![b"this is".to_vec()]);
+		assert_eq!(response.nodes, vec![
+			Node { index: 1, hash: H256::repeat_byte(1), size: 10 },
+		]);
+
+		let response = store.read(&request(archive, &[0, 2], 3)).unwrap();
+		assert_eq!(response.chunks, vec![b"hello".to_vec(), b"world".to_vec()]);
+		assert_eq!(response.nodes, vec![
+			Node { index: 4, hash: H256::repeat_byte(4), size: 7 },
+		]);
+	}
+
+	#[test]
+	fn answers_queued_requests() {
+		let dir = tempfile::tempdir().unwrap();
+		let archive = ed25519::Public::from_raw([1; 32]);
+		write_feed(dir.path(), &archive, &[b"hello", b"world"]);
+		let store = DatStore::new(dir.path().to_path_buf());
+		let mut db = InMemOffchainStorage::default();
+		// the third chunk is not on disk.
+		let requests = vec![request(archive, &[2], 2), request(archive, &[2, 4], 3)];
+		db.set(STORAGE_PREFIX, REQUESTS_KEY, &requests.encode());
+
+		store.answer_requests(&mut db);
+
+		let responses: Vec<ChunkResponse> =
+			Decode::decode(&mut &db.get(STORAGE_PREFIX, RESPONSES_KEY).unwrap()[..]).unwrap();
+		assert_eq!(responses, vec![store.read(&requests[0]).unwrap()]);
+		let requests: Vec<ChunkRequest> =
+			Decode::decode(&mut &db.get(STORAGE_PREFIX, REQUESTS_KEY).unwrap()[..]).unwrap();
+		assert!(requests.is_empty());
+	}
+}
@@ -0,0 +1,356 @@
+//! The `datdot` command: publishing, seeding and proving archives through the RPC of a node.
+//!
+//! Every action but `status` and `types` signs a dat_verify extrinsic with the `--suri` key, or
+//! the `datv` seeder key of a keystore, and submits it with `author_submitExtrinsic`. The state
+//! of the archives and challenges is queried with the `datdot_*` methods, while `types` only
+//! prints the custom types of the runtime, without a node.
+
+use codec::Encode;
+use futures01::Future;
+use jsonrpc_core_client::{RpcChannel, RpcError, transports::http};
+use sc_cli::error::{Error, Result};
+use sc_rpc_api::{author::AuthorClient, chain::ChainClient, state::StateClient};
+use sp_core::{Pair, sr25519, crypto::Ss58Codec, hexdisplay::HexDisplay};
+use sp_rpc::{list::ListOrValue, number::NumberOrHex};
+use sp_runtime::{MultiSigner, generic::{Era, SignedBlock}, traits::IdentifyAccount};
+use substrate_frame_rpc_system::SystemClient;
+use tokio::runtime::Runtime;
+use node_primitives::{AccountId, Block, BlockNumber, Hash, Header, Index};
+use node_runtime::{Runtime as NodeRuntime, Call, SignedExtra, SignedPayload, UncheckedExtrinsic};
+use pallet_dat_verify::{SeedRequest, Proof, offchain::KEY_TYPE};
+use pallet_dat_verify_rpc::DatVerifyClient;
+use pallet_dat_verify_rpc_runtime_api::{ArchiveInfo, ChallengeInfo, Public};
+use dat_proof_builder::{Drive, Feed, SubmitProof};
+use crate::{DatdotCmd, DatdotAction};
+
+/// Run the `datdot` command.
+pub fn run(cmd: DatdotCmd) -> Result<()> {
+	if let DatdotAction::Types = cmd.action {
+		print!("{}", node_runtime::types::types_json());
+		return Ok(());
+	}
+	let signer = signer(&cmd)?;
+	let account = account_of(&signer);
+	let mut node = Node::connect(&cmd.url)?;
+	match cmd.action {
+		DatdotAction::Publish { feed_dir, length, private } if Drive::is_drive(&feed_dir) => {
+			if length.is_some() || private {
+				return Err(Error::Input("`--length` and `--private` only apply to a single feed".into()));
+			}
+			publish_drive(&mut node, &signer, Drive::open(feed_dir)?)
+		},
+		DatdotAction::Publish { feed_dir, length, private } => {
+			let feed = Feed::open(feed_dir)?;
+			let length = match length {
+				Some(length) => length,
+				None => feed.length()?,
+			};
+			// a private feed is registered by its registration key.
+			let archive = if private { feed.registration_pair()?.public() } else { feed.key() };
+			let registered = node.run(node.dat_verify().archive_info(archive, None))?;
+			let registered_length = registered.map(|info| info.tree_length);
+			if registered_length == Some(length) {
+				println!("{} is already registered at {} chunks", archive_key(&archive), length);
+				return Ok(());
+			}
+			let call = if private {
+				feed.register_private_data(length, registered_length)?.call::<NodeRuntime>()
+			} else {
+				feed.register_data(length, registered_length)?.call::<NodeRuntime>()
+			};
+			node.submit(&signer, Call::DatVerify(call))
+		},
+		DatdotAction::Trust { feed_dir, private, any } => {
+			// a drive is assigned by its metadata feed, a private feed by its registration key.
+			let archive = if Drive::is_drive(&feed_dir) {
+				Drive::open(feed_dir)?.metadata().key()
+			} else {
+				let feed = Feed::open(feed_dir)?;
+				if private { feed.registration_pair()?.public() } else { feed.key() }
+			};
+			let call = pallet_dat_verify::Call::require_trusted(archive, !any);
+			node.submit(&signer, Call::DatVerify(call))
+		},
+		DatdotAction::Seed { archives, capacity } => {
+			let request = match capacity {
+				Some(capacity) => SeedRequest::Capacity(capacity),
+				None => SeedRequest::Archives(archives),
+			};
+			node.submit(&signer, Call::DatVerify(pallet_dat_verify::Call::register_seeder(request)))
+		},
+		DatdotAction::Capacity { bytes } => {
+			node.submit(&signer, Call::DatVerify(pallet_dat_verify::Call::declare_capacity(bytes)))
+		},
+		DatdotAction::Unseed => {
+			node.submit(&signer, Call::DatVerify(pallet_dat_verify::Call::unregister_seeder()))
+		},
+		DatdotAction::Prove { challenge, dat_dir } => {
+			let challenges = node.run(node.dat_verify().challenges_for(account.clone(), None))?;
+			let info = challenges.into_iter()
+				.find(|info| info.index == challenge)
+				.ok_or_else(|| Error::Input(format!("No open challenge {} to prove", challenge)))?;
+			let hosted = node.run(node.dat_verify().archives_hosted_by(account, None))?;
+			let mut proofs = Vec::new();
+			for (archive, leaves) in pallet_dat_verify::Module::<NodeRuntime>::challenged_archives(&info.chunks) {
+				// archives unregistered since the challenge need no proof.
+				let registered = node.run(node.dat_verify().archive_info(archive, None))?;
+				let proof = match registered {
+					Some(registered) if hosted.contains(&archive) => {
+						let feed = Feed::new(dat_dir.join(archive_key(&archive)), archive);
+						feed.prove(&leaves, registered.tree_length)?
+					},
+					_ => Proof::default(),
+				};
+				proofs.push(proof);
+			}
+			let call = SubmitProof { proofs }.call::<NodeRuntime>(challenge);
+			node.submit(&signer, Call::DatVerify(call))
+		},
+		DatdotAction::Status => {
+			println!("Account {}", account.to_ss58check());
+			if let Some(capacity) = node.run(node.dat_verify().capacity_of(account.clone(), None))? {
+				println!("Using {} of {} declared bytes", capacity.used, capacity.declared);
+			}
+			let archives = node.run(node.dat_verify().archives_hosted_by(account.clone(), None))?;
+			println!("Hosting {} archive(s)", archives.len());
+			for archive in archives {
+				match node.run(node.dat_verify().archive_info(archive, None))? {
+					Some(info) => println!("  {}", describe_archive(&archive, &info)),
+					None => println!("  {}, no longer registered", archive_key(&archive)),
+				}
+			}
+			let challenges = node.run(node.dat_verify().challenges_for(account, None))?;
+			println!("{} open challenge(s)", challenges.len());
+			for info in challenges {
+				println!("  {}", describe_challenge(&info));
+			}
+			Ok(())
+		},
+		DatdotAction::Types => Ok(()),
+	}
+}
+
+/// Register `drive` at its current length in one extrinsic, or update the registrations of
+/// its feeds that grew since, one extrinsic per feed.
+fn publish_drive(node: &mut Node, signer: &sr25519::Pair, drive: Drive) -> Result<()> {
+	let mut registered_lengths = Vec::new();
+	for feed in [drive.metadata(), drive.content()].iter() {
+		let registered = node.run(node.dat_verify().archive_info(feed.key(), None))?;
+		registered_lengths.push(registered.map(|info| info.tree_length));
+	}
+	let (metadata_length, content_length) = (drive.metadata().length()?, drive.content().length()?);
+	let calls = match (registered_lengths[0], registered_lengths[1]) {
+		(None, None) => {
+			vec![drive.register_drive(metadata_length, content_length)?.call::<NodeRuntime>()]
+		},
+		(Some(registered_metadata), Some(registered_content)) => {
+			let mut calls = Vec::new();
+			for (feed, length, registered) in vec![
+				(drive.metadata(), metadata_length, registered_metadata),
+				(drive.content(), content_length, registered_content),
+			] {
+				if length != registered {
+					calls.push(feed.register_data(length, Some(registered))?.call::<NodeRuntime>());
+				}
+			}
+			calls
+		},
+		_ => return Err(Error::Input("Only one feed of the drive is registered".into())),
+	};
+	if calls.is_empty() {
+		println!("{} is already registered", archive_key(&drive.metadata().key()));
+		return Ok(());
+	}
+	node.submit_all(signer, calls.into_iter().map(Call::DatVerify).collect())
+}
+
+/// The key signing the extrinsics: the `--suri` one, or the first seeder key of the keystore.
+fn signer(cmd: &DatdotCmd) -> Result<sr25519::Pair> {
+	match (&cmd.suri, &cmd.keystore_path) {
+		(Some(suri), _) => sr25519::Pair::from_string(suri, None)
+			.map_err(|_| Error::Input("Invalid secret URI".into())),
+		(None, Some(path)) => {
+			let password = cmd.password.clone().map(Into::into);
+			let keystore = sc_keystore::Store::open(path.clone(), password)
+				.map_err(|e| Error::Other(format!("Cannot open the keystore: {}", e)))?;
+			let keystore = keystore.read();
+			let public = keystore.public_keys_by_type::<sr25519::Public>(KEY_TYPE)
+				.map_err(|e| Error::Other(format!("Cannot read the keystore: {}", e)))?
+				.into_iter()
+				.next()
+				.ok_or_else(|| Error::Input("No `datv` seeder key in the keystore".into()))?;
+			keystore.key_pair_by_type::<sr25519::Pair>(&public, KEY_TYPE)
+				.map_err(|e| Error::Other(format!("Cannot read the seeder key: {}", e)))
+		},
+		(None, None) => Err(Error::Input("Either `--suri` or `--keystore-path` is required".into())),
+	}
+}
+
+fn account_of(signer: &sr25519::Pair) -> AccountId {
+	MultiSigner::from(signer.public()).into_account()
+}
+
+/// An RPC connection to a node.
+struct Node {
+	runtime: Runtime,
+	channel: RpcChannel,
+}
+
+impl Node {
+	fn connect(url: &str) -> Result<Self> {
+		let mut runtime = Runtime::new()?;
+		let channel = runtime.block_on(http::connect::<RpcChannel>(url)).map_err(rpc_error)?;
+		Ok(Node { runtime, channel })
+	}
+
+	/// Wait for the result of an RPC call.
+	fn run<T, F>(&mut self, call: F) -> Result<T> where
+		F: Future<Item = T, Error = RpcError> + Send + 'static,
+		T: Send + 'static,
+	{
+		self.runtime.block_on(call).map_err(rpc_error)
+	}
+
+	fn dat_verify(&self) -> DatVerifyClient<Hash, AccountId, BlockNumber> {
+		self.channel.clone().into()
+	}
+
+	/// Sign `call` with `signer`, for the chain and runtime of the node, and submit it.
+	fn submit(&mut self, signer: &sr25519::Pair, call: Call) -> Result<()> {
+		self.submit_all(signer, vec![call])
+	}
+
+	/// Sign the `calls` with `signer` under consecutive nonces, and submit them in order.
+	fn submit_all(&mut self, signer: &sr25519::Pair, calls: Vec<Call>) -> Result<()> {
+		let account = account_of(signer);
+		let system = SystemClient::<AccountId, Index>::from(self.channel.clone());
+		let nonce = self.run(system.nonce(account))?;
+		let chain = ChainClient::<BlockNumber, Hash, Header, SignedBlock<Block>>::from(
+			self.channel.clone(),
+		);
+		let genesis = Some(ListOrValue::Value(NumberOrHex::Number(0)));
+		let genesis_hash = match self.run(chain.block_hash(genesis))? {
+			ListOrValue::Value(Some(hash)) => hash,
+			_ => return Err(Error::Other("The node has no genesis block".into())),
+		};
+		let state = StateClient::<Hash>::from(self.channel.clone());
+		let version = self.run(state.runtime_version(None))?;
+		let author = AuthorClient::<Hash, Hash>::from(self.channel.clone());
+		for (call, nonce) in calls.into_iter().zip(nonce..) {
+			let extrinsic = sign(call, signer, nonce, version.spec_version, genesis_hash);
+			let hash = self.run(author.submit_extrinsic(extrinsic.encode().into()))?;
+			println!("Submitted extrinsic {:?}", hash);
+		}
+		Ok(())
+	}
+}
+
+/// `call` signed by `signer` with `nonce`, valid forever on the chain of `genesis_hash` while
+/// it runs `spec_version`.
+fn sign(
+	call: Call,
+	signer: &sr25519::Pair,
+	nonce: Index,
+	spec_version: u32,
+	genesis_hash: Hash,
+) -> UncheckedExtrinsic {
+	let extra: SignedExtra = (
+		frame_system::CheckVersion::new(),
+		frame_system::CheckGenesis::new(),
+		frame_system::CheckEra::from(Era::Immortal),
+		frame_system::CheckNonce::from(nonce),
+		frame_system::CheckWeight::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::from(0),
+		pallet_dat_verify::CheckProofLimits::new(),
+	);
+	let payload = SignedPayload::from_raw(
+		call,
+		extra,
+		(spec_version, genesis_hash, genesis_hash, (), (), (), ()),
+	);
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+	UncheckedExtrinsic::new_signed(call, account_of(signer).into(), signature.into(), extra)
+}
+
+fn rpc_error(error: RpcError) -> Error {
+	Error::Other(format!("RPC call failed: {}", error))
+}
+
+fn archive_key(archive: &Public) -> String {
+	HexDisplay::from(&archive.0).to_string()
+}
+
+fn describe_archive(archive: &Public, info: &ArchiveInfo<AccountId>) -> String {
+	format!(
+		"{}: index {}, {} chunks ({} bytes), version {}, published by {}, {} hoster(s)",
+		archive_key(archive),
+		info.index,
+		info.tree_length,
+		info.tree_size,
+		info.tree_version,
+		info.publisher.to_ss58check(),
+		info.hosters.len(),
+	)
+}
+
+fn describe_challenge(info: &ChallengeInfo<BlockNumber>) -> String {
+	let archives: Vec<String> = pallet_dat_verify::Module::<NodeRuntime>::challenged_archives(&info.chunks)
+		.into_iter()
+		.map(|(archive, leaves)| {
+			let chunks: Vec<String> = leaves.iter().map(|leaf| (leaf / 2).to_string()).collect();
+			format!("chunks {} of {}", chunks.join(", "), archive_key(&archive))
+		})
+		.collect();
+	format!(
+		"challenge {}: {}, failing after block {}",
+		info.index,
+		archives.join("; "),
+		info.deadline,
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+	use sp_runtime::traits::Verify;
+	use node_primitives::Signature;
+	use pallet_indices::address::Address;
+
+	#[test]
+	fn signs_for_the_signing_account() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let call = Call::DatVerify(pallet_dat_verify::Call::unregister_seeder());
+		let genesis_hash = H256::repeat_byte(1);
+		let extrinsic = sign(call.clone(), &alice, 3, 7, genesis_hash);
+
+		let (address, signature, extra) = extrinsic.signature.expect("the extrinsic is signed");
+		assert_eq!(address, Address::Id(account_of(&alice)));
+		let payload = SignedPayload::from_raw(call, extra, (7, genesis_hash, genesis_hash, (), (), (), ()));
+		assert!(payload.using_encoded(|payload| {
+			Signature::verify(&signature, payload, &account_of(&alice))
+		}));
+	}
+
+	#[test]
+	fn describes_challenges_by_chunk() {
+		let info = ChallengeInfo {
+			index: 3,
+			chunks: vec![
+				(Public::from_raw([1; 32]), 4),
+				(Public::from_raw([2; 32]), 0),
+				(Public::from_raw([1; 32]), 10),
+			],
+			deadline: 42,
+		};
+		assert_eq!(
+			describe_challenge(&info),
+			format!(
+				"challenge 3: chunks 2, 5 of {}; chunks 0 of {}, failing after block 42",
+				"01".repeat(32),
+				"02".repeat(32),
+			),
+		);
+	}
+}
@@ -16,7 +16,9 @@
 
 //! Implementation of the transaction factory trait, which enables
 //! using the cli to manufacture transactions and distribute them
-//! to accounts.
+//! to accounts, or to publish, seed and prove synthetic dat archives.
+
+use std::path::PathBuf;
 
 use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
@@ -25,21 +27,28 @@ use codec::{Encode, Decode};
 use sp_keyring::sr25519::Keyring;
 use node_runtime::{
 	Call, CheckedExtrinsic, UncheckedExtrinsic, SignedExtra, BalancesCall, ExistentialDeposit,
-	MinimumPeriod
+	MinimumPeriod, Runtime,
 };
 use node_primitives::Signature;
-use sp_core::{sr25519, crypto::Pair};
+use sp_core::{ed25519, sr25519, crypto::Pair};
 use sp_runtime::{
 	generic::Era, traits::{Block as BlockT, Header as HeaderT, SignedExtension, Verify, IdentifyAccount}
 };
-use node_transaction_factory::RuntimeAdapter;
+use node_transaction_factory::{DatdotCall, RuntimeAdapter};
 use node_transaction_factory::modes::Mode;
+use pallet_dat_verify::SeedRequest;
+use dat_proof_builder::{Feed, SubmitProof};
 use sp_inherents::InherentData;
 use sp_timestamp;
 use sp_finality_tracker;
 
 type AccountPublic = <Signature as Verify>::Signer;
 
+/// The number of chunks of every synthetic feed of the `Datdot` mode.
+const FEED_CHUNKS: usize = 64;
+/// The size of every chunk of the synthetic feeds, in bytes.
+const CHUNK_SIZE: usize = 1024;
+
 pub struct FactoryState<N> {
 	block_no: N,
 
@@ -49,11 +58,23 @@ pub struct FactoryState<N> {
 	round: u32,
 	block_in_round: u32,
 	num: u32,
+
+	/// The directory the synthetic feeds of the `Datdot` mode are written to.
+	feed_dir: PathBuf,
+	/// The synthetic feeds registered so far.
+	feeds: Vec<Feed>,
+	/// The number of extrinsics the master account made in the `Datdot` mode.
+	dat_verify_extrinsics: u32,
 }
 
 type Number = <<node_primitives::Block as BlockT>::Header as HeaderT>::Number;
 
 impl<Number> FactoryState<Number> {
+	/// Write the synthetic feeds of the `Datdot` mode to `dir`.
+	pub fn set_feed_dir(&mut self, dir: PathBuf) {
+		self.feed_dir = dir;
+	}
+
 	fn build_extra(index: node_primitives::Index, phase: u64) -> node_runtime::SignedExtra {
 		(
 			frame_system::CheckVersion::new(),
@@ -61,7 +82,8 @@ impl<Number> FactoryState<Number> {
 			frame_system::CheckEra::from(Era::mortal(256, phase)),
 			frame_system::CheckNonce::from(index),
 			frame_system::CheckWeight::new(),
-			pallet_transaction_payment::ChargeTransactionPayment::from(0)
+			pallet_transaction_payment::ChargeTransactionPayment::from(0),
+			pallet_dat_verify::CheckProofLimits::new(),
 		)
 	}
 }
@@ -89,6 +111,9 @@ impl RuntimeAdapter for FactoryState<Number> {
 			block_in_round: 0,
 			block_no: 0,
 			start_number: 0,
+			feed_dir: std::env::temp_dir().join("datdot-factory"),
+			feeds: Vec::new(),
+			dat_verify_extrinsics: 0,
 		}
 	}
 
@@ -152,7 +177,55 @@ impl RuntimeAdapter for FactoryState<Number> {
 					(*amount).into()
 				)
 			)
-		}, key, (version, genesis_hash.clone(), prior_block_hash.clone(), (), (), ()))
+		}, key, (version, genesis_hash.clone(), prior_block_hash.clone(), (), (), (), ()))
+	}
+
+	fn dat_verify_extrinsic(
+		&mut self,
+		call: DatdotCall<Self::Number>,
+		version: u32,
+		genesis_hash: &<Self::Block as BlockT>::Hash,
+		prior_block_hash: &<Self::Block as BlockT>::Hash,
+	) -> <Self::Block as BlockT>::Extrinsic {
+		let call = match call {
+			DatdotCall::RegisterData(seed) => {
+				let feed = synthetic_feed(self.feed_dir.join(seed.to_string()), seed);
+				let call = feed.register_data(FEED_CHUNKS as u64, None)
+					.expect("The synthetic feed is signed at its length")
+					.call::<Runtime>();
+				self.feeds.push(feed);
+				Call::DatVerify(call)
+			},
+			DatdotCall::OpenDeal(seed) => {
+				// the deal lasts the whole run, so the archive stays below its replication target,
+				// at the least price a deal can pay.
+				let duration = 2 * self.num + 2;
+				let archive = self.feeds[seed as usize].key();
+				Call::DatDeals(pallet_dat_deals::Call::open_deal(archive, duration, 1, ExistentialDeposit::get()))
+			},
+			DatdotCall::RegisterSeeder(archives) => Call::DatVerify(
+				pallet_dat_verify::Call::register_seeder(SeedRequest::Archives(archives)),
+			),
+			DatdotCall::SubmitProof(challenge) => {
+				let archives = pallet_dat_verify::Module::<Runtime>::challenged_archives(&challenge.chunks);
+				let proofs = archives.into_iter().map(|(archive, leaves)| self.feeds.iter()
+					.find(|feed| feed.key() == archive)
+					.expect("The master account only seeds the synthetic feeds")
+					.prove(&leaves, FEED_CHUNKS as u64)
+					.expect("Every chunk of the synthetic feeds is signed")
+				).collect();
+				Call::DatVerify(SubmitProof { proofs }.call::<Runtime>(challenge.index))
+			},
+		};
+		let sender = Self::master_account_id();
+		let index = self.extract_index(&sender, prior_block_hash);
+		let phase = self.extract_phase(*prior_block_hash);
+		self.dat_verify_extrinsics += 1;
+		let additional_signed = (version, genesis_hash.clone(), prior_block_hash.clone(), (), (), (), ());
+		sign::<Self>(CheckedExtrinsic {
+			signed: Some((sender, Self::build_extra(index, phase))),
+			function: call,
+		}, &Self::master_account_secret(), additional_signed)
 	}
 
 	fn inherent_extrinsics(&self) -> InherentData {
@@ -200,6 +273,9 @@ impl RuntimeAdapter for FactoryState<Number> {
 		// without a preceding purge of the database.
 		if self.mode == Mode::MasterToN || self.mode == Mode::MasterTo1 {
 			self.block_no() as Self::Index
+		} else if self.mode == Mode::Datdot {
+			// the master account makes every extrinsic of the mode, several in a block.
+			self.dat_verify_extrinsics as Self::Index
 		} else {
 			match self.round() {
 				0 =>
@@ -234,6 +310,17 @@ fn gen_seed_bytes(seed: u32) -> [u8; 32] {
 	seed_bytes
 }
 
+/// The synthetic feed of `seed` written to `dir`: `FEED_CHUNKS` random chunks, signed by a key
+/// generated from the seed.
+fn synthetic_feed(dir: PathBuf, seed: u32) -> Feed {
+	let mut rng: StdRng = SeedableRng::seed_from_u64(seed as u64);
+	let chunks: Vec<Vec<u8>> = (0..FEED_CHUNKS)
+		.map(|_| (0..CHUNK_SIZE).map(|_| rng.gen::<u8>()).collect())
+		.collect();
+	let pair = ed25519::Pair::from_seed(&gen_seed_bytes(seed));
+	Feed::create(dir, &pair, &chunks).expect("Failed to write the synthetic feed")
+}
+
 /// Creates an `UncheckedExtrinsic` containing the appropriate signature for
 /// a `CheckedExtrinsics`.
 fn sign<RA: RuntimeAdapter>(
@@ -265,3 +352,26 @@ fn sign<RA: RuntimeAdapter>(
 	let e = Encode::encode(&s);
 	Decode::decode(&mut &e[..]).expect("Failed to decode signed unchecked extrinsic")
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn synthetic_feeds_prove_every_chunk() {
+		let dir = tempfile::tempdir().unwrap();
+		let feed = synthetic_feed(dir.path().join("3"), 3);
+		let again = synthetic_feed(dir.path().join("again"), 3);
+		assert_eq!(feed.key(), again.key());
+		assert_ne!(feed.key(), synthetic_feed(dir.path().join("4"), 4).key());
+
+		assert_eq!(feed.length().unwrap(), FEED_CHUNKS as u64);
+		assert!(feed.register_data(FEED_CHUNKS as u64, None).is_ok());
+		let leaves: Vec<u64> = (0..FEED_CHUNKS as u64).map(|chunk| 2 * chunk).collect();
+		let proof = feed.prove(&leaves, FEED_CHUNKS as u64).unwrap();
+		for (leaf, chunk) in leaves.iter().zip(proof.chunks) {
+			assert_eq!(chunk.len(), CHUNK_SIZE);
+			assert_eq!(chunk, again.chunk(*leaf).unwrap());
+		}
+	}
+}
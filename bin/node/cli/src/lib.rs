@@ -33,6 +33,7 @@ pub mod chain_spec;
 
 #[macro_use]
 mod service;
+mod dat_store;
 #[cfg(feature = "browser")]
 mod browser;
 #[cfg(feature = "cli")]
@@ -41,6 +42,8 @@ mod cli;
 mod factory_impl;
 #[cfg(feature = "cli")]
 mod command;
+#[cfg(feature = "cli")]
+mod datdot;
 
 #[cfg(feature = "browser")]
 pub use browser::*;
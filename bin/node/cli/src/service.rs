@@ -18,7 +18,7 @@
 
 //! Service implementation. Specialized wrapper over substrate service.
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use sc_consensus_babe;
 use sc_client::{self, LongestChain};
@@ -109,7 +109,7 @@ macro_rules! new_full_start {
 /// We need to use a macro because the test suit doesn't work with an opaque service. It expects
 /// concrete types instead.
 macro_rules! new_full {
-	($config:expr, $with_startup_data: expr) => {{
+	($config:expr, $dat_dir:expr, $with_startup_data: expr) => {{
 		use futures::prelude::*;
 		use sc_network::Event;
 
@@ -134,12 +134,25 @@ macro_rules! new_full {
 
 		let (builder, mut import_setup, inherent_data_providers) = new_full_start!($config);
 
+		let offchain_storage = sc_client_api::backend::Backend::offchain_storage(&**builder.backend());
+
 		let service = builder.with_network_protocol(|_| Ok(crate::service::NodeProtocol::new()))?
 			.with_finality_proof_provider(|client, backend|
 				Ok(Arc::new(grandpa::FinalityProofProvider::new(backend, client)) as _)
 			)?
 			.build()?;
 
+		// answer the chunk requests of the dat_verify offchain worker from the local feeds.
+		if let Some(dat_dir) = $dat_dir {
+			match offchain_storage {
+				Some(db) => service.spawn_task(
+					"dat-store",
+					crate::dat_store::serve(crate::dat_store::DatStore::new(dat_dir), db),
+				),
+				None => log::warn!("No offchain storage to serve the feeds in {:?} from", dat_dir),
+			}
+		}
+
 		let (block_import, grandpa_link, babe_link) = import_setup.take()
 				.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
 
@@ -249,6 +262,9 @@ macro_rules! new_full {
 
 		Ok((service, inherent_data_providers))
 	}};
+	($config:expr, $with_startup_data: expr) => {{
+		new_full!($config, None::<std::path::PathBuf>, $with_startup_data)
+	}};
 	($config:expr) => {{
 		new_full!($config, |_, _| {})
 	}}
@@ -295,7 +311,34 @@ pub fn new_full(config: NodeConfiguration)
 	ServiceError,
 >
 {
-	new_full!(config).map(|(service, _)| service)
+	new_full_with_dat_dir(config, None)
+}
+
+/// Builds a new service for a full client, answering the dat_verify challenges of the local
+/// seeders from the hypercore feeds in `dat_dir`, if any.
+pub fn new_full_with_dat_dir(mut config: NodeConfiguration, dat_dir: Option<PathBuf>)
+-> Result<
+	Service<
+		ConcreteBlock,
+		ConcreteClient,
+		LongestChain<ConcreteBackend, ConcreteBlock>,
+		NetworkStatus<ConcreteBlock>,
+		NetworkService<ConcreteBlock, crate::service::NodeProtocol, <ConcreteBlock as BlockT>::Hash>,
+		ConcreteTransactionPool,
+		OffchainWorkers<
+			ConcreteClient,
+			<ConcreteBackend as sc_client_api::backend::Backend<Block>>::OffchainStorage,
+			ConcreteBlock,
+		>
+	>,
+	ServiceError,
+>
+{
+	// the challenges are answered by the offchain worker.
+	if dat_dir.is_some() {
+		config.offchain_worker = true;
+	}
+	new_full!(config, dat_dir, |_, _| {}).map(|(service, _)| service)
 }
 
 /// Builds a new service for a light client.
@@ -67,12 +67,14 @@ pub fn create<C, P, M, F>(
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
+	C::Api: pallet_dat_verify_rpc::DatVerifyRuntimeApi<Block, AccountId, BlockNumber>,
 	F: sc_client::light::fetcher::Fetcher<Block> + 'static,
 	P: TransactionPool + 'static,
 	M: jsonrpc_core::Metadata + Default,
 {
 	use substrate_frame_rpc_system::{FullSystem, LightSystem, SystemApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use pallet_dat_verify_rpc::{DatVerify, DatVerifyApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 
@@ -89,7 +91,10 @@ pub fn create<C, P, M, F>(
 		// more context: https://github.com/paritytech/substrate/pull/3480
 		// These RPCs should use an asynchronous caller instead.
 		io.extend_with(
-			TransactionPaymentApi::to_delegate(TransactionPayment::new(client))
+			TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
+		);
+		io.extend_with(
+			DatVerifyApi::to_delegate(DatVerify::new(client))
 		);
 	}
 	io
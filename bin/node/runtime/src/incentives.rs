@@ -1,10 +0,0 @@
-use sp_std::prelude::*;
-use support::{
-	decl_module,
-	decl_storage, 
-	decl_event, 
-    ensure,
-	StorageValue, 
-	StorageMap
-};
-use system::{ensure_signed};
\ No newline at end of file
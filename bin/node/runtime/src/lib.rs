@@ -44,8 +44,10 @@ use sp_core::OpaqueMetadata;
 use pallet_grandpa::AuthorityList as GrandpaAuthorityList;
 use pallet_grandpa::fg_primitives;
 use pallet_im_online::sr25519::{AuthorityId as ImOnlineId};
+use pallet_dat_verify::offchain::crypto::SeederId as DatSeederId;
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
+use pallet_dat_verify_rpc_runtime_api::{ArchiveInfo, CapacityInfo, ChallengeInfo, Public as DatPublic};
 use frame_system::offchain::TransactionSubmitter;
 use sp_inherents::{InherentData, CheckInherentsResult};
 
@@ -64,8 +66,9 @@ use impls::{CurrencyToVoteHandler, Author, LinearWeightToFee, TargetedFeeAdjustm
 pub mod constants;
 use constants::{time::*, currency::*};
 
-// Datdot modules.
-mod dat_verify;
+/// The custom types of the datdot modules, for front ends.
+#[cfg(feature = "std")]
+pub mod types;
 
 // Make the WASM binary available.
 #[cfg(feature = "std")]
@@ -380,6 +383,9 @@ impl pallet_sudo::Trait for Runtime {
 /// A runtime transaction submitter.
 pub type SubmitTransaction = TransactionSubmitter<ImOnlineId, Runtime, UncheckedExtrinsic>;
 
+/// A runtime transaction submitter signing with the seeder keys of dat_verify.
+pub type DatVerifySubmitTransaction = TransactionSubmitter<DatSeederId, Runtime, UncheckedExtrinsic>;
+
 parameter_types! {
 	pub const SessionDuration: BlockNumber = EPOCH_DURATION_IN_SLOTS as _;
 }
@@ -437,14 +443,102 @@ impl pallet_identity::Trait for Runtime {
 	type RegistrarOrigin = pallet_collective::EnsureMember<AccountId, CouncilCollective>;
 }
 
-impl dat_verify::Trait for Runtime {
+parameter_types! {
+	pub const SeederBond: Balance = 10 * DOLLARS;
+	pub const BondPerGigabyte: Balance = 1 * DOLLARS;
+	pub const ChallengeSlash: Perbill = Perbill::from_percent(10);
+	pub const ChallengeReward: Balance = 10 * CENTS;
+	pub const ChallengesPerBlock: u32 = 1;
+	// 45 chunks catch a seeder who lost 5% of their archives 90% of the time.
+	pub const DetectableLoss: Perbill = Perbill::from_percent(5);
+	pub const DetectionProbability: Perbill = Perbill::from_percent(90);
+	pub const MaxChunksPerChallenge: u32 = 64;
+	// the default chunk size of hypercore, and enough nodes for a proof of every chunk of a
+	// challenge in a tree of a billion chunks.
+	pub const MaxChunkSize: u32 = 64 * 1024;
+	pub const MaxProofNodes: u32 = 2 * 1024;
+	// a tree has at most one root per bit of its length.
+	pub const MaxRootChildren: u32 = 64;
+	pub const AttestorsPerChallenge: u32 = 5;
+	pub const AttestationQuorum: u32 = 3;
+	pub const AttestationReward: Balance = 2 * CENTS;
+	// about a month of a passed challenge a day reaches the most reputation, doubling rewards.
+	pub const ProofReputation: u32 = 10;
+	pub const FailureReputation: u32 = 100;
+	pub const AttestationReputation: u32 = 2;
+	pub const MaxReputation: u32 = 300;
+	pub const ReputationDecay: Perbill = Perbill::from_percent(1);
+	pub const ReputationPeriod: BlockNumber = 1 * DAYS;
+	pub const ReputationsDecayedPerBlock: u32 = 20;
+	pub const HostingsReleasedPerBlock: u32 = 10;
+}
+
+impl pallet_dat_verify::Trait for Runtime {
 	type Event = Event;
-	type Randomness = RandomnessCollectiveFlip;
-	type Hash = Hash;
+	// the epoch randomness of BABE, which block authors cannot steer challenges with.
+	type Randomness = Babe;
 	type ForceOrigin = pallet_collective::EnsureMember<AccountId, CouncilCollective>;
-	type SeederMembership = DatCollective; //todo: manipulate staking via autostake here
+	type SeederMembership = DatCollective;
 	type UserMembership = DatCollective;
-	type Proposal = Call;
+	type Currency = Balances;
+	type SeederBond = SeederBond;
+	type BondPerGigabyte = BondPerGigabyte;
+	type ChallengeSlash = ChallengeSlash;
+	type ChallengeReward = ChallengeReward;
+	type ChallengesPerBlock = ChallengesPerBlock;
+	type DetectableLoss = DetectableLoss;
+	type DetectionProbability = DetectionProbability;
+	type MaxChunksPerChallenge = MaxChunksPerChallenge;
+	type MaxChunkSize = MaxChunkSize;
+	type MaxProofNodes = MaxProofNodes;
+	type MaxRootChildren = MaxRootChildren;
+	type AttestorsPerChallenge = AttestorsPerChallenge;
+	type AttestationQuorum = AttestationQuorum;
+	type AttestationReward = AttestationReward;
+	type ProofReputation = ProofReputation;
+	type FailureReputation = FailureReputation;
+	type AttestationReputation = AttestationReputation;
+	type MaxReputation = MaxReputation;
+	type ReputationDecay = ReputationDecay;
+	type ReputationPeriod = ReputationPeriod;
+	type ReputationsDecayedPerBlock = ReputationsDecayedPerBlock;
+	type HostingsReleasedPerBlock = HostingsReleasedPerBlock;
+	type TrustedSeeders = pallet_dat_verify::ScoredPool<Runtime, pallet_scored_pool::DefaultInstance>;
+	type ReportChallengeFailure = Offences;
+	type StorageDeals = DatDeals;
+	type Call = Call;
+	type SubmitTransaction = DatVerifySubmitTransaction;
+}
+
+parameter_types! {
+	pub const DealPaymentPeriod: BlockNumber = 1 * HOURS;
+	pub const MaxDealPayoutsPerBlock: u32 = 10;
+	pub const MaxDealReplication: u32 = 10;
+}
+
+impl pallet_dat_deals::Trait for Runtime {
+	type Event = Event;
+	type PaymentPeriod = DealPaymentPeriod;
+	type MaxPayoutsPerBlock = MaxDealPayoutsPerBlock;
+	type MaxReplication = MaxDealReplication;
+}
+
+parameter_types! {
+	pub const TrustedSeedersPeriod: BlockNumber = 1 * HOURS;
+}
+
+// the trusted seeders, scored by dat_verify with their reputation, and entered into the pool
+// without a deposit.
+impl pallet_scored_pool::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type Score = pallet_dat_verify::Reputation;
+	type CandidateDeposit = pallet_dat_verify::NoCandidateDeposit;
+	type Period = TrustedSeedersPeriod;
+	type MembershipInitialized = ();
+	type MembershipChanged = ();
+	type ScoreOrigin = frame_system::EnsureRoot<AccountId>;
+	type KickOrigin = frame_system::EnsureRoot<AccountId>;
 }
 
 impl frame_system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for Runtime {
@@ -475,6 +569,7 @@ impl frame_system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for
 			frame_system::CheckNonce::<Runtime>::from(index),
 			frame_system::CheckWeight::<Runtime>::new(),
 			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
+			pallet_dat_verify::CheckProofLimits::<Runtime>::new(),
 		);
 		let raw_payload = SignedPayload::new(call, extra).map_err(|e| {
 			debug::warn!("Unable to create signed payload: {:?}", e);
@@ -533,7 +628,10 @@ construct_runtime!(
 		RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Module, Call, Storage},
 		Identity: pallet_identity::{Module, Call, Storage, Event<T>},
 		DatCollective: pallet_collective::<Instance3>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
-		DatVerify: dat_verify::{Module, Call, Storage, Event<T>},
+		DatVerify: pallet_dat_verify::{Module, Call, Storage, Event<T>, Config<T>},
+		DatDeals: pallet_dat_deals::{Module, Call, Storage, Event<T>},
+		// only dat_verify enters and scores trusted seeders, so the pool has no calls.
+		TrustedSeeders: pallet_scored_pool::{Module, Storage, Event<T>, Config<T>},
 		Recovery: pallet_recovery::{Module, Call, Storage, Event<T>},
 	}
 );
@@ -556,6 +654,7 @@ pub type SignedExtra = (
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+	pallet_dat_verify::CheckProofLimits<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -667,6 +766,28 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_dat_verify_rpc_runtime_api::DatVerifyApi<Block, AccountId, BlockNumber> for Runtime {
+		fn archives_hosted_by(account: AccountId) -> Vec<DatPublic> {
+			DatVerify::archives_hosted_by(account)
+		}
+
+		fn hosters_of(archive: DatPublic) -> Vec<AccountId> {
+			DatVerify::hosters_of(archive)
+		}
+
+		fn challenges_for(account: AccountId) -> Vec<ChallengeInfo<BlockNumber>> {
+			DatVerify::challenges_for(account)
+		}
+
+		fn archive_info(archive: DatPublic) -> Option<ArchiveInfo<AccountId>> {
+			DatVerify::archive_info(archive)
+		}
+
+		fn capacity_of(account: AccountId) -> Option<CapacityInfo> {
+			DatVerify::capacity_of(account)
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			SessionKeys::generate(seed)
@@ -706,6 +827,105 @@ mod tests {
 
 		is_submit_signed_transaction::<SubmitTransaction>();
 		is_sign_and_submit_transaction::<SubmitTransaction>();
+		is_submit_signed_transaction::<DatVerifySubmitTransaction>();
+	}
+
+	// What the author of a block picks, or can grind on, while the epoch randomness is fixed.
+	#[derive(Clone)]
+	struct Authored {
+		authority_index: u32,
+		slot_number: u64,
+		parent_hash: [u8; 32],
+		extrinsics_root: [u8; 32],
+		vrf_output: [u8; 32],
+	}
+
+	impl Default for Authored {
+		fn default() -> Self {
+			Authored {
+				authority_index: 0,
+				slot_number: 100,
+				parent_hash: [1; 32],
+				extrinsics_root: [0; 32],
+				vrf_output: [1; 32],
+			}
+		}
+	}
+
+	// The challenge seed and the collective flip randomness of block `number` of the first
+	// epoch as `authored`, and the VRF output BABE took from its digest.
+	fn seeds_of_block(number: BlockNumber, authored: Authored) -> (Hash, Hash, Option<[u8; 32]>) {
+		use sp_runtime::traits::OnInitialize;
+		use sp_consensus_babe::{BABE_ENGINE_ID, RawBabePreDigest};
+		use codec::Encode;
+
+		let storage = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		sp_io::TestExternalities::new(storage).execute_with(|| {
+			pallet_babe::Randomness::put([7; 32]);
+			let pre_digest = RawBabePreDigest::Primary {
+				authority_index: authored.authority_index,
+				slot_number: authored.slot_number,
+				vrf_output: authored.vrf_output,
+				vrf_proof: [0xff; 64],
+			};
+			let digest = generic::Digest {
+				logs: vec![generic::DigestItem::PreRuntime(BABE_ENGINE_ID, pre_digest.encode())],
+			};
+			System::initialize(
+				&number,
+				&authored.parent_hash.into(),
+				&authored.extrinsics_root.into(),
+				&digest,
+				Default::default(),
+			);
+			Babe::on_initialize(number);
+			RandomnessCollectiveFlip::on_initialize(number);
+			(
+				pallet_dat_verify::Module::<Runtime>::challenge_seed(number),
+				<RandomnessCollectiveFlip as Randomness<Hash>>::random(b"dat_verify_challenge"),
+				Babe::initialized().and_then(|vrf_output| vrf_output),
+			)
+		})
+	}
+
+	#[test]
+	fn block_authors_cannot_bias_challenges() {
+		use pallet_dat_verify::{Module as DatVerify, Draw};
+
+		let draws = |seed: &Hash| [Draw::Seeder, Draw::Chunk, Draw::Attestor].iter()
+			.map(|purpose| DatVerify::<Runtime>::draw(seed, 3, *purpose, 0))
+			.collect::<Vec<_>>();
+		let (seed, flip, vrf_output) = seeds_of_block(1, Authored::default());
+		assert_eq!(vrf_output, Some([1; 32]));
+
+		// every input an author controls, changed one at a time.
+		let base = Authored::default();
+		let authored = vec![
+			Authored { authority_index: 1, ..base.clone() },
+			Authored { slot_number: 101, ..base.clone() },
+			Authored { parent_hash: [2; 32], ..base.clone() },
+			Authored { extrinsics_root: [2; 32], ..base.clone() },
+			Authored { vrf_output: [2; 32], ..base.clone() },
+		];
+		for other in authored {
+			let (other_seed, other_flip, other_vrf_output) = seeds_of_block(1, other.clone());
+			// the block takes what its author picked ...
+			assert_eq!(other_vrf_output, Some(other.vrf_output));
+			if other.parent_hash != base.parent_hash {
+				assert_ne!(flip, other_flip);
+			}
+			// ... but the seeder, the chunks and the attestors of a challenge stay the same.
+			assert_eq!(draws(&seed), draws(&other_seed));
+		}
+	}
+
+	#[test]
+	fn challenge_seeds_change_every_block() {
+		// the epoch randomness is the same for both blocks, a draw which issued nothing at one
+		// is not drawn again at the next.
+		let (seed, _, _) = seeds_of_block(1, Authored::default());
+		let (next_seed, _, _) = seeds_of_block(2, Authored::default());
+		assert_ne!(seed, next_seed);
 	}
 
 	#[test]
@@ -716,9 +936,16 @@ mod tests {
 				<AllModules as WeighBlock<BlockNumber>>::on_initialize(b) +
 				<AllModules as WeighBlock<BlockNumber>>::on_finalize(b);
 
+			// dat_verify issues and expires up to `ChallengesPerBlock` challenges, decays up to
+			// `ReputationsDecayedPerBlock` reputations and releases up to
+			// `HostingsReleasedPerBlock` hostings of evicted seeders. dat_deals pays out up to
+			// `MaxDealPayoutsPerBlock` deals, each to up to `MaxDealReplication` hosters.
 			assert_eq!(
 				block_hooks_weight,
-				0,
+				2 * pallet_dat_verify::CHALLENGE_HOOK_WEIGHT * ChallengesPerBlock::get() +
+					pallet_dat_verify::REPUTATION_DECAY_WEIGHT * ReputationsDecayedPerBlock::get() +
+					pallet_dat_verify::HOSTING_RELEASE_WEIGHT * HostingsReleasedPerBlock::get() +
+					pallet_dat_deals::PAYOUT_WEIGHT * MaxDealPayoutsPerBlock::get() * MaxDealReplication::get(),
 				"This test might fail simply because the value being compared to has increased to a \
 				module declaring a new weight for a hook or call. In this case update the test and \
 				happily move on.",
@@ -0,0 +1,100 @@
+//! The custom types of the datdot modules, as front ends register them with Polkadot.js.
+//!
+//! `types.json` next to the runtime is generated from these with `substrate datdot types`, and
+//! the tests fail while it is stale, or while the metadata uses a type it does not define.
+
+use pallet_dat_verify::types::{self, Definition};
+
+/// The definitions of the custom types of the datdot modules.
+pub fn definitions() -> Vec<(&'static str, Definition)> {
+	let mut definitions = types::definitions();
+	definitions.extend(pallet_dat_deals::type_definitions());
+	definitions
+}
+
+/// The contents of `types.json`.
+pub fn types_json() -> String {
+	types::to_json(&definitions())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::Value;
+	use crate::Runtime;
+
+	// the types Polkadot.js knows, and the generic parameter it strips.
+	const KNOWN_TYPES: &[&str] = &[
+		"T", "bool", "u8", "u32", "u64", "Vec", "Option", "H256", "H512", "Signature",
+		"AccountId", "Balance", "BalanceOf", "BlockNumber",
+	];
+
+	// the modules `types.json` defines the types of.
+	const DATDOT_MODULES: &[&str] = &["DatVerify", "DatDeals"];
+
+	#[test]
+	fn types_json_is_up_to_date() {
+		assert!(
+			types_json() == include_str!("../types.json"),
+			"types.json is stale, regenerate it with `substrate datdot types > bin/node/runtime/types.json`",
+		);
+	}
+
+	#[test]
+	fn types_json_defines_the_types_in_the_metadata() {
+		let metadata = serde_json::to_value(Runtime::metadata()).unwrap();
+		let defined = definitions().into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+		let modules = metadata[1]["V11"]["modules"].as_array().unwrap().iter()
+			.filter(|module| DATDOT_MODULES.iter().any(|name| module["name"] == *name))
+			.collect::<Vec<_>>();
+		assert_eq!(modules.len(), DATDOT_MODULES.len());
+
+		let mut used = Vec::new();
+		for module in modules {
+			metadata_types(&module["calls"], &mut used);
+			metadata_types(&module["event"], &mut used);
+			metadata_types(&module["storage"], &mut used);
+		}
+		assert!(used.contains(&"SeedRequest".to_string()));
+		for name in used {
+			assert!(
+				defined.contains(&name.as_str()) || KNOWN_TYPES.contains(&name.as_str()),
+				"types.json does not define {}",
+				name,
+			);
+		}
+	}
+
+	// the names making up the types of call arguments, event arguments and storage entries.
+	fn metadata_types(value: &Value, names: &mut Vec<String>) {
+		match value {
+			Value::Array(values) => values.iter().for_each(|value| metadata_types(value, names)),
+			Value::Object(entries) => for (key, value) in entries {
+				match (key.as_str(), value) {
+					("ty", Value::String(ty)) |
+					("Plain", Value::String(ty)) |
+					("key", Value::String(ty)) |
+					("key1", Value::String(ty)) |
+					("key2", Value::String(ty)) |
+					("value", Value::String(ty)) => type_names(ty, names),
+					// event arguments are bare types, call arguments have a `ty`.
+					("arguments", Value::Array(arguments)) => for argument in arguments {
+						match argument.as_str() {
+							Some(ty) => type_names(ty, names),
+							None => metadata_types(argument, names),
+						}
+					},
+					("documentation", _) | ("default", _) | ("name", _) => (),
+					_ => metadata_types(value, names),
+				}
+			},
+			_ => (),
+		}
+	}
+
+	fn type_names(ty: &str, names: &mut Vec<String>) {
+		ty.split(|c: char| !c.is_alphanumeric() && c != '_')
+			.filter(|name| !name.is_empty() && !name.starts_with(|c: char| c.is_ascii_digit()))
+			.for_each(|name| names.push(name.to_string()));
+	}
+}
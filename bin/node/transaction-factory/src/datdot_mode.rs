@@ -0,0 +1,112 @@
+/// This module implements the `Datdot` mode:
+///
+/// Manufacture the storage-proof workload of dat_verify, all from the
+/// master account. `num` synthetic feeds are registered, one per block,
+/// each with a storage deal so they are the archives the master account
+/// is assigned when it seeds `num` archives next. For the following
+/// `num` blocks it answers every challenge it has open with a valid proof.
+///
+///   register_data(feed 0), open_deal(feed 0)
+///   register_data(feed 1), open_deal(feed 1)
+///   ... x `num`
+///
+///   register_seeder(`num` archives)
+///
+///   submit_proof(open challenges)
+///   ... x `num`
+
+use std::sync::Arc;
+
+use codec::Codec;
+use log::info;
+use sc_client::Client;
+use sp_block_builder::BlockBuilder;
+use sp_api::{ConstructRuntimeApi, ProvideRuntimeApi};
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::{Block as BlockT, One};
+use pallet_dat_verify_rpc_runtime_api::{ChallengeInfo, DatVerifyApi};
+
+use crate::{RuntimeAdapter, create_block_with_extrinsics};
+
+/// A dat_verify call the master account makes in the `Datdot` mode.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DatdotCall<Number> {
+	/// Register the synthetic feed generated from this seed.
+	RegisterData(Number),
+	/// Open a deal asking for one hoster of the synthetic feed of this seed.
+	OpenDeal(Number),
+	/// Seed this many of the registered archives.
+	RegisterSeeder(Number),
+	/// Prove the chunks of an open challenge.
+	SubmitProof(ChallengeInfo<Number>),
+}
+
+pub fn next<RA, Backend, Exec, Block, RtApi>(
+	factory_state: &mut RA,
+	client: &Arc<Client<Backend, Exec, Block, RtApi>>,
+	version: u32,
+	genesis_hash: <RA::Block as BlockT>::Hash,
+	prior_block_hash: <RA::Block as BlockT>::Hash,
+	prior_block_id: BlockId<Block>,
+) -> Option<Block>
+where
+	Block: BlockT,
+	Exec: sc_client::CallExecutor<Block, Backend = Backend> + Send + Sync + Clone,
+	Backend: sc_client_api::backend::Backend<Block> + Send,
+	Client<Backend, Exec, Block, RtApi>: ProvideRuntimeApi<Block>,
+	<Client<Backend, Exec, Block, RtApi> as ProvideRuntimeApi<Block>>::Api:
+		BlockBuilder<Block, Error = sp_blockchain::Error> +
+		DatVerifyApi<Block, RA::AccountId, RA::Number> +
+		sp_api::ApiExt<Block, StateBackend = Backend::State>,
+	RtApi: ConstructRuntimeApi<Block, Client<Backend, Exec, Block, RtApi>> + Send + Sync,
+	RA: RuntimeAdapter,
+	RA::AccountId: Codec,
+	RA::Number: Codec,
+{
+	if factory_state.block_no() > factory_state.num() + factory_state.num() {
+		return None;
+	}
+
+	let calls = if factory_state.block_no() < factory_state.num() {
+		vec![
+			DatdotCall::RegisterData(factory_state.block_no()),
+			DatdotCall::OpenDeal(factory_state.block_no()),
+		]
+	} else if factory_state.block_no() == factory_state.num() {
+		vec![DatdotCall::RegisterSeeder(factory_state.num())]
+	} else {
+		client.runtime_api().challenges_for(&prior_block_id, RA::master_account_id())
+			.expect("Failed to query the open challenges")
+			.into_iter()
+			.map(DatdotCall::SubmitProof)
+			.collect()
+	};
+
+	let count = calls.len();
+	let extrinsics = calls.into_iter()
+		.map(|call| factory_state.dat_verify_extrinsic(
+			call,
+			version,
+			&genesis_hash,
+			&prior_block_hash,
+		))
+		.collect();
+
+	let inherents = RA::inherent_extrinsics(&factory_state);
+	let inherents = client.runtime_api().inherent_extrinsics(&prior_block_id, inherents)
+		.expect("Failed to create inherent extrinsics");
+
+	let block = create_block_with_extrinsics::<RA, _, _, _, _>(&client, extrinsics, inherents);
+
+	factory_state.set_block_no(factory_state.block_no() + RA::Number::one());
+
+	info!(
+		"Created block {} with hash {}. Submitting {} dat_verify extrinsic(s) from {}.",
+		factory_state.block_no(),
+		prior_block_hash,
+		count,
+		RA::master_account_id(),
+	);
+
+	Some(block)
+}
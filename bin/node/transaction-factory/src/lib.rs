@@ -15,7 +15,8 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Simple transaction factory which distributes tokens from a master
-//! account to a specified number of newly created accounts.
+//! account to a specified number of newly created accounts, or makes
+//! the master account publish, seed and prove dat archives.
 //!
 //! The factory currently only works on an empty database!
 
@@ -34,15 +35,18 @@ use sp_consensus::{
 	SelectChain
 };
 use sp_consensus::block_import::BlockImport;
-use codec::{Decode, Encode};
+use codec::{Codec, Decode, Encode};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{
 	Block as BlockT, Header as HeaderT, SimpleArithmetic, One, Zero,
 };
+use pallet_dat_verify_rpc_runtime_api::DatVerifyApi;
 pub use crate::modes::Mode;
+pub use crate::datdot_mode::DatdotCall;
 
 pub mod modes;
 mod complex_mode;
+mod datdot_mode;
 mod simple_modes;
 
 pub trait RuntimeAdapter {
@@ -79,6 +83,15 @@ pub trait RuntimeAdapter {
 		prior_block_hash: &<Self::Block as BlockT>::Hash,
 	) -> <Self::Block as BlockT>::Extrinsic;
 
+	/// The master account making `call`, see the `Datdot` mode.
+	fn dat_verify_extrinsic(
+		&mut self,
+		call: DatdotCall<Self::Number>,
+		version: u32,
+		genesis_hash: &<Self::Block as BlockT>::Hash,
+		prior_block_hash: &<Self::Block as BlockT>::Hash,
+	) -> <Self::Block as BlockT>::Extrinsic;
+
 	fn inherent_extrinsics(&self) -> InherentData;
 
 	fn minimum_balance() -> Self::Balance;
@@ -104,10 +117,13 @@ where
 	Client<Backend, Exec, Block, RtApi>: ProvideRuntimeApi<Block>,
 	<Client<Backend, Exec, Block, RtApi> as ProvideRuntimeApi<Block>>::Api:
 		BlockBuilder<Block, Error = sp_blockchain::Error> +
+		DatVerifyApi<Block, RA::AccountId, RA::Number> +
 		ApiExt<Block, StateBackend = Backend::State>,
 	RtApi: ConstructRuntimeApi<Block, Client<Backend, Exec, Block, RtApi>> + Send + Sync,
 	Sc: SelectChain<Block>,
 	RA: RuntimeAdapter<Block = Block>,
+	RA::AccountId: Codec,
+	RA::Number: Codec,
 	Block::Hash: From<sp_core::H256>,
 {
 	if *factory_state.mode() != Mode::MasterToNToM && factory_state.rounds() > RA::Number::one() {
@@ -132,6 +148,14 @@ where
 			best_hash.into(),
 			best_block_id,
 		),
+		Mode::Datdot => datdot_mode::next::<RA, _, _, _, _>(
+			&mut factory_state,
+			&client,
+			version,
+			genesis_hash,
+			best_hash.into(),
+			best_block_id,
+		),
 		_ => simple_modes::next::<RA, _, _, _, _>(
 			&mut factory_state,
 			&client,
@@ -157,6 +181,26 @@ pub fn create_block<RA, Backend, Exec, Block, RtApi>(
 	transfer: <RA::Block as BlockT>::Extrinsic,
 	inherent_extrinsics: Vec<<Block as BlockT>::Extrinsic>,
 ) -> Block
+where
+	Block: BlockT,
+	Exec: sc_client::CallExecutor<Block, Backend = Backend> + Send + Sync + Clone,
+	Backend: sc_client_api::backend::Backend<Block> + Send,
+	Client<Backend, Exec, Block, RtApi>: ProvideRuntimeApi<Block>,
+	RtApi: ConstructRuntimeApi<Block, Client<Backend, Exec, Block, RtApi>> + Send + Sync,
+	<Client<Backend, Exec, Block, RtApi> as ProvideRuntimeApi<Block>>::Api:
+		BlockBuilder<Block, Error = sp_blockchain::Error> +
+		ApiExt<Block, StateBackend = Backend::State>,
+	RA: RuntimeAdapter,
+{
+	create_block_with_extrinsics::<RA, _, _, _, _>(client, vec![transfer], inherent_extrinsics)
+}
+
+/// Create a baked block from any number of extrinsics and the timestamp inherent.
+pub fn create_block_with_extrinsics<RA, Backend, Exec, Block, RtApi>(
+	client: &Arc<Client<Backend, Exec, Block, RtApi>>,
+	extrinsics: Vec<<RA::Block as BlockT>::Extrinsic>,
+	inherent_extrinsics: Vec<<Block as BlockT>::Extrinsic>,
+) -> Block
 where
 	Block: BlockT,
 	Exec: sc_client::CallExecutor<Block, Backend = Backend> + Send + Sync + Clone,
@@ -169,10 +213,12 @@ where
 	RA: RuntimeAdapter,
 {
 	let mut block = client.new_block(Default::default()).expect("Failed to create new block");
-	block.push(
-		Decode::decode(&mut &transfer.encode()[..])
-			.expect("Failed to decode transfer extrinsic")
-	).expect("Failed to push transfer extrinsic into block");
+	for extrinsic in extrinsics {
+		block.push(
+			Decode::decode(&mut &extrinsic.encode()[..])
+				.expect("Failed to decode extrinsic")
+		).expect("Failed to push extrinsic into block");
+	}
 
 	for inherent in inherent_extrinsics {
 		block.push(inherent).expect("Failed ...");
@@ -15,7 +15,7 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 //! The transaction factory can operate in different modes. See
-//! the `simple_mode` and `complex_mode` modules for details.
+//! the `simple_mode`, `complex_mode` and `datdot_mode` modules for details.
 
 use std::str::FromStr;
 
@@ -24,7 +24,8 @@ use std::str::FromStr;
 pub enum Mode {
 	MasterToN,
 	MasterTo1,
-	MasterToNToM
+	MasterToNToM,
+	Datdot,
 }
 
 impl FromStr for Mode {
@@ -34,6 +35,7 @@ impl FromStr for Mode {
 			"MasterToN" => Ok(Mode::MasterToN),
 			"MasterTo1" => Ok(Mode::MasterTo1),
 			"MasterToNToM" => Ok(Mode::MasterToNToM),
+			"Datdot" => Ok(Mode::Datdot),
 			_ => Err(format!("Invalid mode: {}", mode)),
 		}
 	}
@@ -22,7 +22,12 @@ use structopt::StructOpt;
 
 use sc_keystore::{Store as Keystore};
 use node_cli::chain_spec::{self, AccountId};
-use sp_core::{sr25519, crypto::{Public, Ss58Codec}, traits::BareCryptoStore};
+use sp_core::{H512, ed25519, sr25519, crypto::{Public, Ss58Codec}, traits::BareCryptoStore};
+use pallet_dat_verify::RootHashPayload;
+use dat_proof_builder::Feed;
+
+/// A genesis archive: its publisher, and its key, roots and signature.
+type Archive = (AccountId, (ed25519::Public, RootHashPayload, H512));
 
 /// A utility to easily create a testnet chain spec definition with a given set
 /// of authorities and endowed accounts and/or generate random accounts.
@@ -41,6 +46,13 @@ enum ChainSpecBuilder {
 		/// Sudo account address (SS58 format).
 		#[structopt(long, short)]
 		sudo_account: String,
+		/// Directory of a hypercore feed to register at genesis, published by
+		/// the sudo account.
+		#[structopt(long)]
+		archives: Vec<PathBuf>,
+		/// Seeder account address (SS58 format), pinning every genesis archive.
+		#[structopt(long)]
+		seeders: Vec<String>,
 		/// The path where the chain spec should be saved.
 		#[structopt(long, short, default_value = "./chain_spec.json")]
 		chain_spec_path: PathBuf,
@@ -83,6 +95,8 @@ fn genesis_constructor(
 	authority_seeds: &[String],
 	endowed_accounts: &[AccountId],
 	sudo_account: &AccountId,
+	archives: &[Archive],
+	seeders: &[AccountId],
 ) -> chain_spec::GenesisConfig {
 	let authorities = authority_seeds
 		.iter()
@@ -92,11 +106,18 @@ fn genesis_constructor(
 
 	let enable_println = true;
 
+	let archive_keys = archives.iter().map(|(_, archive)| archive.0).collect::<Vec<_>>();
+	let dat_verify = chain_spec::DatVerifyConfig {
+		archives: archives.to_vec(),
+		seeders: seeders.iter().map(|seeder| (seeder.clone(), archive_keys.clone())).collect(),
+	};
+
 	chain_spec::testnet_genesis(
 		authorities,
 		sudo_account.clone(),
 		Some(endowed_accounts.to_vec()),
 		enable_println,
+		dat_verify,
 	)
 }
 
@@ -104,6 +125,8 @@ fn generate_chain_spec(
 	authority_seeds: Vec<String>,
 	endowed_accounts: Vec<String>,
 	sudo_account: String,
+	archives: Vec<PathBuf>,
+	seeders: Vec<String>,
 ) -> Result<String, String> {
 	let parse_account = |address: &String| {
 		AccountId::from_string(address)
@@ -117,10 +140,26 @@ fn generate_chain_spec(
 
 	let sudo_account = parse_account(&sudo_account)?;
 
+	let archives = archives
+		.iter()
+		.map(|dir| read_archive(dir, &sudo_account))
+		.collect::<Result<Vec<_>, String>>()?;
+
+	let seeders = seeders
+		.iter()
+		.map(parse_account)
+		.collect::<Result<Vec<_>, String>>()?;
+
 	let chain_spec = chain_spec::ChainSpec::from_genesis(
 		"Custom",
 		"custom",
-		move || genesis_constructor(&authority_seeds, &endowed_accounts, &sudo_account),
+		move || genesis_constructor(
+			&authority_seeds,
+			&endowed_accounts,
+			&sudo_account,
+			&archives,
+			&seeders,
+		),
 		vec![],
 		None,
 		None,
@@ -131,6 +170,14 @@ fn generate_chain_spec(
 	chain_spec.to_json(false).map_err(|err| err.to_string())
 }
 
+/// The feed in `dir`, registered at its full length by `publisher`.
+fn read_archive(dir: &Path, publisher: &AccountId) -> Result<Archive, String> {
+	let register = Feed::open(dir)
+		.and_then(|feed| feed.register_data(feed.length()?, None))
+		.map_err(|err| format!("Failed to read archive {}: {}", dir.display(), err))?;
+	Ok((publisher.clone(), register.merkle_root))
+}
+
 fn generate_authority_keys_and_store(
 	seeds: &[String],
 	keystore_path: &Path,
@@ -222,7 +269,7 @@ fn main() -> Result<(), String> {
 	let builder = ChainSpecBuilder::from_args();
 	let chain_spec_path = builder.chain_spec_path().to_path_buf();
 
-	let (authority_seeds, endowed_accounts, sudo_account) = match builder {
+	let (authority_seeds, endowed_accounts, sudo_account, archives, seeders) = match builder {
 		ChainSpecBuilder::Generate { authorities, endowed, keystore_path, .. } => {
 			let authorities = authorities.max(1);
 			let rand_str = || -> String {
@@ -256,10 +303,17 @@ fn main() -> Result<(), String> {
 			let sudo_account = chain_spec::get_account_id_from_seed::<sr25519::Public>(&sudo_seed)
 				.to_ss58check();
 
-			(authority_seeds, endowed_accounts, sudo_account)
+			(authority_seeds, endowed_accounts, sudo_account, vec![], vec![])
 		},
-		ChainSpecBuilder::New { authority_seeds, endowed_accounts, sudo_account, .. } => {
-			(authority_seeds, endowed_accounts, sudo_account)
+		ChainSpecBuilder::New {
+			authority_seeds,
+			endowed_accounts,
+			sudo_account,
+			archives,
+			seeders,
+			..
+		} => {
+			(authority_seeds, endowed_accounts, sudo_account, archives, seeders)
 		},
 	};
 
@@ -267,6 +321,8 @@ fn main() -> Result<(), String> {
 		authority_seeds,
 		endowed_accounts,
 		sudo_account,
+		archives,
+		seeders,
 	)?;
 
 	fs::write(chain_spec_path, json).map_err(|err| err.to_string())
@@ -23,10 +23,10 @@
 pub use pallet_timestamp;
 
 use sp_std::{result, prelude::*};
-use frame_support::{decl_storage, decl_module, traits::FindAuthor, traits::Get};
+use frame_support::{decl_storage, decl_module, traits::FindAuthor, traits::Get, traits::Randomness as RandomnessT};
 use sp_timestamp::OnTimestampSet;
 use sp_runtime::{generic::DigestItem, ConsensusEngineId, Perbill};
-use sp_runtime::traits::{IsMember, SaturatedConversion, Saturating, RandomnessBeacon};
+use sp_runtime::traits::{IsMember, SaturatedConversion, Saturating, RandomnessBeacon, Hash};
 use sp_staking::{
 	SessionIndex,
 	offence::{Offence, Kind},
@@ -196,6 +196,20 @@ impl<T: Trait> RandomnessBeacon for Module<T> {
 	}
 }
 
+impl<T: Trait> RandomnessT<<T as frame_system::Trait>::Hash> for Module<T> {
+	/// The randomness of the current epoch, hashed with `subject`.
+	///
+	/// It is fixed when the epoch starts, out of the VRF outputs of the epoch before last, so
+	/// the author of a block has no say in it. It is the same for every block of the epoch.
+	fn random(subject: &[u8]) -> T::Hash {
+		let mut subject = subject.to_vec();
+		subject.reserve(VRF_OUTPUT_LENGTH);
+		subject.extend_from_slice(&Self::randomness()[..]);
+
+		<T as frame_system::Trait>::Hashing::hash(&subject[..])
+	}
+}
+
 /// A BABE public key
 pub type BabeKey = [u8; PUBLIC_KEY_LENGTH];
 
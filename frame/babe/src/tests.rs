@@ -18,7 +18,7 @@
 
 use super::*;
 use mock::{new_test_ext, Babe, Test};
-use sp_runtime::{traits::OnFinalize, testing::{Digest, DigestItem}};
+use sp_runtime::{traits::{OnFinalize, OnInitialize}, testing::{Digest, DigestItem}};
 use pallet_session::ShouldEndSession;
 
 const EMPTY_RANDOMNESS: [u8; 32] = [
@@ -122,6 +122,35 @@ fn first_block_epoch_zero_start() {
 	})
 }
 
+#[test]
+fn randomness_is_fixed_for_the_epoch() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		Randomness::put([7; RANDOMNESS_LENGTH]);
+		let subject = b"a subject";
+		let random = <Babe as RandomnessT<_>>::random(subject);
+
+		// whatever the authors build on, and whatever their VRF outputs.
+		for block in 1..=3u64 {
+			let pre_digest = make_pre_digest(0, 100 + block, [block as u8; 32], [0xff; 64]);
+			System::initialize(
+				&block,
+				&[block as u8; 32].into(),
+				&Default::default(),
+				&pre_digest,
+				Default::default(),
+			);
+			Babe::on_initialize(block);
+			assert_eq!(<Babe as RandomnessT<_>>::random(subject), random);
+			Babe::on_finalize(block);
+			System::finalize();
+		}
+
+		assert_ne!(<Babe as RandomnessT<_>>::random(b"another subject"), random);
+		Randomness::put([8; RANDOMNESS_LENGTH]);
+		assert_ne!(<Babe as RandomnessT<_>>::random(subject), random);
+	})
+}
+
 #[test]
 fn authority_index() {
 	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
@@ -0,0 +1,311 @@
+//! # Dat Deals Module
+//!
+//! Publishers pay for the hosting of their archives with storage deals. Opening a deal moves
+//! an escrow covering a price per block for every replica, over a chosen duration and
+//! replication factor, into an account of the deal's own, out of reach of the slashes of the
+//! bonds the publisher may have reserved.
+//!
+//! Every `PaymentPeriod`, the escrow pays the price of the blocks since the last payout to as
+//! many hosters of the archive as the replication factor asks for. Payouts are scheduled by
+//! block, at most `MaxPayoutsPerBlock` of them in a block. Hosters failing a challenge are
+//! evicted in `dat_verify`, and stop being paid while they are released from their archives. Whatever is left of the escrow when
+//! the deal runs out, or when the archive is unregistered, is returned to the publisher.
+//!
+//! The replication factor and price of a deal also steer `dat_verify`, which assigns new
+//! seeders to archives with fewer hosters than their deal pays for, favouring the better paid.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{
+	decl_module,
+	decl_storage,
+	decl_event,
+	decl_error,
+	ensure,
+	StorageValue,
+	StorageMap,
+	StorageDoubleMap,
+	traits::{Currency, ExistenceRequirement, Get},
+	weights::{Weight, WeighData, SimpleDispatchInfo},
+};
+use frame_system::{self as system, ensure_signed};
+use codec::{Encode, Decode};
+use sp_runtime::{
+	RuntimeDebug,
+	ModuleId,
+	traits::{Zero, One, CheckedMul, Saturating, SaturatedConversion, AccountIdConversion},
+};
+use pallet_dat_verify::{Public, DatHostersAt, DatHostersCount, Evictions, UserRequestsMap, StorageDeals};
+#[cfg(feature = "std")]
+use pallet_dat_verify::types::{Definition, TypeDefinition};
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+type BalanceOf<T> = <<T as pallet_dat_verify::Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type DealOf<T> = Deal<<T as system::Trait>::AccountId, BalanceOf<T>, <T as system::Trait>::BlockNumber>;
+
+// the escrow of every deal is kept in a sub-account of this, one for each archive.
+const MODULE_ID: ModuleId = ModuleId(*b"deals/es");
+
+/// The module's configuration trait.
+///
+/// Deals are paid in the currency seeders bond in `dat_verify`.
+pub trait Trait: pallet_dat_verify::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The number of blocks between two payouts of a deal.
+	type PaymentPeriod: Get<Self::BlockNumber>;
+
+	/// The most deals paid out in a block, later ones are paid out in the next block with room.
+	type MaxPayoutsPerBlock: Get<u32>;
+
+	/// The highest replication factor of a deal, which is as many hosters as a payout pays.
+	type MaxReplication: Get<u32>;
+}
+
+/// The weight of paying a hoster of a deal.
+pub const PAYOUT_WEIGHT: Weight = 1_000_000;
+
+/// The weight of opening a deal: moving its escrow, and queueing its first payout.
+pub const OPEN_DEAL_WEIGHT: Weight = 2_000_000;
+
+/// The weight of `on_finalize` paying out up to the given number of deals, each to up to the
+/// given number of hosters.
+pub struct WeightForPayouts(u32, u32);
+
+impl<BlockNumber> WeighData<BlockNumber> for WeightForPayouts {
+	fn weigh_data(&self, _: BlockNumber) -> Weight {
+		self.0.saturating_mul(self.1).saturating_mul(PAYOUT_WEIGHT)
+	}
+}
+
+/// A publisher paying for the hosting of one of their archives.
+#[cfg_attr(feature = "std", derive(TypeDefinition))]
+#[derive(Decode, PartialEq, Eq, Encode, Clone, RuntimeDebug)]
+pub struct Deal<AccountId, Balance, BlockNumber> {
+	/// The publisher the escrow is paid by.
+	publisher: AccountId,
+	/// How many hosters are paid every block.
+	replication: u32,
+	/// What every paid hoster earns in a block.
+	price_per_block: Balance,
+	/// What is left of the escrow.
+	escrow: Balance,
+	/// The first block hosters are no longer paid for.
+	end: BlockNumber,
+	/// The first block hosters have not been paid for yet.
+	paid_until: BlockNumber,
+	/// The block the next payout is scheduled at.
+	next_payout: BlockNumber,
+}
+
+/// The definitions of the types the module uses, see `pallet_dat_verify::types`.
+#[cfg(feature = "std")]
+pub fn type_definitions() -> Vec<(&'static str, Definition)> {
+	vec![
+		Deal::<(), (), ()>::type_definition(),
+		// the storage holds the deal of the runtime's own types.
+		("DealOf", Definition::Alias("Deal")),
+	]
+}
+
+decl_event!(
+	pub enum Event<T> where
+		AccountId = <T as system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+	{
+		/// A publisher put up an escrow to pay for the hosting of an archive.
+		DealOpened(Public, AccountId, Balance),
+		/// The deal of an archive ended, returning what was left of its escrow.
+		DealEnded(Public, Balance),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// Only the publisher of an archive can pay for it.
+		NotPublisher,
+		/// The archive already has a deal.
+		DealExists,
+		/// A deal needs a duration, a replication factor up to `MaxReplication`, and a price
+		/// of at least the existential deposit.
+		InvalidDeal,
+		/// The escrow does not fit in a balance.
+		EscrowOverflow,
+		/// The publisher cannot afford the escrow.
+		InsufficientFunds,
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as DatDeals {
+		/// The deal paying for the hosting of each archive.
+		pub Deals get(deal): map hasher(blake2_256) Public => Option<DealOf<T>>;
+		/// Block => the archives whose deals are paid out at the end of it.
+		pub DealsDue get(deals_due): map hasher(twox_256) T::BlockNumber => Vec<Public>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Pay `price_per_block` to `replication` hosters of `archive` for the next `duration`
+		/// blocks, moving the whole escrow into the account of the deal upfront.
+		///
+		/// Only the publisher who registered the archive can open its deal. The price is at
+		/// least the existential deposit, so paying it out never leaves dust in the escrow,
+		/// unless the existential deposit is raised, see `pay_out`.
+		#[weight = SimpleDispatchInfo::FixedNormal(OPEN_DEAL_WEIGHT)]
+		fn open_deal(
+			origin,
+			archive: Public,
+			duration: T::BlockNumber,
+			replication: u32,
+			price_per_block: BalanceOf<T>
+		) {
+			let publisher = ensure_signed(origin)?;
+			ensure!(
+				<UserRequestsMap<T>>::exists(&archive) && <UserRequestsMap<T>>::get(&archive) == publisher,
+				Error::<T>::NotPublisher
+			);
+			ensure!(!<Deals<T>>::exists(&archive), Error::<T>::DealExists);
+			ensure!(
+				!duration.is_zero() &&
+					replication > 0 &&
+					replication <= T::MaxReplication::get() &&
+					!price_per_block.is_zero() &&
+					price_per_block >= T::Currency::minimum_balance(),
+				Error::<T>::InvalidDeal
+			);
+			let blocks = <BalanceOf<T>>::saturated_from(duration.saturated_into::<u64>());
+			let escrow = price_per_block.checked_mul(&replication.into())
+				.and_then(|per_block| per_block.checked_mul(&blocks))
+				.ok_or(Error::<T>::EscrowOverflow)?;
+			T::Currency::transfer(&publisher, &Self::escrow_account(&archive), escrow, ExistenceRequirement::KeepAlive)
+				.map_err(|_| Error::<T>::InsufficientFunds)?;
+
+			let now = <system::Module<T>>::block_number();
+			let end = now + duration;
+			let due = (now + T::PaymentPeriod::get().max(One::one()) - One::one()).min(end - One::one());
+			let next_payout = Self::schedule_payout(&archive, due);
+			<Deals<T>>::insert(&archive, Deal {
+				publisher: publisher.clone(),
+				replication,
+				price_per_block,
+				escrow,
+				end,
+				paid_until: now,
+				next_payout,
+			});
+			Self::deposit_event(RawEvent::DealOpened(archive, publisher, escrow));
+		}
+
+		// only the deals due this block are paid out, see `schedule_payout`.
+		#[weight = WeightForPayouts(T::MaxPayoutsPerBlock::get(), T::MaxReplication::get())]
+		fn on_finalize(n: T::BlockNumber) {
+			for archive in <DealsDue<T>>::take(n) {
+				Self::pay_out(&archive, n);
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The account the escrow of the deal of `archive` is kept in.
+	pub fn escrow_account(archive: &Public) -> T::AccountId {
+		MODULE_ID.into_sub_account(archive)
+	}
+
+	/// Queue the payout of the deal of `archive` at the first block from `block` on with room
+	/// for it, returning that block.
+	fn schedule_payout(archive: &Public, mut block: T::BlockNumber) -> T::BlockNumber {
+		let limit = T::MaxPayoutsPerBlock::get().max(1) as usize;
+		while <DealsDue<T>>::get(&block).len() >= limit {
+			block += One::one();
+		}
+		<DealsDue<T>>::mutate(&block, |due| due.push(*archive));
+		block
+	}
+
+	/// Pay the hosters of `archive` for the blocks since the last payout, and queue the next
+	/// payout or end the deal. Up to the replication factor of them are paid, the first not
+	/// pending eviction in `DatHostersAt`, which moves the last hoster into the slot of one that
+	/// leaves, so hosters are not paid in the order they started hosting.
+	fn pay_out(archive: &Public, n: T::BlockNumber) {
+		let mut deal = match <Deals<T>>::get(archive) {
+			Some(deal) => deal,
+			None => return,
+		};
+		let paid_until = (n + One::one()).min(deal.end);
+		let blocks = <BalanceOf<T>>::saturated_from(
+			paid_until.saturating_sub(deal.paid_until).saturated_into::<u64>()
+		);
+		let escrow_account = Self::escrow_account(archive);
+		let minimum_balance = T::Currency::minimum_balance();
+		let evictions = <Evictions<T>>::get();
+		let paid_hosters = (0..<DatHostersCount>::get(archive))
+			.map(|slot| <DatHostersAt<T>>::get(archive, slot))
+			.filter(|hoster| !evictions.contains(hoster))
+			.take(deal.replication as usize);
+		for hoster in paid_hosters {
+			let balance = T::Currency::free_balance(&escrow_account);
+			if balance.is_zero() {
+				break;
+			}
+			let mut payment = deal.price_per_block.saturating_mul(blocks).min(balance);
+			// the escrow account would be reaped with the rest of the escrow in it, so the rest
+			// goes to this hoster, and the deal ends.
+			if balance - payment < minimum_balance {
+				payment = balance;
+			}
+			// whatever could not be moved stays in the escrow.
+			let _ = T::Currency::transfer(&escrow_account, &hoster, payment, ExistenceRequirement::AllowDeath);
+		}
+		deal.escrow = T::Currency::free_balance(&escrow_account);
+		deal.paid_until = paid_until;
+		if paid_until >= deal.end || deal.escrow.is_zero() {
+			<Deals<T>>::insert(archive, deal);
+			Self::end_deal(archive);
+			return;
+		}
+		let due = (n + T::PaymentPeriod::get().max(One::one())).min(deal.end - One::one());
+		deal.next_payout = Self::schedule_payout(archive, due);
+		<Deals<T>>::insert(archive, deal);
+	}
+
+	/// End the deal of `archive`, returning what is left of its escrow to the publisher.
+	fn end_deal(archive: &Public) {
+		if let Some(deal) = <Deals<T>>::take(archive) {
+			// a deal ended by its last payout is no longer queued.
+			let mut due = <DealsDue<T>>::take(&deal.next_payout);
+			due.retain(|due| due != archive);
+			if !due.is_empty() {
+				<DealsDue<T>>::insert(&deal.next_payout, due);
+			}
+			let escrow_account = Self::escrow_account(archive);
+			let escrow = T::Currency::free_balance(&escrow_account);
+			let _ = T::Currency::transfer(&escrow_account, &deal.publisher, escrow, ExistenceRequirement::AllowDeath);
+			Self::deposit_event(RawEvent::DealEnded(*archive, escrow));
+		}
+	}
+}
+
+impl<T: Trait> StorageDeals for Module<T> {
+	fn replication(archive: &Public) -> u32 {
+		<Deals<T>>::get(archive).map(|deal| deal.replication).unwrap_or(0)
+	}
+
+	fn price_per_block(archive: &Public) -> u64 {
+		<Deals<T>>::get(archive).map(|deal| deal.price_per_block.saturated_into::<u64>()).unwrap_or(0)
+	}
+
+	fn on_unregister(archive: &Public) {
+		Self::end_deal(archive);
+	}
+}
@@ -0,0 +1,299 @@
+//! Test utilities
+
+use super::*;
+
+use std::cell::RefCell;
+use frame_support::{
+	impl_outer_origin, impl_outer_event, parameter_types, ord_parameter_types, StorageValue,
+	weights::Weight,
+};
+use sp_core::{H256, Pair, ed25519, sr25519};
+use sp_runtime::{
+	Perbill,
+	traits::{BlakeTwo256, IdentityLookup, ConvertInto, IdentifyAccount},
+	testing::{Header, UintAuthorityId, TestXt},
+};
+use frame_system::{EnsureSignedBy, offchain::{CreateTransaction, Signer, TransactionSubmitter}};
+use pallet_dat_verify::{
+	DatCount, DatKey, DatIndex, DatHosters, UsersStorage, UsersStorageAt, UsersStorageCount,
+	offchain::crypto::SeederId,
+};
+use crate as dat_deals;
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		pallet_balances<T>,
+		pallet_session,
+		pallet_dat_verify<T>,
+		dat_deals<T>,
+	}
+}
+
+// For testing the module, we construct most of a mock runtime. This means
+// first constructing a configuration type (`Test`) which `impl`s each of the
+// configuration traits of modules we want to use.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = ();
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+}
+
+thread_local! {
+	pub static EXISTENTIAL_DEPOSIT: RefCell<u64> = RefCell::new(1);
+}
+
+/// The existential deposit, changed with `set_existential_deposit`.
+pub struct ExistentialDeposit;
+impl Get<u64> for ExistentialDeposit {
+	fn get() -> u64 {
+		EXISTENTIAL_DEPOSIT.with(|v| *v.borrow())
+	}
+}
+
+/// Change the existential deposit, as an upgrade of the runtime might while deals are open.
+pub fn set_existential_deposit(deposit: u64) {
+	EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = deposit);
+}
+
+parameter_types! {
+	pub const CreationFee: u64 = 0;
+}
+
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type OnReapAccount = System;
+	type OnNewAccount = ();
+	type Event = TestEvent;
+	type TransferPayment = ();
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type CreationFee = CreationFee;
+}
+
+parameter_types! {
+	pub const Period: u64 = 1;
+	pub const Offset: u64 = 0;
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(33);
+}
+
+impl pallet_session::Trait for Test {
+	type Event = TestEvent;
+	type ValidatorId = u64;
+	type ValidatorIdOf = ConvertInto;
+	type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
+	type SessionManager = ();
+	type SessionHandler = pallet_session::TestSessionHandler;
+	type Keys = UintAuthorityId;
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+}
+
+impl pallet_session::historical::Trait for Test {
+	type FullIdentification = u64;
+	type FullIdentificationOf = ConvertInto;
+}
+
+/// An extrinsic type used for tests.
+pub type Extrinsic = TestXt<pallet_dat_verify::Call<Test>, ()>;
+type SubmitTransaction = TransactionSubmitter<SeederId, Test, Extrinsic>;
+
+/// A seeder key, which stands for no account as offchain workers are not run in these tests.
+#[derive(Clone)]
+pub struct SeederKey(sr25519::Public);
+
+impl From<sr25519::Public> for SeederKey {
+	fn from(public: sr25519::Public) -> Self {
+		SeederKey(public)
+	}
+}
+
+impl From<SeederKey> for sr25519::Public {
+	fn from(key: SeederKey) -> Self {
+		key.0
+	}
+}
+
+impl IdentifyAccount for SeederKey {
+	type AccountId = u64;
+
+	fn into_account(self) -> u64 {
+		0
+	}
+}
+
+impl CreateTransaction<Test, Extrinsic> for Test {
+	type Public = SeederKey;
+	type Signature = sr25519::Signature;
+
+	fn create_transaction<F: Signer<Self::Public, Self::Signature>>(
+		_call: pallet_dat_verify::Call<Test>,
+		_public: Self::Public,
+		_account: u64,
+		_index: u64,
+	) -> Option<(pallet_dat_verify::Call<Test>, (u64, ()))> {
+		None
+	}
+}
+
+ord_parameter_types! {
+	pub const ForceAccount: u64 = 100;
+}
+
+parameter_types! {
+	pub const SeederBond: u64 = 10;
+	pub const BondPerGigabyte: u64 = 2;
+	pub const ChallengeSlash: Perbill = Perbill::from_percent(50);
+	pub const ChallengeReward: u64 = 5;
+	pub const ChallengesPerBlock: u32 = 1;
+	pub const DetectableLoss: Perbill = Perbill::from_percent(50);
+	pub const DetectionProbability: Perbill = Perbill::from_percent(50);
+	pub const MaxChunksPerChallenge: u32 = 8;
+	pub const MaxChunkSize: u32 = 1024;
+	pub const MaxProofNodes: u32 = 32;
+	pub const MaxRootChildren: u32 = 8;
+	pub const AttestorsPerChallenge: u32 = 3;
+	pub const AttestationQuorum: u32 = 2;
+	pub const AttestationReward: u64 = 3;
+	pub const ProofReputation: u32 = 10;
+	pub const FailureReputation: u32 = 30;
+	pub const AttestationReputation: u32 = 5;
+	pub const MaxReputation: u32 = 100;
+	pub const ReputationDecay: Perbill = Perbill::from_percent(10);
+	pub const ReputationPeriod: u64 = 10;
+	pub const ReputationsDecayedPerBlock: u32 = 2;
+	pub const HostingsReleasedPerBlock: u32 = 2;
+}
+
+impl pallet_dat_verify::Trait for Test {
+	type Event = TestEvent;
+	type Randomness = ();
+	type ForceOrigin = EnsureSignedBy<ForceAccount, u64>;
+	type SeederMembership = ();
+	type UserMembership = ();
+	type Currency = Balances;
+	type SeederBond = SeederBond;
+	type BondPerGigabyte = BondPerGigabyte;
+	type ChallengeSlash = ChallengeSlash;
+	type ChallengeReward = ChallengeReward;
+	type ChallengesPerBlock = ChallengesPerBlock;
+	type DetectableLoss = DetectableLoss;
+	type DetectionProbability = DetectionProbability;
+	type MaxChunksPerChallenge = MaxChunksPerChallenge;
+	type MaxChunkSize = MaxChunkSize;
+	type MaxProofNodes = MaxProofNodes;
+	type MaxRootChildren = MaxRootChildren;
+	type AttestorsPerChallenge = AttestorsPerChallenge;
+	type AttestationQuorum = AttestationQuorum;
+	type AttestationReward = AttestationReward;
+	type ProofReputation = ProofReputation;
+	type FailureReputation = FailureReputation;
+	type AttestationReputation = AttestationReputation;
+	type MaxReputation = MaxReputation;
+	type ReputationDecay = ReputationDecay;
+	type ReputationPeriod = ReputationPeriod;
+	type ReputationsDecayedPerBlock = ReputationsDecayedPerBlock;
+	type HostingsReleasedPerBlock = HostingsReleasedPerBlock;
+	type TrustedSeeders = ();
+	type ReportChallengeFailure = ();
+	type StorageDeals = DatDeals;
+	type Call = pallet_dat_verify::Call<Test>;
+	type SubmitTransaction = SubmitTransaction;
+}
+
+parameter_types! {
+	pub const PaymentPeriod: u64 = 2;
+	pub const MaxPayoutsPerBlock: u32 = 2;
+	pub const MaxReplication: u32 = 3;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type PaymentPeriod = PaymentPeriod;
+	type MaxPayoutsPerBlock = MaxPayoutsPerBlock;
+	type MaxReplication = MaxReplication;
+}
+
+pub type DatDeals = Module<Test>;
+pub type DatVerify = pallet_dat_verify::Module<Test>;
+pub type System = frame_system::Module<Test>;
+pub type Balances = pallet_balances::Module<Test>;
+
+/// The account publishing archives in the tests.
+pub const PUBLISHER: u64 = 1;
+/// The accounts hosting archives in the tests.
+pub const HOSTERS: [u64; 3] = [2, 3, 4];
+/// An account without funds.
+pub const PAUPER: u64 = 5;
+/// What every funded account starts with.
+pub const INITIAL_BALANCE: u64 = 100;
+
+/// The key of the archive registered by `register_archive`.
+pub fn archive() -> Public {
+	ed25519::Pair::from_seed(&[1; 32]).public()
+}
+
+/// Register `archive()` as the first archive of `PUBLISHER`, hosted by `hosters`.
+pub fn register_archive(hosters: &[u64]) {
+	DatKey::insert(0, archive());
+	DatIndex::insert(archive(), 0);
+	DatCount::put(1);
+	<UserRequestsMap<Test>>::insert(archive(), PUBLISHER);
+	for (slot, hoster) in hosters.iter().enumerate() {
+		<DatHosters<Test>>::insert(archive(), hoster, slot as u32);
+		<DatHostersAt<Test>>::insert(archive(), slot as u32, hoster);
+		<UsersStorage<Test>>::insert(hoster, archive(), 0);
+		<UsersStorageAt<Test>>::insert(hoster, 0, archive());
+		<UsersStorageCount<Test>>::insert(hoster, 1);
+	}
+	DatHostersCount::insert(archive(), hosters.len() as u32);
+}
+
+/// The events deposited by this module so far.
+pub fn dat_deals_events() -> Vec<Event<Test>> {
+	System::events().into_iter().filter_map(|record| match record.event {
+		TestEvent::dat_deals(inner) => Some(inner),
+		_ => None,
+	}).collect()
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![
+			(PUBLISHER, INITIAL_BALANCE),
+			(HOSTERS[0], INITIAL_BALANCE),
+			(HOSTERS[1], INITIAL_BALANCE),
+			(HOSTERS[2], INITIAL_BALANCE),
+		],
+	}.assimilate_storage(&mut t).unwrap();
+	let mut ext: sp_io::TestExternalities = t.into();
+	// events are not stored at genesis.
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
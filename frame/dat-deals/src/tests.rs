@@ -0,0 +1,221 @@
+use super::*;
+use crate::mock::*;
+use frame_support::{assert_ok, assert_noop, traits::{OnFinalize, ReservableCurrency}};
+use sp_core::{Pair, ed25519};
+
+fn open_deal(duration: u64, replication: u32, price_per_block: u64) -> frame_support::dispatch::DispatchResult {
+	DatDeals::open_deal(Origin::signed(PUBLISHER), archive(), duration, replication, price_per_block)
+}
+
+fn escrow() -> u64 {
+	Balances::free_balance(DatDeals::escrow_account(&archive()))
+}
+
+#[test]
+fn open_deal_moves_escrow_to_its_account() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_ok!(open_deal(10, 2, 3));
+		assert_eq!(escrow(), 60);
+		assert_eq!(Balances::reserved_balance(PUBLISHER), 0);
+		assert_eq!(Balances::free_balance(PUBLISHER), INITIAL_BALANCE - 60);
+		assert_eq!(DatDeals::deal(archive()), Some(Deal {
+			publisher: PUBLISHER,
+			replication: 2,
+			price_per_block: 3,
+			escrow: 60,
+			end: 11,
+			paid_until: 1,
+			next_payout: 2,
+		}));
+		assert_eq!(DatDeals::deals_due(2), vec![archive()]);
+		assert_eq!(dat_deals_events(), vec![RawEvent::DealOpened(archive(), PUBLISHER, 60)]);
+	});
+}
+
+#[test]
+fn open_deal_requires_publisher() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(open_deal(10, 2, 3), Error::<Test>::NotPublisher);
+		register_archive(&HOSTERS);
+		assert_noop!(
+			DatDeals::open_deal(Origin::signed(HOSTERS[0]), archive(), 10, 2, 3),
+			Error::<Test>::NotPublisher
+		);
+	});
+}
+
+#[test]
+fn open_deal_rejects_invalid_deals() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_noop!(open_deal(0, 2, 3), Error::<Test>::InvalidDeal);
+		assert_noop!(open_deal(10, 0, 3), Error::<Test>::InvalidDeal);
+		assert_noop!(open_deal(10, MaxReplication::get() + 1, 3), Error::<Test>::InvalidDeal);
+		assert_noop!(open_deal(10, 2, 0), Error::<Test>::InvalidDeal);
+		assert_noop!(open_deal(10, 2, u64::max_value()), Error::<Test>::EscrowOverflow);
+		assert_noop!(open_deal(10, 2, 10), Error::<Test>::InsufficientFunds);
+	});
+}
+
+#[test]
+fn open_deal_rejects_second_deal() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_ok!(open_deal(10, 2, 3));
+		assert_noop!(open_deal(10, 1, 1), Error::<Test>::DealExists);
+	});
+}
+
+#[test]
+fn deal_pays_hosters_every_period() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_ok!(open_deal(10, 2, 3));
+		DatDeals::on_finalize(1);
+		assert_eq!(Balances::free_balance(HOSTERS[0]), INITIAL_BALANCE);
+		// the payout pays for every block of the period.
+		DatDeals::on_finalize(2);
+		// only as many hosters as the replication factor are paid.
+		assert_eq!(Balances::free_balance(HOSTERS[0]), INITIAL_BALANCE + 6);
+		assert_eq!(Balances::free_balance(HOSTERS[1]), INITIAL_BALANCE + 6);
+		assert_eq!(Balances::free_balance(HOSTERS[2]), INITIAL_BALANCE);
+		assert_eq!(escrow(), 48);
+		let deal = DatDeals::deal(archive()).unwrap();
+		assert_eq!((deal.escrow, deal.paid_until, deal.next_payout), (48, 3, 4));
+		assert!(DatDeals::deals_due(2).is_empty());
+		assert_eq!(DatDeals::deals_due(4), vec![archive()]);
+	});
+}
+
+#[test]
+fn payouts_beyond_the_limit_are_queued_in_the_next_block() {
+	new_test_ext().execute_with(|| {
+		let others = [ed25519::Pair::from_seed(&[2; 32]).public(), ed25519::Pair::from_seed(&[3; 32]).public()];
+		for other in others.iter() {
+			assert_eq!(DatDeals::schedule_payout(other, 2), 2);
+		}
+		register_archive(&HOSTERS);
+		assert_ok!(open_deal(10, 2, 3));
+		assert_eq!(DatDeals::deals_due(3), vec![archive()]);
+
+		// archives without a deal are not paid out, and a late payout pays for the blocks it missed.
+		DatDeals::on_finalize(2);
+		assert_eq!(Balances::free_balance(HOSTERS[0]), INITIAL_BALANCE);
+		DatDeals::on_finalize(3);
+		assert_eq!(Balances::free_balance(HOSTERS[0]), INITIAL_BALANCE + 9);
+		assert_eq!(escrow(), 42);
+	});
+}
+
+#[test]
+fn escrow_is_out_of_reach_of_slashes() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_ok!(open_deal(10, 2, 3));
+		assert_ok!(Balances::reserve(&PUBLISHER, 10));
+		let _ = Balances::slash_reserved(&PUBLISHER, INITIAL_BALANCE);
+		assert_eq!(escrow(), 60);
+		DatDeals::on_finalize(2);
+		assert_eq!(Balances::free_balance(HOSTERS[0]), INITIAL_BALANCE + 6);
+	});
+}
+
+#[test]
+fn deal_ends_and_refunds_escrow() {
+	new_test_ext().execute_with(|| {
+		// a single hoster for a replication factor of two leaves half the escrow unspent.
+		register_archive(&HOSTERS[..1]);
+		assert_ok!(open_deal(2, 2, 3));
+		DatDeals::on_finalize(1);
+		assert!(DatDeals::deal(archive()).is_some());
+		DatDeals::on_finalize(2);
+		assert_eq!(DatDeals::deal(archive()), None);
+		assert_eq!(Balances::free_balance(HOSTERS[0]), INITIAL_BALANCE + 6);
+		assert_eq!(escrow(), 0);
+		assert_eq!(Balances::free_balance(PUBLISHER), INITIAL_BALANCE - 6);
+		assert_eq!(dat_deals_events().last(), Some(&RawEvent::DealEnded(archive(), 6)));
+	});
+}
+
+#[test]
+fn deal_spends_whole_escrow() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_ok!(open_deal(1, 3, 1));
+		DatDeals::on_finalize(1);
+		assert_eq!(DatDeals::deal(archive()), None);
+		assert_eq!(Balances::free_balance(PUBLISHER), INITIAL_BALANCE - 3);
+		assert_eq!(dat_deals_events().last(), Some(&RawEvent::DealEnded(archive(), 0)));
+	});
+}
+
+#[test]
+fn escrow_below_the_existential_deposit_goes_to_the_hoster() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_ok!(open_deal(10, 2, 3));
+		// paying the second hoster leaves 48 of the escrow, which would be reaped.
+		set_existential_deposit(50);
+		DatDeals::on_finalize(2);
+		assert_eq!(Balances::free_balance(HOSTERS[0]), INITIAL_BALANCE + 6);
+		assert_eq!(Balances::free_balance(HOSTERS[1]), INITIAL_BALANCE + 54);
+		assert_eq!(escrow(), 0);
+		assert_eq!(DatDeals::deal(archive()), None);
+		assert!(DatDeals::deals_due(4).is_empty());
+		assert_eq!(dat_deals_events().last(), Some(&RawEvent::DealEnded(archive(), 0)));
+	});
+}
+
+#[test]
+fn unregister_data_refunds_escrow() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_ok!(open_deal(10, 2, 3));
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 0));
+		assert_eq!(DatDeals::deal(archive()), None);
+		assert!(DatDeals::deals_due(2).is_empty());
+		assert_eq!(escrow(), 0);
+		assert_eq!(Balances::free_balance(PUBLISHER), INITIAL_BALANCE);
+		assert_eq!(dat_deals_events().last(), Some(&RawEvent::DealEnded(archive(), 60)));
+	});
+}
+
+#[test]
+fn unregistered_seeder_is_not_paid() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_ok!(open_deal(10, 2, 3));
+		assert_ok!(DatVerify::unregister_seeder(Origin::signed(HOSTERS[0])));
+		DatDeals::on_finalize(2);
+		assert_eq!(Balances::free_balance(HOSTERS[0]), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(HOSTERS[1]), INITIAL_BALANCE + 6);
+		assert_eq!(Balances::free_balance(HOSTERS[2]), INITIAL_BALANCE + 6);
+	});
+}
+
+#[test]
+fn hosters_pending_eviction_are_not_paid() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_ok!(open_deal(10, 2, 3));
+		// an evicted hoster keeps its slot until `dat_verify` gets to release it.
+		<Evictions<Test>>::put(vec![HOSTERS[1]]);
+		DatDeals::on_finalize(2);
+		assert_eq!(Balances::free_balance(HOSTERS[0]), INITIAL_BALANCE + 6);
+		assert_eq!(Balances::free_balance(HOSTERS[1]), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(HOSTERS[2]), INITIAL_BALANCE + 6);
+	});
+}
+
+#[test]
+fn deal_sets_replication_target_and_price() {
+	new_test_ext().execute_with(|| {
+		register_archive(&HOSTERS);
+		assert_eq!(<DatDeals as StorageDeals>::replication(&archive()), 0);
+		assert_eq!(<DatDeals as StorageDeals>::price_per_block(&archive()), 0);
+		assert_ok!(open_deal(10, 2, 3));
+		assert_eq!(<DatDeals as StorageDeals>::replication(&archive()), 2);
+		assert_eq!(<DatDeals as StorageDeals>::price_per_block(&archive()), 3);
+	});
+}
@@ -0,0 +1,102 @@
+//! Runtime API definition for the dat_verify module.
+//!
+//! This API should be imported and implemented by the runtime of a node that serves the
+//! `datdot_*` RPC methods to seeder daemons and dashboards.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Codec, Decode, Encode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use sp_core::{ed25519, H256};
+use sp_runtime::RuntimeDebug;
+
+/// The public key a Dat archive is registered with.
+pub type Public = ed25519::Public;
+
+/// What is known about a registered archive.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ArchiveInfo<AccountId> {
+	/// The index the archive is registered at.
+	pub index: u64,
+	/// The account that registered the archive.
+	pub publisher: AccountId,
+	/// The hash of the signed roots of the archive's Merkle tree.
+	pub merkle_root: H256,
+	/// The size of the archive, in bytes.
+	pub tree_size: u64,
+	/// The number of chunks in the archive.
+	pub tree_length: u64,
+	/// The number of updates made to the archive's Merkle root.
+	pub tree_version: u64,
+	/// The accounts hosting the archive.
+	pub hosters: Vec<AccountId>,
+}
+
+/// The capacity a seeder declared, and how much of it is used.
+#[derive(Eq, PartialEq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct CapacityInfo {
+	/// The bytes of archives the seeder declared it has room for.
+	pub declared: u64,
+	/// The bytes of the archives the seeder hosts, which updates may grow past `declared`.
+	pub used: u64,
+}
+
+/// A challenge a seeder has yet to answer.
+#[derive(Eq, PartialEq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ChallengeInfo<BlockNumber> {
+	/// The index of the challenge, to answer it with.
+	pub index: u64,
+	/// The archive and flat tree index of every challenged chunk, sorted.
+	pub chunks: Vec<(Public, u64)>,
+	/// The block at the end of which the challenge fails.
+	pub deadline: BlockNumber,
+}
+
+sp_api::decl_runtime_apis! {
+	/// The API to query the archives, hosters and challenges of dat_verify.
+	pub trait DatVerifyApi<AccountId, BlockNumber> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+	{
+		/// The archives hosted by `account`.
+		fn archives_hosted_by(account: AccountId) -> Vec<Public>;
+
+		/// The accounts hosting `archive`.
+		fn hosters_of(archive: Public) -> Vec<AccountId>;
+
+		/// The challenges `account` has yet to answer.
+		fn challenges_for(account: AccountId) -> Vec<ChallengeInfo<BlockNumber>>;
+
+		/// What is known about `archive`, `None` if it is not registered.
+		fn archive_info(archive: Public) -> Option<ArchiveInfo<AccountId>>;
+
+		/// The capacity `account` declared and uses, `None` if it declared none.
+		fn capacity_of(account: AccountId) -> Option<CapacityInfo>;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_serialize_properly() {
+		let challenge = ChallengeInfo {
+			index: 3,
+			chunks: vec![(Public::from_raw([0; 32]), 4), (Public::from_raw([0; 32]), 6)],
+			deadline: 10u32,
+		};
+		let json = serde_json::to_value(&challenge).unwrap();
+		assert_eq!(json["chunks"][1][1], 6);
+		assert_eq!(json["deadline"], 10);
+		assert_eq!(serde_json::from_value::<ChallengeInfo<u32>>(json).unwrap(), challenge);
+	}
+}
@@ -0,0 +1,139 @@
+//! RPC interface for the dat_verify module, serving the `datdot_*` methods.
+
+use std::sync::Arc;
+use codec::Codec;
+use sp_blockchain::HeaderBackend;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_api::ProvideRuntimeApi;
+use pallet_dat_verify_rpc_runtime_api::{Public, ArchiveInfo, CapacityInfo, ChallengeInfo};
+pub use pallet_dat_verify_rpc_runtime_api::DatVerifyApi as DatVerifyRuntimeApi;
+pub use self::gen_client::Client as DatVerifyClient;
+
+/// Queries on the archives, hosters and challenges of dat_verify.
+#[rpc]
+pub trait DatVerifyApi<BlockHash, AccountId, BlockNumber> {
+	/// The archives hosted by `account`.
+	#[rpc(name = "datdot_archivesHostedBy")]
+	fn archives_hosted_by(&self, account: AccountId, at: Option<BlockHash>) -> Result<Vec<Public>>;
+
+	/// The accounts hosting `archive`.
+	#[rpc(name = "datdot_hostersOf")]
+	fn hosters_of(&self, archive: Public, at: Option<BlockHash>) -> Result<Vec<AccountId>>;
+
+	/// The challenges `account` has yet to answer.
+	#[rpc(name = "datdot_challengesFor")]
+	fn challenges_for(
+		&self,
+		account: AccountId,
+		at: Option<BlockHash>
+	) -> Result<Vec<ChallengeInfo<BlockNumber>>>;
+
+	/// What is known about `archive`, `null` if it is not registered.
+	#[rpc(name = "datdot_archiveInfo")]
+	fn archive_info(&self, archive: Public, at: Option<BlockHash>) -> Result<Option<ArchiveInfo<AccountId>>>;
+
+	/// The capacity `account` declared and uses, `null` if it declared none.
+	#[rpc(name = "datdot_capacityOf")]
+	fn capacity_of(&self, account: AccountId, at: Option<BlockHash>) -> Result<Option<CapacityInfo>>;
+}
+
+/// A struct that implements the [`DatVerifyApi`].
+pub struct DatVerify<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> DatVerify<C, B> {
+	/// Create new `DatVerify` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		DatVerify { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+/// Map a failed runtime API call to an RPC error.
+fn runtime_error<E: std::fmt::Debug>(e: E) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: "Unable to query dat_verify.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, AccountId, BlockNumber> DatVerifyApi<<Block as BlockT>::Hash, AccountId, BlockNumber>
+	for DatVerify<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: DatVerifyRuntimeApi<Block, AccountId, BlockNumber>,
+	AccountId: Codec,
+	BlockNumber: Codec,
+{
+	fn archives_hosted_by(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>
+	) -> Result<Vec<Public>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+		api.archives_hosted_by(&at, account).map_err(runtime_error)
+	}
+
+	fn hosters_of(
+		&self,
+		archive: Public,
+		at: Option<<Block as BlockT>::Hash>
+	) -> Result<Vec<AccountId>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.hosters_of(&at, archive).map_err(runtime_error)
+	}
+
+	fn challenges_for(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>
+	) -> Result<Vec<ChallengeInfo<BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.challenges_for(&at, account).map_err(runtime_error)
+	}
+
+	fn archive_info(
+		&self,
+		archive: Public,
+		at: Option<<Block as BlockT>::Hash>
+	) -> Result<Option<ArchiveInfo<AccountId>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.archive_info(&at, archive).map_err(runtime_error)
+	}
+
+	fn capacity_of(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>
+	) -> Result<Option<CapacityInfo>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.capacity_of(&at, account).map_err(runtime_error)
+	}
+}
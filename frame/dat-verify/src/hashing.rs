@@ -0,0 +1,182 @@
+//! Hypercore Merkle tree hashing.
+//!
+//! Byte compatible with `hypercore-crypto`: every hash is an unkeyed BLAKE2b-256 over a one
+//! byte type prefix followed by big-endian `u64` lengths and indeces, so roots signed by
+//! real Dat clients verify here unchanged.
+//! See <https://datprotocol.github.io/how-dat-works/#hashes-and-signatures>.
+//!
+//! The discovery key of a feed is the one exception, a BLAKE2b-256 keyed with the feed key, as
+//! is the seed of the registration key of a private feed, keyed with the seed of the feed key.
+
+use sp_std::prelude::*;
+use sp_core::H256;
+use sp_io::hashing::blake2_256;
+use blake2_rfc::blake2b::blake2b;
+use crate::{ParentHashInRoot, Public};
+
+/// Type prefix of the hash of a chunk of data.
+pub const LEAF_TYPE: u8 = 0;
+/// Type prefix of the hash of two sibling nodes.
+pub const PARENT_TYPE: u8 = 1;
+/// Type prefix of the hash of the roots of a tree, which is what the feed key signs.
+pub const ROOT_TYPE: u8 = 2;
+
+/// The message hashed, keyed with the feed key, into the discovery key.
+pub const DISCOVERY_MESSAGE: &[u8] = b"hypercore";
+
+/// The message hashed, keyed with the seed of the feed key, into the seed of its registration key.
+pub const REGISTRATION_MESSAGE: &[u8] = b"datdot registration";
+
+/// Hash of a single chunk of feed data.
+pub fn leaf(data: &[u8]) -> H256 {
+	let mut payload = Vec::with_capacity(9 + data.len());
+	payload.push(LEAF_TYPE);
+	payload.extend_from_slice(&(data.len() as u64).to_be_bytes());
+	payload.extend_from_slice(data);
+	blake2_256(&payload).into()
+}
+
+/// Hash of a parent node.
+///
+/// `left` must be the child with the lower flat-tree index, and `total_length` is the number of
+/// data bytes spanned by both children.
+pub fn parent(left: &H256, right: &H256, total_length: u64) -> H256 {
+	let mut payload = Vec::with_capacity(73);
+	payload.push(PARENT_TYPE);
+	payload.extend_from_slice(&total_length.to_be_bytes());
+	payload.extend_from_slice(left.as_bytes());
+	payload.extend_from_slice(right.as_bytes());
+	blake2_256(&payload).into()
+}
+
+/// Hash of the roots of a tree, in ascending flat-tree index order.
+pub fn tree(roots: &[ParentHashInRoot]) -> H256 {
+	let mut payload = Vec::with_capacity(1 + 48 * roots.len());
+	payload.push(ROOT_TYPE);
+	for root in roots {
+		payload.extend_from_slice(root.hash.as_bytes());
+		payload.extend_from_slice(&root.hash_number.to_be_bytes());
+		payload.extend_from_slice(&root.total_length.to_be_bytes());
+	}
+	blake2_256(&payload).into()
+}
+
+/// The discovery key of the feed `key`, which peers find and replicate the feed by without
+/// learning its key. The seeders of a private archive find its feed by it.
+pub fn discovery_key(key: &Public) -> Public {
+	let mut raw = [0u8; 32];
+	raw.copy_from_slice(blake2b(32, &key.0, DISCOVERY_MESSAGE).as_bytes());
+	Public::from_raw(raw)
+}
+
+/// The seed of the registration key of the feed with the secret `seed`, which signs its roots
+/// when it is registered privately, see `Module::register_private_data`. Only the holder of the
+/// secret key of the feed derives it, and the feed key cannot be told from it.
+pub fn registration_seed(seed: &[u8; 32]) -> [u8; 32] {
+	let mut raw = [0u8; 32];
+	raw.copy_from_slice(blake2b(32, seed, REGISTRATION_MESSAGE).as_bytes());
+	raw
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+	use sp_core::ed25519;
+	use sp_runtime::traits::Verify;
+
+	// A five chunk feed: leaves 0, 2, 4, 6 and 8, parents 1, 5 and 3, roots 3 and 8.
+	// Worked out by hand from the `hypercore-crypto` construction and signed with the ed25519
+	// key whose seed is `[7; 32]`; they have not been compared with a feed hypercore wrote.
+	// The proof builder's `reads_a_feed_written_by_hypercore` does that once
+	// `fixtures/make-feed.js` has produced `fixtures/hypercore-feed`.
+	const FEED: [&[u8]; 5] = [b"hello", b"world", b"this is", b"a small", b"hypercore"];
+	const FEED_KEY: [u8; 32] = hex!["ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c"];
+	const FEED_LEAVES: [[u8; 32]; 5] = [
+		hex!["6717b25f24d96ccbc95166bacbb671d59eb4263ee5e1aa0f6b1520815cbee80b"],
+		hex!["b49340bf69887822e1c282929e2c81125ec7aedb902b34f7ca3ba1db7aabdea5"],
+		hex!["8ea73ed4a726a3725227075b269330d499cfcb5b5a79458c9040109763b36767"],
+		hex!["cc539bac0370552f408c631865a9783da3c53b8c34ab205fde7b50ea2b723792"],
+		hex!["bf05c99d995f6004651a9f7f33ea44859360a4dbfadb65c3180faff12d895f9c"],
+	];
+	const FEED_NODE_1: [u8; 32] = hex!["408f1fc979c28158324b753394dc4630723761a06fc7202df5d95ad27028a130"];
+	const FEED_NODE_5: [u8; 32] = hex!["f4cac4c81690bbc7891cf37d9d78a1f3aca254ec046c42263a01815171064903"];
+	const FEED_NODE_3: [u8; 32] = hex!["e2cbb0030c5282d9c1c441c1d21895b9e72bc79a461111e4e439a0376ec0e627"];
+	const FEED_TREE: [u8; 32] = hex!["332af0dd545d29846723384c4c919cba837f12f8941e5f33dc4d479f8dd428aa"];
+	const FEED_SIGNATURE: [u8; 64] = hex!["6d3a1665e4c5380a633f3225f4a1acc6cf8e51bcb9fce70f933a1f63908572f821b9f7adc2436f296c9dac4e13991e40ebfd9e446bc45a6dcb4b2828a2fbde00"];
+
+	#[test]
+	fn leaf_matches_hypercore_crypto() {
+		// the `hello world` vector of the `hypercore-crypto` test suite.
+		assert_eq!(
+			leaf(b"hello world"),
+			H256::from(hex!["ccfa4259ee7c41e411e5770973a49c5ceffb5272d6a37f2c6f2dac2190f7e2b7"]),
+		);
+	}
+
+	#[test]
+	fn parent_hashes_both_children() {
+		let hello = leaf(b"hello world");
+		assert_eq!(
+			parent(&hello, &hello, 22),
+			H256::from(hex!["43563406adba8b34b133fdca32d0a458c5be769615e01df30e6535ccd3c075f0"]),
+		);
+		assert_ne!(parent(&hello, &H256::zero(), 22), parent(&H256::zero(), &hello, 22));
+	}
+
+	#[test]
+	fn tree_hashes_indeces_and_sizes() {
+		let roots = [
+			ParentHashInRoot { hash: H256::zero(), hash_number: 3, total_length: 11 },
+			ParentHashInRoot { hash: H256::zero(), hash_number: 9, total_length: 2 },
+		];
+		assert_eq!(
+			tree(&roots),
+			H256::from(hex!["334dd9d8f9a48c7b7e60affa8704a3597f87fe645fe83f1aada3a1216ea91e65"]),
+		);
+	}
+
+	#[test]
+	fn discovery_key_matches_hypercore_crypto() {
+		// `crypto_generichash(out, 'hypercore', key)`, as `hypercore-crypto` computes it.
+		assert_eq!(
+			discovery_key(&ed25519::Public::from_raw(FEED_KEY)),
+			ed25519::Public::from_raw(hex!["e3ac68a2145739cf48b8c0c43ad54595216b4bcbd5914c35dab942fb8c0c571b"]),
+		);
+		assert_ne!(discovery_key(&ed25519::Public::from_raw([0u8; 32])), ed25519::Public::from_raw([0u8; 32]));
+	}
+
+	#[test]
+	fn registration_seed_is_keyed_with_the_feed_seed() {
+		// `crypto_generichash(out, 'datdot registration', seed)`.
+		assert_eq!(
+			registration_seed(&[7u8; 32]),
+			hex!["829527a1d29bb62174762cf3f69aedee7e7dc69ce69f131a47a637c628d18958"],
+		);
+		assert_ne!(registration_seed(&[7u8; 32]), registration_seed(&[8u8; 32]));
+	}
+
+	#[test]
+	fn feed_vectors_verify() {
+		let leaves: Vec<H256> = FEED.iter().map(|chunk| leaf(chunk)).collect();
+		let expected: Vec<H256> = FEED_LEAVES.iter().map(|hash| H256::from(*hash)).collect();
+		assert_eq!(leaves, expected);
+
+		let node_1 = parent(&leaves[0], &leaves[1], 10);
+		let node_5 = parent(&leaves[2], &leaves[3], 14);
+		let node_3 = parent(&node_1, &node_5, 24);
+		assert_eq!(node_1, H256::from(FEED_NODE_1));
+		assert_eq!(node_5, H256::from(FEED_NODE_5));
+		assert_eq!(node_3, H256::from(FEED_NODE_3));
+
+		let roots = [
+			ParentHashInRoot { hash: node_3, hash_number: 3, total_length: 24 },
+			ParentHashInRoot { hash: leaves[4], hash_number: 8, total_length: 9 },
+		];
+		let root_hash = tree(&roots);
+		assert_eq!(root_hash, H256::from(FEED_TREE));
+
+		let signature = ed25519::Signature::from_raw(FEED_SIGNATURE);
+		assert!(signature.verify(root_hash.as_bytes(), &ed25519::Public::from_raw(FEED_KEY)));
+	}
+}
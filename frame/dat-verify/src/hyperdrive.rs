@@ -0,0 +1,145 @@
+//! Hyperdrive archives: a metadata feed and the content feed it names.
+//!
+//! The first chunk of the metadata feed of a hyperdrive is its header, the protobuf message
+//! `Index { required string type = 1; optional bytes content = 2; }` with the type `hyperdrive`
+//! and the key of the content feed. Proving that chunk against the signed roots of the
+//! metadata feed links both feeds, see `Module::register_drive`.
+//! See <https://datprotocol.github.io/how-dat-works/#hyperdrive>.
+
+use sp_std::prelude::*;
+use crate::Public;
+
+/// The `type` of the header of a hyperdrive.
+pub const DRIVE_TYPE: &[u8] = b"hyperdrive";
+
+// the fields of the header.
+const TYPE_FIELD: u64 = 1;
+const CONTENT_FIELD: u64 = 2;
+
+// the protobuf wire types.
+const VARINT: u64 = 0;
+const FIXED_64: u64 = 1;
+const LENGTH_DELIMITED: u64 = 2;
+const FIXED_32: u64 = 5;
+
+/// The header of a hyperdrive whose content feed is `content`, as hyperdrive writes it.
+pub fn header(content: &Public) -> Vec<u8> {
+	let mut header = Vec::with_capacity(2 + DRIVE_TYPE.len() + 2 + 32);
+	push_bytes(&mut header, TYPE_FIELD, DRIVE_TYPE);
+	push_bytes(&mut header, CONTENT_FIELD, &content.0);
+	header
+}
+
+/// The key of the content feed `header` names, if it is the header of a hyperdrive. Fields
+/// hyperdrive may add later are skipped.
+pub fn content_key(header: &[u8]) -> Option<Public> {
+	let mut rest = header;
+	let (mut drive_type, mut content) = (None, None);
+	while !rest.is_empty() {
+		let key = read_varint(&mut rest)?;
+		match key & 7 {
+			VARINT => {
+				read_varint(&mut rest)?;
+			},
+			FIXED_64 => rest = rest.get(8..)?,
+			FIXED_32 => rest = rest.get(4..)?,
+			LENGTH_DELIMITED => {
+				let length = read_varint(&mut rest)?;
+				if length > rest.len() as u64 {
+					return None;
+				}
+				let (value, tail) = rest.split_at(length as usize);
+				rest = tail;
+				match key >> 3 {
+					TYPE_FIELD => drive_type = Some(value),
+					CONTENT_FIELD => content = Some(value),
+					_ => {},
+				}
+			},
+			_ => return None,
+		}
+	}
+	match (drive_type, content) {
+		(Some(DRIVE_TYPE), Some(content)) if content.len() == 32 => {
+			let mut raw = [0u8; 32];
+			raw.copy_from_slice(content);
+			Some(Public::from_raw(raw))
+		},
+		_ => None,
+	}
+}
+
+// read a base 128 varint off the front of `input`.
+fn read_varint(input: &mut &[u8]) -> Option<u64> {
+	let mut value = 0u64;
+	for shift in (0..64).step_by(7) {
+		let (byte, rest) = input.split_first()?;
+		*input = rest;
+		value |= u64::from(byte & 0x7f) << shift;
+		if byte & 0x80 == 0 {
+			return Some(value);
+		}
+	}
+	None
+}
+
+fn push_varint(output: &mut Vec<u8>, mut value: u64) {
+	while value >= 0x80 {
+		output.push(value as u8 | 0x80);
+		value >>= 7;
+	}
+	output.push(value as u8);
+}
+
+fn push_bytes(output: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+	push_varint(output, field << 3 | LENGTH_DELIMITED);
+	push_varint(output, bytes.len() as u64);
+	output.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+
+	const CONTENT_KEY: [u8; 32] = hex!["ea4a6c63e29c520abef5507b132ec5f9954776aebebe7b92421eea691446d22c"];
+
+	#[test]
+	fn header_matches_hyperdrive() {
+		// `messages.Index.encode({ type: 'hyperdrive', content })` of hyperdrive.
+		let mut expected = hex!["0a0a687970657264726976651220"].to_vec();
+		expected.extend_from_slice(&CONTENT_KEY);
+		assert_eq!(header(&Public::from_raw(CONTENT_KEY)), expected);
+	}
+
+	#[test]
+	fn content_key_reads_the_header() {
+		let content = Public::from_raw(CONTENT_KEY);
+		assert_eq!(content_key(&header(&content)), Some(content));
+
+		// unknown fields are skipped, wherever they are.
+		let mut extended = vec![0x18, 0x96, 0x01];
+		extended.extend_from_slice(&header(&content));
+		extended.extend_from_slice(&[0x22, 0x02, 0xff, 0xff]);
+		assert_eq!(content_key(&extended), Some(content));
+	}
+
+	#[test]
+	fn content_key_rejects_other_headers() {
+		let content = Public::from_raw(CONTENT_KEY);
+		let mut other_type = Vec::new();
+		push_bytes(&mut other_type, TYPE_FIELD, b"hypertrie");
+		push_bytes(&mut other_type, CONTENT_FIELD, &content.0);
+		assert_eq!(content_key(&other_type), None);
+
+		let mut short_key = Vec::new();
+		push_bytes(&mut short_key, TYPE_FIELD, DRIVE_TYPE);
+		push_bytes(&mut short_key, CONTENT_FIELD, &content.0[..31]);
+		assert_eq!(content_key(&short_key), None);
+
+		let full = header(&content);
+		assert_eq!(content_key(&full[..full.len() - 1]), None);
+		assert_eq!(content_key(&full[..12]), None);
+		assert_eq!(content_key(b""), None);
+	}
+}
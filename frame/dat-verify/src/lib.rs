@@ -0,0 +1,2357 @@
+//! # Dat Verify Module
+//!
+//! Publishers register Dat archives by public key and signed Merkle root. Seeders ask for a
+//! number of archives, or an amount of capacity, to pin and are assigned the archives furthest
+//! from the replication their storage deal pays for. Every block a random seeder is
+//! challenged to prove they still hold a number of random chunks of their archives, answering
+//! with a single proof per archive against its registered Merkle root. The number of chunks is
+//! derived from the chance a challenge should have to catch a seeder who lost part of an archive.
+//!
+//! Every random choice of a challenge is drawn from the randomness of the runtime, meant to be
+//! BABE's epoch randomness which block authors cannot bias, the block the challenge is issued in
+//! and its index alone, see `Module::draw`. The seed of every challenge is in its `ChallengeSeed`
+//! event.
+//!
+//! Other seeders are picked at random to attest whether the challenged seeder serves one of those
+//! archives on the Dat network. Once a quorum of them agree, the seeder is slashed if it does
+//! not, and the attestors who agreed are paid out of the reward pot.
+//!
+//! Seeders reserve a bond when they start seeding. Failing a challenge slashes part of
+//! that bond into the reward pot, reports an offence and evicts the seeder, who is released from
+//! its archives `HostingsReleasedPerBlock` at a time, while every passed challenge is
+//! paid a reward out of the pot. Seeders who declare the capacity they have room for bond
+//! more for every gigabyte of it, and are only assigned archives within what is left of it.
+//!
+//! Every seeder has a reputation, raised by passed challenges and attestations agreeing with the
+//! quorum, lowered by failed challenges and attestations, and decaying every `ReputationPeriod`
+//! blocks, `ReputationsDecayedPerBlock` at a time. Rewards grow with it, up to double at
+//! `MaxReputation`, and the seeders with the most reputation are kept as the `TrustedSeeders`.
+//! Publishers may have an archive assigned to trusted seeders only, who are assigned those
+//! archives before any other, see `require_trusted`.
+//!
+//! Proofs and roots are weighed, and paid for, by the chunk bytes and nodes they carry, up to
+//! the limits set in the `Trait`. The `CheckProofLimits` signed extension drops calls over those
+//! limits before they reach the transaction pool.
+//!
+//! Publishers who would rather not have the key of an archive on chain register it privately,
+//! by a registration key derived from the secret key of the feed, which signs its roots instead.
+//! They hand the feed key to the seeders assigned the archive in envelopes encrypted to each of
+//! them, see `deliver_key`. Proofs still carry the challenged chunks, so private content should
+//! be encrypted before it is published.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use frame_support::{
+	decl_module,
+	decl_storage,
+	decl_event,
+	decl_error,
+	debug,
+	dispatch::DispatchResult,
+	ensure,
+	fail,
+	StorageValue,
+	StorageMap,
+	StorageDoubleMap,
+	StorageLinkedMap,
+	IsSubType,
+	weights::{
+		Weight,
+		WeighData,
+		ClassifyDispatch,
+		DispatchClass,
+		DispatchInfo,
+		PaysFee,
+		SimpleDispatchInfo,
+	},
+	traits::{
+		Randomness,
+		ChangeMembers,
+		Currency,
+		ReservableCurrency,
+		ExistenceRequirement,
+		Imbalance,
+		Get,
+	},
+};
+use sp_std::{marker::PhantomData, mem};
+use frame_system::{
+	self as system,
+	ensure_signed,
+	ensure_root,
+	offchain::SubmitSignedTransaction,
+};
+use codec::{Encode, Decode};
+use sp_core::{
+	ed25519,
+	Hasher,
+	Blake2Hasher,
+	H256,
+	H512,
+};
+#[cfg(feature = "std")]
+use sp_runtime::{Serialize, Deserialize};
+#[cfg(feature = "std")]
+use types::TypeDefinition;
+use sp_runtime::{
+	RuntimeDebug,
+	Perbill,
+	ModuleId,
+	traits::{
+		Verify,
+		EnsureOrigin,
+		Convert,
+		AccountIdConversion,
+		Saturating,
+		SaturatedConversion,
+		SignedExtension,
+		StaticLookup,
+		One,
+		Zero,
+	},
+	transaction_validity::{
+		TransactionValidity,
+		TransactionValidityError,
+		InvalidTransaction,
+		ValidTransaction,
+	},
+};
+use sp_staking::{
+	SessionIndex,
+	offence::{ReportOffence, Offence, Kind},
+};
+use pallet_session::historical::IdentificationTuple;
+use pallet_dat_verify_rpc_runtime_api::{ArchiveInfo, CapacityInfo, ChallengeInfo};
+
+pub mod hashing;
+pub mod hyperdrive;
+mod migration;
+pub mod offchain;
+#[cfg(feature = "std")]
+pub mod types;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+pub type Public = ed25519::Public;
+pub type Signature = ed25519::Signature;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+// the account of the reward pot.
+const MODULE_ID: ModuleId = ModuleId(*b"dat/vrfy");
+
+// the unit of declared capacity bonded for.
+const GIGABYTE: u64 = 1 << 30;
+
+// the subject of the randomness challenges are drawn from.
+const CHALLENGE_SUBJECT: &[u8] = b"dat_verify_challenge";
+
+/// The module's configuration trait.
+pub trait Trait: system::Trait + pallet_session::historical::Trait {
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	type Randomness: Randomness<<Self as system::Trait>::Hash>;
+	type ForceOrigin: EnsureOrigin<<Self as system::Trait>::Origin>;
+	type SeederMembership: ChangeMembers<<Self as system::Trait>::AccountId>;
+	type UserMembership: ChangeMembers<<Self as system::Trait>::AccountId>;
+
+	/// The currency seeders bond and are rewarded in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The bond reserved from a seeder for as long as they are registered.
+	type SeederBond: Get<BalanceOf<Self>>;
+
+	/// The bond reserved on top of `SeederBond` for every gigabyte, or part of one, of the
+	/// capacity a seeder declares.
+	type BondPerGigabyte: Get<BalanceOf<Self>>;
+
+	/// The fraction of their bond a seeder loses for every failed challenge.
+	type ChallengeSlash: Get<Perbill>;
+
+	/// The reward paid out of the pot for every passed challenge.
+	type ChallengeReward: Get<BalanceOf<Self>>;
+
+	/// The most challenges issued, and expired, in a block.
+	type ChallengesPerBlock: Get<u32>;
+
+	/// The fraction of their archives a seeder may have lost that challenges should detect.
+	type DetectableLoss: Get<Perbill>;
+
+	/// The chance a single challenge has of catching a seeder who lost `DetectableLoss` of their
+	/// archives, which sets the number of chunks it asks for.
+	type DetectionProbability: Get<Perbill>;
+
+	/// The most chunks a challenge asks for, whatever the detection probability. Proofs
+	/// carrying more are rejected.
+	type MaxChunksPerChallenge: Get<u32>;
+
+	/// The most bytes a chunk may have in a proof.
+	type MaxChunkSize: Get<u32>;
+
+	/// The most nodes the proofs of a `submit_proof` call, or a consistency proof, may carry.
+	type MaxProofNodes: Get<u32>;
+
+	/// The most roots a registered tree may have.
+	type MaxRootChildren: Get<u32>;
+
+	/// The number of seeders asked to attest that a challenged seeder serves its archive.
+	type AttestorsPerChallenge: Get<u32>;
+
+	/// The number of matching attestations deciding whether a challenged seeder serves its
+	/// archive.
+	type AttestationQuorum: Get<u32>;
+
+	/// The reward paid out of the pot to every attestor agreeing with the decision.
+	type AttestationReward: Get<BalanceOf<Self>>;
+
+	/// The reputation a seeder gains for every passed challenge.
+	type ProofReputation: Get<Reputation>;
+
+	/// The reputation a seeder loses for every failed challenge, or attestation round.
+	type FailureReputation: Get<Reputation>;
+
+	/// The reputation an attestor gains for agreeing with the decision, and loses for not.
+	type AttestationReputation: Get<Reputation>;
+
+	/// The most reputation a seeder can have, at which its rewards are doubled.
+	type MaxReputation: Get<Reputation>;
+
+	/// The fraction of their reputation seeders lose every `ReputationPeriod`.
+	type ReputationDecay: Get<Perbill>;
+
+	/// The number of blocks between two decays of every reputation.
+	type ReputationPeriod: Get<Self::BlockNumber>;
+
+	/// The most reputations decayed in a block, the decay of a period going on over as many
+	/// blocks as it takes.
+	type ReputationsDecayedPerBlock: Get<u32>;
+
+	/// The most archives evicted seeders are released from in a block, the release of a seeder
+	/// going on over as many blocks as it takes.
+	type HostingsReleasedPerBlock: Get<u32>;
+
+	/// The seeders trusted with the archives which require them, ranked by reputation.
+	type TrustedSeeders: TrustedSeeders<Self::AccountId>;
+
+	/// A type that gives us the ability to submit failed challenge offence reports.
+	type ReportChallengeFailure:
+		ReportOffence<
+			Self::AccountId,
+			IdentificationTuple<Self>,
+			ChallengeFailureOffence<IdentificationTuple<Self>>,
+		>;
+
+	/// The storage deals paying for the hosting of archives.
+	type StorageDeals: StorageDeals;
+
+	/// A dispatchable call type.
+	type Call: From<Call<Self>>;
+
+	/// A transaction submitter signing the proofs of local seeders, see the `offchain` module.
+	type SubmitTransaction: SubmitSignedTransaction<Self, <Self as Trait>::Call>;
+}
+
+/// The weight of handling a single challenge in a block hook.
+pub const CHALLENGE_HOOK_WEIGHT: Weight = 5_000_000;
+
+/// The weight of a block hook handling up to the given number of challenges.
+pub struct WeightPerChallenge(u32);
+
+impl<BlockNumber> WeighData<BlockNumber> for WeightPerChallenge {
+	fn weigh_data(&self, _: BlockNumber) -> Weight {
+		self.0.saturating_mul(CHALLENGE_HOOK_WEIGHT)
+	}
+}
+
+/// The weight of decaying a reputation, and ranking its seeder again among the trusted seeders.
+pub const REPUTATION_DECAY_WEIGHT: Weight = 5_000_000;
+
+/// The weight of releasing an evicted seeder from an archive, or of its bond once it hosts none.
+pub const HOSTING_RELEASE_WEIGHT: Weight = 5_000_000;
+
+/// The weight of `on_initialize` issuing up to the given number of challenges, decaying up to
+/// the given number of reputations and releasing up to the given number of hostings.
+pub struct WeightForInitialize(u32, u32, u32);
+
+impl<BlockNumber> WeighData<BlockNumber> for WeightForInitialize {
+	fn weigh_data(&self, _: BlockNumber) -> Weight {
+		self.0.saturating_mul(CHALLENGE_HOOK_WEIGHT)
+			.saturating_add(self.1.saturating_mul(REPUTATION_DECAY_WEIGHT))
+			.saturating_add(self.2.saturating_mul(HOSTING_RELEASE_WEIGHT))
+	}
+}
+
+/// The weight of a call before any of its data is hashed or verified.
+pub const BASE_CALL_WEIGHT: Weight = 1_000_000;
+
+/// The weight of a call walking the archives, or the hosters, of the module.
+pub const SEEDING_CALL_WEIGHT: Weight = 50_000_000;
+
+/// The weight of hashing a byte of chunk content.
+pub const WEIGHT_PER_CHUNK_BYTE: Weight = 50;
+
+/// The weight of hashing or looking up a node of a tree: a leaf, a proof node or a root.
+pub const WEIGHT_PER_NODE: Weight = 100_000;
+
+/// The weight of verifying proofs of the given number of chunk bytes and nodes.
+fn verification_weight(bytes: usize, nodes: usize) -> Weight {
+	BASE_CALL_WEIGHT
+		.saturating_add(WEIGHT_PER_CHUNK_BYTE.saturating_mul(bytes.saturated_into()))
+		.saturating_add(WEIGHT_PER_NODE.saturating_mul(nodes.saturated_into()))
+}
+
+/// The weight of `submit_proof`, scaling with the bytes of the chunks it hashes and the nodes
+/// it verifies.
+pub struct WeightForProofs;
+
+impl WeighData<(&u64, &Vec<Proof>)> for WeightForProofs {
+	fn weigh_data(&self, (_, proofs): (&u64, &Vec<Proof>)) -> Weight {
+		let (bytes, nodes) = proofs.iter().fold((0usize, 0usize), |(bytes, nodes), proof| {
+			let chunk_bytes = proof.chunks.iter().fold(0usize, |sum, chunk| sum.saturating_add(chunk.len()));
+			(
+				bytes.saturating_add(chunk_bytes),
+				nodes.saturating_add(proof.chunks.len()).saturating_add(proof.nodes.len()),
+			)
+		});
+		verification_weight(bytes, nodes)
+	}
+}
+
+impl ClassifyDispatch<(&u64, &Vec<Proof>)> for WeightForProofs {
+	fn classify_dispatch(&self, _: (&u64, &Vec<Proof>)) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl PaysFee<(&u64, &Vec<Proof>)> for WeightForProofs {
+	fn pays_fee(&self, _: (&u64, &Vec<Proof>)) -> bool {
+		true
+	}
+}
+
+/// The arguments of `register_data`.
+type RegistrationArgs<'a> = (&'a (Public, RootHashPayload, H512), &'a Option<ConsistencyProof>);
+
+/// The weight of `register_data` and `register_private_data`, scaling with the roots they hash
+/// and the nodes of their consistency proof.
+pub struct WeightForRegistration;
+
+impl<'a> WeighData<RegistrationArgs<'a>> for WeightForRegistration {
+	fn weigh_data(&self, (merkle_root, consistency_proof): RegistrationArgs<'a>) -> Weight {
+		let proof_nodes = consistency_proof.as_ref()
+			.map(|proof| proof.old_roots.len().saturating_add(proof.nodes.len()))
+			.unwrap_or(0);
+		verification_weight(0, merkle_root.1.children.len().saturating_add(proof_nodes))
+	}
+}
+
+impl<'a> ClassifyDispatch<RegistrationArgs<'a>> for WeightForRegistration {
+	fn classify_dispatch(&self, _: RegistrationArgs<'a>) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl<'a> PaysFee<RegistrationArgs<'a>> for WeightForRegistration {
+	fn pays_fee(&self, _: RegistrationArgs<'a>) -> bool {
+		true
+	}
+}
+
+/// The arguments of `register_drive`.
+type DriveArgs<'a> = (&'a (Public, RootHashPayload, H512), &'a (Public, RootHashPayload, H512), &'a Proof);
+
+/// The weight of `register_drive`, scaling with the roots of both feeds and the proof of the
+/// header.
+pub struct WeightForDrive;
+
+impl<'a> WeighData<DriveArgs<'a>> for WeightForDrive {
+	fn weigh_data(&self, (metadata, content, header_proof): DriveArgs<'a>) -> Weight {
+		let bytes = header_proof.chunks.iter().fold(0usize, |sum, chunk| sum.saturating_add(chunk.len()));
+		let nodes = metadata.1.children.len()
+			.saturating_add(content.1.children.len())
+			.saturating_add(header_proof.chunks.len())
+			.saturating_add(header_proof.nodes.len());
+		verification_weight(bytes, nodes)
+	}
+}
+
+impl<'a> ClassifyDispatch<DriveArgs<'a>> for WeightForDrive {
+	fn classify_dispatch(&self, _: DriveArgs<'a>) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl<'a> PaysFee<DriveArgs<'a>> for WeightForDrive {
+	fn pays_fee(&self, _: DriveArgs<'a>) -> bool {
+		true
+	}
+}
+
+/// The weight of considering an archive for assignment, or of pinning an assigned one.
+pub const WEIGHT_PER_CANDIDATE: Weight = 50_000;
+
+/// The weight of `register_seeder` while the given number of archive indeces are handed out,
+/// see `DatCount`: every archive is considered, and up to as many as asked for are pinned.
+pub struct WeightForSeeding(u64);
+
+impl WeighData<(&SeedRequest,)> for WeightForSeeding {
+	fn weigh_data(&self, (request,): (&SeedRequest,)) -> Weight {
+		let archives = self.0;
+		let pinned = match request {
+			SeedRequest::Archives(count) => archives.min(*count as u64),
+			SeedRequest::Capacity(_) => archives,
+		};
+		SEEDING_CALL_WEIGHT.saturating_add(
+			WEIGHT_PER_CANDIDATE.saturating_mul(archives.saturating_add(pinned).saturated_into())
+		)
+	}
+}
+
+impl ClassifyDispatch<(&SeedRequest,)> for WeightForSeeding {
+	fn classify_dispatch(&self, _: (&SeedRequest,)) -> DispatchClass {
+		DispatchClass::Normal
+	}
+}
+
+impl PaysFee<(&SeedRequest,)> for WeightForSeeding {
+	fn pays_fee(&self, _: (&SeedRequest,)) -> bool {
+		true
+	}
+}
+
+/// The most bytes the envelope of the key of a private archive may have, see `deliver_key`.
+pub const MAX_ENVELOPE_SIZE: usize = 256;
+
+/// Storage deals paying hosters of an archive on behalf of its publisher.
+pub trait StorageDeals {
+	/// The number of hosters the deal of `archive` pays for, `0` without a deal.
+	fn replication(archive: &Public) -> u32;
+	/// What the deal of `archive` pays every hoster per block, `0` without a deal.
+	fn price_per_block(archive: &Public) -> u64;
+	/// `archive` was unregistered, so its deal ends.
+	fn on_unregister(archive: &Public);
+}
+
+impl StorageDeals for () {
+	fn replication(_archive: &Public) -> u32 { 0 }
+	fn price_per_block(_archive: &Public) -> u64 { 0 }
+	fn on_unregister(_archive: &Public) {}
+}
+
+/// The seeders trusted with the archives which require them, ranked by their reputation.
+pub trait TrustedSeeders<AccountId> {
+	/// Whether `seeder` is trusted.
+	fn is_trusted(seeder: &AccountId) -> bool;
+	/// The reputation of the registered `seeder` changed to `reputation`.
+	fn score(seeder: &AccountId, reputation: Reputation);
+	/// `seeder` stopped seeding, or lost its reputation.
+	fn remove(seeder: &AccountId);
+}
+
+impl<AccountId> TrustedSeeders<AccountId> for () {
+	fn is_trusted(_seeder: &AccountId) -> bool { false }
+	fn score(_seeder: &AccountId, _reputation: Reputation) {}
+	fn remove(_seeder: &AccountId) {}
+}
+
+/// The candidate deposit of a `pallet_scored_pool` instance kept by `ScoredPool`, which has to
+/// be nothing: seeders are bonded already, and entered into the pool without being asked.
+pub struct NoCandidateDeposit;
+
+impl<Balance: Zero> Get<Balance> for NoCandidateDeposit {
+	fn get() -> Balance {
+		Zero::zero()
+	}
+}
+
+/// The trusted seeders as the members of a `pallet_scored_pool` instance, scored by their
+/// reputation. Seeders are entered into the pool on their behalf once they have a reputation,
+/// which the instance must not take a deposit for, and the pool takes those with the most as
+/// its members every period. The calls of the instance are not for the runtime to expose, or
+/// anyone could enter the pool without a reputation.
+pub struct ScoredPool<T, I>(PhantomData<(T, I)>);
+
+impl<T, I> ScoredPool<T, I> where
+	T: pallet_scored_pool::Trait<I, Score = Reputation, CandidateDeposit = NoCandidateDeposit>,
+	I: pallet_scored_pool::Instance,
+{
+	// the index of `seeder` in the pool, which scoring and kicking it are checked against.
+	fn pool_index(seeder: &T::AccountId) -> Option<u32> {
+		<pallet_scored_pool::Module<T, I>>::pool().iter()
+			.position(|(candidate, _)| candidate == seeder)
+			.map(|index| index as u32)
+	}
+}
+
+impl<T, I> TrustedSeeders<T::AccountId> for ScoredPool<T, I> where
+	T: pallet_scored_pool::Trait<I, Score = Reputation, CandidateDeposit = NoCandidateDeposit>,
+	I: pallet_scored_pool::Instance,
+{
+	fn is_trusted(seeder: &T::AccountId) -> bool {
+		<pallet_scored_pool::Module<T, I>>::members().binary_search(seeder).is_ok()
+	}
+
+	fn score(seeder: &T::AccountId, reputation: Reputation) {
+		let index = match Self::pool_index(seeder) {
+			Some(index) => index,
+			None => {
+				// the pool has no privileged way in, but there is no deposit to take either.
+				let candidacy = <pallet_scored_pool::Module<T, I>>::submit_candidacy(
+					system::RawOrigin::Signed(seeder.clone()).into()
+				);
+				match candidacy.ok().and_then(|_| Self::pool_index(seeder)) {
+					Some(index) => index,
+					None => return,
+				}
+			},
+		};
+		let _ = <pallet_scored_pool::Module<T, I>>::score(
+			system::RawOrigin::Root.into(),
+			T::Lookup::unlookup(seeder.clone()),
+			index,
+			reputation,
+		);
+	}
+
+	fn remove(seeder: &T::AccountId) {
+		if let Some(index) = Self::pool_index(seeder) {
+			let _ = <pallet_scored_pool::Module<T, I>>::kick(
+				system::RawOrigin::Root.into(),
+				T::Lookup::unlookup(seeder.clone()),
+				index,
+			);
+		}
+	}
+}
+
+#[cfg_attr(feature = "std", derive(TypeDefinition))]
+#[derive(Decode, PartialEq, Eq, Encode, Clone, RuntimeDebug)]
+pub struct Node {
+	/// The flat-tree index of the node.
+	pub index: u64,
+	/// The hash of the chunk, for a leaf, or of the two children, for a parent.
+	pub hash: H256,
+	/// The number of data bytes under the node.
+	pub size: u64
+}
+
+impl Node {
+	//find a node by index in a list of proof nodes
+	fn find(nodes : &[Node], index : u64) -> Option<&Node> {
+		nodes.iter().find(|node| node.index == index)
+	}
+}
+
+/// Proves the challenged chunks of an archive against its registered Merkle root.
+#[cfg_attr(feature = "std", derive(TypeDefinition))]
+#[derive(Decode, PartialEq, Eq, Encode, Clone, Default, RuntimeDebug)]
+pub struct Proof {
+	/// The content of every challenged chunk, in ascending leaf order.
+	pub chunks: Vec<Vec<u8>>,
+	/// The nodes the chunks do not give: the siblings off their paths, and the roots of the
+	/// tree no path reaches, see `offchain::proof_nodes`.
+	pub nodes: Vec<Node>
+}
+
+
+//https://datprotocol.github.io/how-dat-works/#hashes-and-signatures
+// the payloads are hashed as laid out by the spec, see the `hashing` module.
+#[cfg_attr(feature = "std", derive(TypeDefinition))]
+#[derive(Decode, PartialEq, Eq, Encode, Clone, RuntimeDebug)]
+pub struct ChunkHashPayload {
+	hash_type: u8, //0
+	chunk_length: u64,
+	chunk_content: Vec<u8>
+}
+
+
+#[cfg_attr(feature = "std", derive(TypeDefinition))]
+#[derive(Decode, PartialEq, Eq, Encode, Clone, RuntimeDebug)]
+pub struct ParentHashPayload {
+	hash_type: u8, //1
+	total_length: u64,
+	child_hashes: [H256; 2]
+}
+
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, TypeDefinition))]
+#[derive(Decode, PartialEq, Eq, Encode, Clone, Copy, RuntimeDebug)]
+pub struct ParentHashInRoot {
+	/// The hash of the root node.
+	pub hash: H256,
+	/// The flat-tree index of the root node.
+	pub hash_number: u64,
+	/// The number of data bytes under the root node.
+	pub total_length: u64
+}
+
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, TypeDefinition))]
+#[derive(Decode, PartialEq, Eq, Encode, Clone, RuntimeDebug)]
+pub struct RootHashPayload {
+	/// Always `hashing::ROOT_TYPE`, ignored when registering.
+	pub hash_type: u8, //2
+	/// The roots of the tree, in ascending flat-tree index order.
+	pub children: Vec<ParentHashInRoot>
+}
+
+trait HashPayload {
+	fn hash(&self) -> H256;
+}
+
+impl HashPayload for RootHashPayload {
+	fn hash(&self) -> H256 {
+		hashing::tree(&self.children)
+	}
+}
+impl HashPayload for ParentHashPayload {
+	fn hash(&self) -> H256 {
+		hashing::parent(&self.child_hashes[0], &self.child_hashes[1], self.total_length)
+	}
+}
+impl HashPayload for ChunkHashPayload {
+	fn hash(&self) -> H256 {
+		hashing::leaf(&self.chunk_content)
+	}
+}
+
+/// Shows that a registered tree is extended by an update: every root the tree was signed with
+/// must be an ancestor of one of the roots of the update.
+#[cfg_attr(feature = "std", derive(TypeDefinition))]
+#[derive(Decode, PartialEq, Eq, Encode, Clone, RuntimeDebug)]
+pub struct ConsistencyProof {
+	/// The roots of the registered tree, as they were signed.
+	pub old_roots: Vec<ParentHashInRoot>,
+	/// The nodes linking every old root to one of the new roots.
+	pub nodes: Vec<Node>
+}
+
+/// What a seeder asks to be assigned in `register_seeder`.
+#[cfg_attr(feature = "std", derive(TypeDefinition))]
+#[derive(Decode, PartialEq, Eq, Encode, Clone, RuntimeDebug)]
+pub enum SeedRequest {
+	/// Up to this many archives.
+	Archives(u32),
+	/// As many archives as fit in this many bytes.
+	Capacity(DatSize),
+}
+
+/// Draws without replacement of positions, each as likely as its weight, in logarithmic time:
+/// the weights are kept in a Fenwick tree, where every node sums the weights of the positions
+/// below it down to the position its lowest bit clears.
+struct WeightedDraw {
+	weights: Vec<u128>,
+	// one based, with the sums of the weights.
+	tree: Vec<u128>,
+	total: u128,
+}
+
+impl WeightedDraw {
+	fn new(weights: Vec<u128>) -> Self {
+		let mut tree = vec![0; weights.len() + 1];
+		for (position, weight) in weights.iter().enumerate() {
+			let node = position + 1;
+			tree[node] += weight;
+			let parent = node + (node & node.wrapping_neg());
+			if parent < tree.len() {
+				tree[parent] += tree[node];
+			}
+		}
+		let total = weights.iter().sum();
+		WeightedDraw { weights, tree, total }
+	}
+
+	/// Whether every position was drawn, or had no weight.
+	fn is_empty(&self) -> bool {
+		self.total == 0
+	}
+
+	/// Draw the position `random` falls into, out of those not drawn yet, and take it out.
+	fn take(&mut self, random: u64) -> usize {
+		let mut rest = u128::from(random) % self.total;
+		// the most positions, from the first, whose weights sum to no more than `rest`.
+		let mut skipped = 0;
+		let mut step = (self.tree.len() - 1).next_power_of_two();
+		while step > 0 {
+			let node = skipped + step;
+			if node < self.tree.len() && self.tree[node] <= rest {
+				skipped = node;
+				rest -= self.tree[node];
+			}
+			step /= 2;
+		}
+		let weight = mem::replace(&mut self.weights[skipped], 0);
+		let mut node = skipped + 1;
+		while node < self.tree.len() {
+			self.tree[node] -= weight;
+			node += node & node.wrapping_neg();
+		}
+		self.total -= weight;
+		skipped
+	}
+}
+
+/// What a number drawn for a challenge decides, see `Module::draw`.
+#[derive(Decode, PartialEq, Eq, Encode, Clone, Copy, RuntimeDebug)]
+pub enum Draw {
+	/// The challenged seeder, drawn once more for every seeder skipped for having nothing to prove.
+	Seeder,
+	/// A challenged chunk, drawn once per sample.
+	Chunk,
+	/// An attestor, drawn once per round.
+	Attestor,
+	/// How many blocks the challenged seeder has to answer.
+	Deadline,
+}
+
+/// What came of trying to issue a challenge, see `Module::issue_challenge`.
+#[derive(PartialEq, Eq, Clone, Copy, RuntimeDebug)]
+enum ChallengeIssue {
+	/// The challenge was issued.
+	Issued,
+	/// The drawn seeder had no archive to prove, so another one may be drawn.
+	Skipped,
+	/// No challenge can be issued this block.
+	Stopped,
+}
+
+#[cfg_attr(feature = "std", derive(TypeDefinition))]
+#[derive(Decode, PartialEq, Eq, Encode, Clone, RuntimeDebug)]
+pub struct Attestation {
+	//todo, actually decide format
+	location: u8,
+	latency: Option<u8> //none when the data could not be retrieved.
+}
+
+type DatIdIndex = u64;
+type UserIdIndex = u64; 
+type DatSize = u64;
+/// The standing of a seeder, see `Module::reputation`.
+pub type Reputation = u32;
+
+decl_event!(
+	pub enum Event<T> 
+	where
+	AccountId = <T as system::Trait>::AccountId,
+	BlockNumber = <T as system::Trait>::BlockNumber,
+	Hash = <T as system::Trait>::Hash,
+	Balance = BalanceOf<T>
+	{
+		SomethingStored(DatIdIndex, Public),
+		SomethingUnstored(DatIdIndex, Public),
+		// the seed a challenge is drawn from: (challenge, seed)
+		ChallengeSeed(u64, Hash),
+		Challenge(AccountId,BlockNumber),
+		// the challenged archives the seeder failed to prove.
+		ChallengeFailed(AccountId, Vec<Public>),
+		NewPin(AccountId, Public),
+		Attest(AccountId, Attestation),
+		// attestors asked to attest a challenge: (challenge, attestors)
+		AttestorsSelected(u64, Vec<AccountId>),
+		AttestationsPassed(AccountId, Public),
+		AttestationsFailed(AccountId, Public),
+		AttestorRewarded(AccountId, Balance),
+		// an update did not extend the registered tree: (archive, registered length, update length)
+		UpdateRejected(Public, u64, u64),
+		SeederSlashed(AccountId, Balance),
+		SeederRewarded(AccountId, Balance),
+		// the bytes of archives a seeder hosts, out of those it declared: (seeder, used, declared)
+		CapacityUsed(AccountId, DatSize, DatSize),
+		// a hyperdrive was registered: (metadata feed, content feed)
+		DriveRegistered(Public, Public),
+		// the key of a private archive was delivered to a hoster: (archive, hoster)
+		KeyDelivered(Public, AccountId),
+	}
+);
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+		PermissionError,
+		UnsignedProof,
+		VerificationFailed,
+		ProvesWrongChunk,
+		MissingLeaf,
+		ChunkHashVerificationFailed,
+		RootHashVerificationFailed,
+		InvalidState,
+		InvalidTreeSize,
+		MissingSibling,
+		MissingRoot,
+		ParentHashVerificationFailed,
+		InclusionVerificationFailed,
+		StaleRoot,
+		MissingConsistencyProof,
+		ConsistencyVerificationFailed,
+		InsufficientBond,
+		NotBonded,
+		NotAttestor,
+		NothingToProve,
+		ProofTooLarge,
+		TooManyRoots,
+		DriveAlreadyRegistered,
+		InvalidDriveHeader,
+		NotHoster,
+		EnvelopeTooLarge,
+		CapacityInUse,
+		SeederEvicted
+    }
+}
+
+
+// Dat related storage items.
+decl_storage! {
+	trait Store for Module<T: Trait> as DatVerify {
+		// One more than the highest archive index handed out so far
+		pub DatCount get(dat_count): DatIdIndex;
+		// A stack of the indeces freed by unregistered archives, reused before new ones
+		pub FreeDatIds: map hasher(twox_256) u64 => DatIdIndex;
+		pub FreeDatIdCount get(free_dat_id_count): u64;
+		// Each dat archive has a public key
+		pub DatKey get(public_key): map hasher(twox_256) DatIdIndex => Public;
+		// Each dat archive public key has an index
+		pub DatIndex get(dat_index): map hasher(blake2_256) Public => Option<DatIdIndex>;
+		// Each dat archive has a tree size, in bytes
+		pub TreeSize get(tree_size): map hasher(blake2_256) Public => DatSize;
+		// Each dat archive has a signed tree length, in chunks
+		pub TreeLength get(tree_length): map hasher(blake2_256) Public => u64;
+		// Each dat archive counts the updates made to its merkle root
+		pub TreeVersion get(tree_version): map hasher(blake2_256) Public => u64;
+		// each dat archive has a merkle root
+		pub MerkleRoot get(merkle_root): map hasher(blake2_256) Public => (H256, Signature);
+		// a hyperdrive is a metadata feed and the content feed it names, hosted together
+		pub ContentFeed get(content_feed): map hasher(blake2_256) Public => Option<Public>;
+		pub MetadataFeed get(metadata_feed): map hasher(blake2_256) Public => Option<Public>;
+		// private archives are registered by registration key, see `register_private_data`
+		pub PrivateArchives get(is_private): map hasher(blake2_256) Public => bool;
+		// (private archive, hoster) => the key of the archive, encrypted to the hoster
+		pub KeyEnvelopes get(key_envelope): double_map hasher(blake2_256) Public, hasher(blake2_256) T::AccountId => Option<Vec<u8>>;
+		// users are put into an array, which removals keep without gaps
+		pub UserCount get(user_count): u64;
+		pub Users get(user): map hasher(twox_256) UserIdIndex => T::AccountId;
+		pub UserIds get(user_id): map hasher(blake2_256) T::AccountId => Option<UserIdIndex>;
+		// (user, dat) => the slot of the dat among those the user seeds
+		pub UsersStorage: double_map hasher(blake2_256) T::AccountId, hasher(blake2_256) Public => Option<u32>;
+		pub UsersStorageAt: double_map hasher(blake2_256) T::AccountId, hasher(twox_256) u32 => Public;
+		pub UsersStorageCount get(hosted_count): map hasher(blake2_256) T::AccountId => u32;
+		// (dat, user) => the slot of the user among those pinning the dat
+		pub DatHosters: double_map hasher(blake2_256) Public, hasher(blake2_256) T::AccountId => Option<u32>;
+		pub DatHostersAt: double_map hasher(blake2_256) Public, hasher(twox_256) u32 => T::AccountId;
+		pub DatHostersCount get(hoster_count): map hasher(blake2_256) Public => u32;
+		// each user has a mapping and vec of dats they want seeded
+		pub UserRequestsMap: map hasher(blake2_256) Public => T::AccountId;
+		// each seeder has a bond reserved while seeding, less what was slashed
+		pub SeederBonds get(seeder_bond): map hasher(blake2_256) T::AccountId => BalanceOf<T>;
+		// the bytes of archives a seeder declared it has room for, if it did
+		pub SeederCapacity get(seeder_capacity): map hasher(blake2_256) T::AccountId => Option<DatSize>;
+		// the reputation of every seeder who has one, kept after it stops seeding until it decays
+		pub Reputations get(reputation): map hasher(blake2_256) T::AccountId => Reputation;
+		// the seeders with a reputation are put into an array too, for its decay to go on over blocks
+		pub ReputedCount get(reputed_count): u64;
+		pub Reputed: map hasher(twox_256) u64 => T::AccountId;
+		pub ReputedIds: map hasher(blake2_256) T::AccountId => Option<u64>;
+		// the reputations left to decay this period, those at the indeces below it
+		pub DecayCursor get(decay_cursor): u64;
+		// seeders who failed a challenge, or left, released from their archives a few at a time, oldest first
+		pub Evictions get(evictions): Vec<T::AccountId>;
+		// archives only assigned to trusted seeders, by the key of the archive, or of the metadata feed
+		pub TrustedOnly get(is_trusted_only): map hasher(blake2_256) Public => bool;
+
+		// current check condition
+		pub ChallengeIndex: u64;
+		pub UserIndex: u64;
+		// Challenge => User
+		pub ChallengeMap: linked_map hasher(twox_256) u64 => u64;
+		// Challenge => (the dats and leaf indeces to verify, sorted, and the deadline)
+		pub SelectedChallenges: map hasher(twox_256) u64 => (Vec<(Public, u64)>, T::BlockNumber);
+		// Block => challenges expiring at the end of it
+		pub ChallengeExpiries: map hasher(twox_256) T::BlockNumber => Vec<u64>;
+		pub SelectedUsers: map hasher(twox_256) u64 => T::AccountId;
+		// (index, challenge count)
+		pub SelectedUserIndex: map hasher(twox_256) T::AccountId => (u64, u64);
+		// index of `SelectedUserIndex` => the challenges open to the user, oldest first
+		pub OpenChallenges: map hasher(twox_256) u64 => Vec<u64>;
+		pub Nonce: u64;
+
+		// attestor => relevant challenge
+		// attestors and attestations are ephemeral
+		pub Attestors: map hasher(twox_256) T::AccountId => u64;
+		// challenge => ([expected attestors], [seen attestations])
+		pub ChallengeAttestations: map hasher(twox_256) u64 =>(
+			Vec<T::AccountId>,
+			Vec<(T::AccountId, Attestation)>
+		);
+		// challenge => (attested seeder, archive, block the attestations expire at)
+		pub AttestationRounds: map hasher(twox_256) u64 => (T::AccountId, Public, T::BlockNumber);
+
+		// the version of the storage layout, see the `migration` module.
+		pub StorageVersion: u32;
+	}
+	add_extra_genesis {
+		// (publisher, (key, roots, signature)) of the archives, registered as by `register_data`.
+		config(archives): Vec<(T::AccountId, (Public, RootHashPayload, H512))>;
+		// (seeder, keys of the genesis archives it pins), bonded as by `register_seeder`.
+		config(seeders): Vec<(T::AccountId, Vec<Public>)>;
+		build(|config: &GenesisConfig<T>| {
+			for (publisher, merkle_root) in &config.archives {
+				<Module<T>>::register_data(
+					T::Origin::from(Some(publisher.clone()).into()),
+					merkle_root.clone(),
+					None,
+				).expect("genesis archives must be signed by their key");
+			}
+			for (seeder, archives) in &config.seeders {
+				assert!(
+					archives.iter().all(|archive| <DatIndex>::exists(archive)),
+					"genesis seeders can only pin genesis archives",
+				);
+				<Module<T>>::pin_archives(seeder, archives.clone())
+					.expect("genesis seeders must afford the seeder bond");
+			}
+			StorageVersion::put(migration::CURRENT_VERSION);
+		});
+	}
+}
+
+decl_module!{
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+		type Error = Error<T>;
+		
+		#[weight = WeightForInitialize(
+			T::ChallengesPerBlock::get(),
+			T::ReputationsDecayedPerBlock::get(),
+			T::HostingsReleasedPerBlock::get(),
+		)]
+		fn on_initialize(n: T::BlockNumber) {
+			migration::perform_migrations::<T>();
+			let period = T::ReputationPeriod::get();
+			// a decay not done by the next period is given up for that of the next period.
+			if !period.is_zero() && (n % period).is_zero() {
+				<DecayCursor>::put(<ReputedCount>::get());
+			}
+			Self::decay_reputations(T::ReputationsDecayedPerBlock::get());
+			Self::release_evicted(T::HostingsReleasedPerBlock::get());
+			// a skipped seeder takes up the place of a challenge, so the hook weight still holds.
+			let mut skipped = 0;
+			for _ in 0..T::ChallengesPerBlock::get() {
+				match Self::issue_challenge(n, skipped) {
+					ChallengeIssue::Issued => skipped = 0,
+					ChallengeIssue::Skipped => skipped += 1,
+					ChallengeIssue::Stopped => break,
+				}
+			}
+		}
+
+		// attest whether a challenged peer is online and behaving correctly on the dat network
+		#[weight = SimpleDispatchInfo::FixedNormal(BASE_CALL_WEIGHT)]
+		fn submit_attestation(origin, attestation: Attestation) {
+			let attestor = ensure_signed(origin)?;
+			ensure!(<Attestors<T>>::exists(&attestor), Error::<T>::NotAttestor);
+			let challenge_index = <Attestors<T>>::take(&attestor);
+			let (expected, mut seen) = <ChallengeAttestations<T>>::get(challenge_index);
+			seen.push((attestor.clone(), attestation.clone()));
+			<ChallengeAttestations<T>>::insert(challenge_index, (expected, &seen));
+			Self::deposit_event(RawEvent::Attest(attestor, attestation));
+			Self::tally_attestations(challenge_index, &seen);
+		}
+
+		#[weight = SimpleDispatchInfo::FixedOperational(BASE_CALL_WEIGHT)]
+		fn force_clear_challenge(origin, account: T::AccountId, challenge_index: u64){
+			T::ForceOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?; 
+			let account_index = <ChallengeMap>::get(&challenge_index);
+			let (_, count) = <SelectedUserIndex<T>>::get(&account);
+			match count {
+				1 => {	
+					<SelectedUsers<T>>::remove(account_index);
+					<SelectedUserIndex<T>>::remove(account);
+				},
+				_ => <SelectedUserIndex<T>>::insert(account, (account_index, count-1))
+			}
+			Self::close_challenge(challenge_index, account_index);
+		}
+		
+		
+		// prove every challenged chunk, with one proof per challenged archive, in the order of
+		// `challenged_archives`. The proofs must keep to the size limits of the module.
+		#[weight = WeightForProofs]
+		fn submit_proof(origin, challenge_index: u64, proofs: Vec<Proof>) {
+			let account = ensure_signed(origin)?;
+			Self::ensure_proofs_within_limits(&proofs)?;
+			let account_index = <ChallengeMap>::get(&challenge_index);
+			ensure!(
+				account == <SelectedUsers<T>>::get(&account_index),
+				Error::<T>::PermissionError
+			);
+			let (chunks, _) = <SelectedChallenges<T>>::get(&challenge_index);
+			let archives = Self::challenged_archives(&chunks);
+			ensure!(proofs.len() == archives.len(), Error::<T>::ProvesWrongChunk);
+			let mut proved = 0;
+			for ((archive, leaves), proof) in archives.iter().zip(proofs.iter()) {
+				// archives unregistered since the challenge need no proof.
+				if !<DatHosters<T>>::exists(archive, &account) {
+					continue;
+				}
+				Self::verify_chunks(archive, leaves, proof)?;
+				proved += 1;
+			}
+			// a challenge left without hosted archives lapses, unpaid, see `expire_challenge`.
+			ensure!(proved > 0, Error::<T>::NothingToProve);
+			let temporary_root = system::RawOrigin::Root;
+			match Self::force_clear_challenge(temporary_root.into(), account.clone(), challenge_index) {
+				Ok(x) => x,
+				Err(x) => fail!(x),
+			}
+			// an empty pot pays no reward, the proof still counts.
+			let _ = Self::reward_seeder(system::RawOrigin::Root.into(), account.clone());
+			Self::raise_reputation(&account, T::ProofReputation::get());
+			// else let the user try again until time limit
+		}
+
+		// Submit or update a piece of data that you want to have users copy, optionally provide chunk for execution.
+		// Updates must extend the registered tree, and come with a consistency proof showing it.
+		#[weight = WeightForRegistration]
+		fn register_data(
+			origin,
+			merkle_root: (Public, RootHashPayload, H512),
+			consistency_proof: Option<ConsistencyProof>
+		)
+		{
+			let account = ensure_signed(origin)?;
+			Self::ensure_roots_within_limits(&merkle_root.1.children, consistency_proof.as_ref())?;
+			let pubkey = merkle_root.0;
+			// private archives are updated by their publisher only, see `register_private_data`.
+			ensure!(!<PrivateArchives>::get(&pubkey), Error::<T>::PermissionError);
+			let (_, tree_length) = Self::verify_signed_roots(&merkle_root)?;
+			if <MerkleRoot>::exists(&pubkey) {
+				// the signed roots are public, so only the publisher may submit them.
+				ensure!(<UserRequestsMap<T>>::get(&pubkey) == account, Error::<T>::PermissionError);
+				Self::verify_update(&pubkey, &merkle_root.1.children, tree_length, consistency_proof)?;
+			}
+			let temporary_root = system::RawOrigin::Root;
+			// the rest of the logic is already in force_register_data so, just call that function.
+			match Self::force_register_data(temporary_root.into(), account, merkle_root) {
+				Ok(x) => x,
+				Err(x) => fail!(x),
+			}
+		}
+
+		// Register a hyperdrive: its metadata feed, its content feed and the proof of the header
+		// of the metadata feed naming the content feed, see the `hyperdrive` module. Both feeds
+		// are then hosted, and unregistered, together, and updated one by one with `register_data`.
+		#[weight = WeightForDrive]
+		fn register_drive(
+			origin,
+			metadata: (Public, RootHashPayload, H512),
+			content: (Public, RootHashPayload, H512),
+			header_proof: Proof
+		)
+		{
+			let account = ensure_signed(origin)?;
+			Self::ensure_drive_within_limits(&metadata, &content, &header_proof)?;
+			let (metadata_key, content_key) = (metadata.0, content.0);
+			ensure!(
+				metadata_key != content_key
+					&& !<MerkleRoot>::exists(&metadata_key)
+					&& !<MerkleRoot>::exists(&content_key),
+				Error::<T>::DriveAlreadyRegistered
+			);
+			let (metadata_root, metadata_length) = Self::verify_signed_roots(&metadata)?;
+			Self::verify_signed_roots(&content)?;
+			// the header is the first chunk of the metadata feed.
+			Self::verify_leaves(&[0], &header_proof, metadata_length, metadata_root)?;
+			ensure!(
+				hyperdrive::content_key(&header_proof.chunks[0]) == Some(content_key),
+				Error::<T>::InvalidDriveHeader
+			);
+			for merkle_root in vec![metadata, content] {
+				match Self::force_register_data(system::RawOrigin::Root.into(), account.clone(), merkle_root) {
+					Ok(x) => x,
+					Err(x) => fail!(x),
+				}
+			}
+			<ContentFeed>::insert(&metadata_key, &content_key);
+			<MetadataFeed>::insert(&content_key, &metadata_key);
+			Self::deposit_event(RawEvent::DriveRegistered(metadata_key, content_key));
+		}
+
+		// Register, or update, a private archive by its registration key, see
+		// `hashing::registration_seed`. The feed key stays off chain: the roots are signed by the
+		// registration key instead, which only the holder of the secret key of the feed derives.
+		// Only the publisher may update them.
+		#[weight = WeightForRegistration]
+		fn register_private_data(
+			origin,
+			merkle_root: (Public, RootHashPayload, H512),
+			consistency_proof: Option<ConsistencyProof>
+		)
+		{
+			let account = ensure_signed(origin)?;
+			Self::ensure_roots_within_limits(&merkle_root.1.children, consistency_proof.as_ref())?;
+			let registration_key = merkle_root.0;
+			let (_, tree_length) = Self::verify_signed_roots(&merkle_root)?;
+			if <MerkleRoot>::exists(&registration_key) {
+				ensure!(
+					<PrivateArchives>::get(&registration_key)
+						&& <UserRequestsMap<T>>::get(&registration_key) == account,
+					Error::<T>::PermissionError
+				);
+				Self::verify_update(&registration_key, &merkle_root.1.children, tree_length, consistency_proof)?;
+			}
+			match Self::force_register_data(system::RawOrigin::Root.into(), account, merkle_root) {
+				Ok(x) => x,
+				Err(x) => fail!(x),
+			}
+			<PrivateArchives>::insert(&registration_key, true);
+		}
+
+		// Hand `hoster` the feed key of the private `archive`, in an envelope encrypted to it off
+		// chain. Only the publisher delivers keys, and only to the hosters of the archive.
+		#[weight = SimpleDispatchInfo::FixedNormal(BASE_CALL_WEIGHT)]
+		fn deliver_key(origin, archive: Public, hoster: T::AccountId, envelope: Vec<u8>) {
+			let account = ensure_signed(origin)?;
+			ensure!(envelope.len() <= MAX_ENVELOPE_SIZE, Error::<T>::EnvelopeTooLarge);
+			ensure!(
+				<PrivateArchives>::get(&archive) && <UserRequestsMap<T>>::get(&archive) == account,
+				Error::<T>::PermissionError
+			);
+			ensure!(<DatHosters<T>>::exists(&archive, &hoster), Error::<T>::NotHoster);
+			<KeyEnvelopes<T>>::insert(&archive, &hoster, envelope);
+			Self::deposit_event(RawEvent::KeyDelivered(archive, hoster));
+		}
+
+		// Have `archive`, or the hyperdrive it is the metadata feed of, assigned only to trusted
+		// seeders from now on, or to any seeder again. Its current hosters keep it.
+		#[weight = SimpleDispatchInfo::FixedNormal(BASE_CALL_WEIGHT)]
+		fn require_trusted(origin, archive: Public, required: bool) {
+			let account = ensure_signed(origin)?;
+			ensure!(
+				<DatIndex>::exists(&archive) && <UserRequestsMap<T>>::get(&archive) == account,
+				Error::<T>::PermissionError
+			);
+			match required {
+				true => <TrustedOnly>::insert(&archive, true),
+				false => <TrustedOnly>::remove(&archive),
+			}
+		}
+
+		//debug method when you don't have valid data for register_data, no validity checks, only root.
+		#[weight = SimpleDispatchInfo::FixedOperational(
+			verification_weight(0, T::MaxRootChildren::get() as usize)
+		)]
+		fn force_register_data(
+			origin,
+			account: T::AccountId,
+			merkle_root: (Public, RootHashPayload, H512)
+		)
+		{
+			T::ForceOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?;
+			Self::ensure_roots_within_limits(&merkle_root.1.children, None)?;
+			let pubkey = merkle_root.0;
+			let sig = Signature::from_h512(merkle_root.2);
+			let mut tree_size : u64 = u64::min_value();
+			let root_hash = merkle_root.1.hash(); //todo: do not calculate twice!
+			let tree_length = Self::tree_length_of(&merkle_root.1.children)
+				.ok_or(Error::<T>::InvalidTreeSize)?;
+			for child in merkle_root.1.children {
+				tree_size += child.total_length;
+			}
+			ensure!(
+				tree_size >= 1,
+				Error::<T>::InvalidTreeSize
+			);
+			let index = match <DatIndex>::get(&pubkey) {
+				Some(index) => {
+					<TreeVersion>::mutate(&pubkey, |version| *version += 1);
+					index
+				},
+				None => {
+					//register new unknown dats
+					let index = Self::allocate_dat_index();
+					<DatKey>::insert(&index, &pubkey);
+					<DatIndex>::insert(&pubkey, index);
+					index
+				},
+			};
+			<MerkleRoot>::insert(&pubkey, (root_hash, sig));
+			<TreeSize>::insert(&pubkey, tree_size);
+			<TreeLength>::insert(&pubkey, tree_length);
+			<UserRequestsMap<T>>::insert(&pubkey, &account);
+			Self::deposit_event(RawEvent::SomethingStored(index, pubkey));
+		}
+
+		// prove the length of an archive registered before lengths were kept, see the
+		// `migration` module, with the roots its registered root hash was made of. Archives
+		// without a length are not challenged, and their publisher cannot update them.
+		#[weight = SimpleDispatchInfo::FixedNormal(
+			verification_weight(0, T::MaxRootChildren::get() as usize)
+		)]
+		fn backfill_tree_length(origin, pubkey: Public, roots: Vec<ParentHashInRoot>) {
+			ensure_signed(origin)?;
+			ensure!(
+				<DatIndex>::get(&pubkey).is_some() && <TreeLength>::get(&pubkey) == 0,
+				Error::<T>::InvalidState
+			);
+			Self::ensure_roots_within_limits(&roots, None)?;
+			let tree_length = Self::tree_length_of(&roots).ok_or(Error::<T>::InvalidTreeSize)?;
+			ensure!(
+				hashing::tree(&roots) == <MerkleRoot>::get(&pubkey).0,
+				Error::<T>::RootHashVerificationFailed
+			);
+			<TreeLength>::insert(&pubkey, tree_length);
+		}
+
+		//user stops requesting others pin their data
+		#[weight = SimpleDispatchInfo::FixedNormal(SEEDING_CALL_WEIGHT)]
+		fn unregister_data(origin, index: DatIdIndex){
+			let account = ensure_signed(origin)?;
+			let pubkey = <DatKey>::get(index);
+			//only allow owner to unregister
+			ensure!(
+				<DatKey>::exists(index) && <UserRequestsMap<T>>::get(&pubkey) == account,
+				Error::<T>::PermissionError
+			);
+			// the feeds of a hyperdrive go together.
+			let linked = <ContentFeed>::take(&pubkey).or_else(|| <MetadataFeed>::take(&pubkey));
+			Self::unregister_feed(index, pubkey);
+			if let Some(linked) = linked {
+				<ContentFeed>::remove(&linked);
+				<MetadataFeed>::remove(&linked);
+				if let Some(linked_index) = <DatIndex>::get(&linked) {
+					Self::unregister_feed(linked_index, linked);
+				}
+			}
+		}
+
+		// User requests dats for them to pin, see `assign_archives`.
+		#[weight = WeightForSeeding(<DatCount>::get())]
+		fn register_seeder(origin, request: SeedRequest) {
+			let account = ensure_signed(origin)?;
+			ensure!(!<Evictions<T>>::get().contains(&account), Error::<T>::SeederEvicted);
+			let assigned = Self::assign_archives(&account, &request);
+			if !assigned.is_empty() {
+				Self::pin_archives(&account, assigned)?;
+			}
+			if let Some(capacity) = <SeederCapacity<T>>::get(&account) {
+				Self::deposit_event(RawEvent::CapacityUsed(account.clone(), Self::used_capacity(&account), capacity));
+			}
+		}
+
+		// Declare, or update, the bytes of archives the seeder has room for. The bond is topped
+		// up, or released, to `SeederBond` and `BondPerGigabyte` for every gigabyte of it, and
+		// the capacity cannot be lowered below what the hosted archives take.
+		#[weight = SimpleDispatchInfo::FixedNormal(SEEDING_CALL_WEIGHT)]
+		fn declare_capacity(origin, capacity: DatSize) {
+			let account = ensure_signed(origin)?;
+			let used = Self::used_capacity(&account);
+			ensure!(capacity >= used, Error::<T>::CapacityInUse);
+			Self::bond_seeder(&account, Self::capacity_bond(capacity))?;
+			<SeederCapacity<T>>::insert(&account, capacity);
+			Self::deposit_event(RawEvent::CapacityUsed(account, used, capacity));
+		}
+
+		// Stop seeding. The seeder is no longer challenged, and is released from its archives,
+		// and then its bond, a few at a time like an evicted seeder, see `release_evicted`.
+		#[weight = SimpleDispatchInfo::FixedNormal(SEEDING_CALL_WEIGHT)]
+		fn unregister_seeder(origin) {
+			let account = ensure_signed(origin)?;
+			Self::evict_seeder(&account);
+		}
+
+		// slash a fraction of the seeder's bond into the reward pot.
+		#[weight = SimpleDispatchInfo::FixedOperational(BASE_CALL_WEIGHT)]
+		fn punish_seeder(origin, punished: T::AccountId) {
+			ensure_root(origin)?;
+			ensure!(<SeederBonds<T>>::exists(&punished), Error::<T>::NotBonded);
+			let bond = <SeederBonds<T>>::get(&punished);
+			let (imbalance, _) = T::Currency::slash_reserved(&punished, T::ChallengeSlash::get() * bond);
+			let slashed = imbalance.peek();
+			<SeederBonds<T>>::insert(&punished, bond.saturating_sub(slashed));
+			T::Currency::resolve_creating(&Self::pot_account(), imbalance);
+			Self::deposit_event(RawEvent::SeederSlashed(punished, slashed));
+		}
+
+		// pay the seeder the challenge reward, grown with its reputation, out of the pot.
+		#[weight = SimpleDispatchInfo::FixedOperational(BASE_CALL_WEIGHT)]
+		fn reward_seeder(origin, rewarded: T::AccountId) {
+			ensure_root(origin)?;
+			let reward = Self::with_reputation(&rewarded, T::ChallengeReward::get());
+			T::Currency::transfer(&Self::pot_account(), &rewarded, reward, ExistenceRequirement::KeepAlive)?;
+			Self::deposit_event(RawEvent::SeederRewarded(rewarded, reward));
+		}
+
+		// pay an attestor the attestation reward, grown with its reputation, out of the pot.
+		#[weight = SimpleDispatchInfo::FixedOperational(BASE_CALL_WEIGHT)]
+		fn reward_attestor(origin, rewarded: T::AccountId) {
+			ensure_root(origin)?;
+			let reward = Self::with_reputation(&rewarded, T::AttestationReward::get());
+			T::Currency::transfer(&Self::pot_account(), &rewarded, reward, ExistenceRequirement::KeepAlive)?;
+			Self::deposit_event(RawEvent::AttestorRewarded(rewarded, reward));
+		}
+
+		// only the challenges due this block are touched, see `schedule_expiry`.
+		#[weight = WeightPerChallenge(T::ChallengesPerBlock::get())]
+		fn on_finalize(n: T::BlockNumber) {
+			for challenge_index in <ChallengeExpiries<T>>::take(n) {
+				if <ChallengeMap>::exists(challenge_index) {
+					Self::expire_challenge(challenge_index);
+				}
+				// attestations short of a quorum by their deadline decide nothing.
+				if <AttestationRounds<T>>::exists(challenge_index) {
+					Self::clear_attestations(challenge_index);
+				}
+			}
+		}
+
+		// answer the challenges of the seeders in the local keystore, see the `offchain` module.
+		fn offchain_worker() {
+			debug::RuntimeLogger::init();
+			Self::answer_challenges();
+		}
+
+		//end Module
+	}
+}
+
+/// Public APIs provided by the dat_verify module.
+impl<T: Trait> Module<T> {
+	/// The archives hosted by `account`.
+	pub fn archives_hosted_by(account: T::AccountId) -> Vec<Public> {
+		(0..<UsersStorageCount<T>>::get(&account)).map(|slot| <UsersStorageAt<T>>::get(&account, slot)).collect()
+	}
+
+	/// The accounts hosting `archive`.
+	pub fn hosters_of(archive: Public) -> Vec<T::AccountId> {
+		(0..<DatHostersCount>::get(&archive)).map(|slot| <DatHostersAt<T>>::get(&archive, slot)).collect()
+	}
+
+	/// The challenges `account` has yet to answer.
+	pub fn challenges_for(account: T::AccountId) -> Vec<ChallengeInfo<T::BlockNumber>> {
+		if !<SelectedUserIndex<T>>::exists(&account) {
+			return Vec::new();
+		}
+		let (user_index, _) = <SelectedUserIndex<T>>::get(&account);
+		<OpenChallenges>::get(user_index).into_iter()
+			.map(|index| {
+				let (chunks, deadline) = <SelectedChallenges<T>>::get(index);
+				ChallengeInfo { index, chunks, deadline }
+			})
+			.collect()
+	}
+
+	/// The challenged `chunks` grouped by archive, in the order proofs are submitted in: every
+	/// archive with the flat-tree indeces of its challenged leaves.
+	pub fn challenged_archives(chunks: &[(Public, u64)]) -> Vec<(Public, Vec<u64>)> {
+		let mut chunks = chunks.to_vec();
+		chunks.sort_unstable();
+		chunks.dedup();
+		let mut archives: Vec<(Public, Vec<u64>)> = Vec::new();
+		for (archive, leaf) in chunks {
+			match archives.last_mut() {
+				Some((last, leaves)) if *last == archive => leaves.push(leaf),
+				_ => archives.push((archive, vec![leaf])),
+			}
+		}
+		archives
+	}
+
+	/// The number of chunks a challenge asks for: the fewest which a seeder who lost
+	/// `DetectableLoss` of their archives fails to prove with a chance of at least
+	/// `DetectionProbability`, but at least one and at most `MaxChunksPerChallenge`.
+	pub fn chunks_per_challenge() -> u32 {
+		let kept = Perbill::one().saturating_sub(T::DetectableLoss::get());
+		let undetected = Perbill::one().saturating_sub(T::DetectionProbability::get());
+		let max = T::MaxChunksPerChallenge::get().max(1);
+		// the chance of proving every one of `chunks` sampled chunks.
+		let mut passing = Perbill::one();
+		let mut chunks = 0;
+		while passing > undetected && chunks < max {
+			passing = passing.saturating_mul(kept);
+			chunks += 1;
+		}
+		chunks.max(1)
+	}
+
+	/// The capacity `account` declared and the bytes of archives it hosts, `None` if it declared
+	/// none.
+	pub fn capacity_of(account: T::AccountId) -> Option<CapacityInfo> {
+		let declared = <SeederCapacity<T>>::get(&account)?;
+		Some(CapacityInfo { declared, used: Self::used_capacity(&account) })
+	}
+
+	/// What is known about `archive`, `None` if it is not registered.
+	pub fn archive_info(archive: Public) -> Option<ArchiveInfo<T::AccountId>> {
+		let index = <DatIndex>::get(&archive)?;
+		Some(ArchiveInfo {
+			index,
+			publisher: <UserRequestsMap<T>>::get(&archive),
+			merkle_root: <MerkleRoot>::get(&archive).0,
+			tree_size: <TreeSize>::get(&archive),
+			tree_length: <TreeLength>::get(&archive),
+			tree_version: <TreeVersion>::get(&archive),
+			hosters: Self::hosters_of(archive),
+		})
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The account of the reward pot, funded by slashed bonds and donations.
+	pub fn pot_account() -> T::AccountId {
+		MODULE_ID.into_account()
+	}
+
+	/// Pick the archives to assign to `account` for `request`.
+	///
+	/// Archives with fewer hosters than their deal pays for are picked first, the others only
+	/// once none of those are left, and a trusted seeder is picked those which require trusted
+	/// seeders before any other. Within each group, an archive is picked with a chance weighted
+	/// by the price its deal pays, and archives `account` already hosts, or is not trusted with,
+	/// are skipped. A hyperdrive is picked as one archive, the size of both its feeds.
+	fn assign_archives(account: &T::AccountId, request: &SeedRequest) -> Vec<Public> {
+		let trusted = T::TrustedSeeders::is_trusted(account);
+		// trusted only and below target, below target, and the others, with their weights.
+		let mut groups: [(Vec<Public>, Vec<u128>); 3] = Default::default();
+		let unhosted = (0..<DatCount>::get())
+			.filter(|index| <DatKey>::exists(index))
+			.map(|index| <DatKey>::get(index))
+			.filter(|pubkey| !<DatHosters<T>>::exists(pubkey, account))
+			// the content feed of a hyperdrive is assigned with its metadata feed.
+			.filter(|pubkey| !<MetadataFeed>::exists(pubkey));
+		for pubkey in unhosted {
+			let trusted_only = <TrustedOnly>::get(&pubkey);
+			if trusted_only && !trusted {
+				continue;
+			}
+			let below_target =
+				(<DatHostersCount>::get(&pubkey) as u64) < T::StorageDeals::replication(&pubkey) as u64;
+			let group = match (below_target, trusted_only) {
+				(true, true) => 0,
+				(true, false) => 1,
+				(false, _) => 2,
+			};
+			// archives without a deal can still be picked.
+			let weight = T::StorageDeals::price_per_block(&pubkey).saturating_add(1);
+			groups[group].0.push(pubkey);
+			groups[group].1.push(weight.into());
+		}
+		let mut groups: Vec<(Vec<Public>, WeightedDraw)> = groups.iter_mut()
+			.map(|(archives, weights)| (mem::take(archives), WeightedDraw::new(mem::take(weights))))
+			.collect();
+		let (mut archives_left, mut capacity_left) = match request {
+			SeedRequest::Archives(count) => (*count, DatSize::max_value()),
+			SeedRequest::Capacity(capacity) => (u32::max_value(), *capacity),
+		};
+		// a seeder who declared its capacity gets nothing more once it is full.
+		if let Some(capacity) = <SeederCapacity<T>>::get(account) {
+			capacity_left = capacity_left.min(capacity.saturating_sub(Self::used_capacity(account)));
+		}
+		let mut assigned = Vec::new();
+		while archives_left > 0 {
+			let (candidates, draw) = match groups.iter_mut().find(|(_, draw)| !draw.is_empty()) {
+				Some(group) => group,
+				None => break,
+			};
+			let random = Self::assignment_random(account, assigned.len() as u32);
+			let pubkey = candidates[draw.take(random)];
+			let size = Self::feeds_of(pubkey).iter()
+				.fold(0, |size: DatSize, feed| size.saturating_add(<TreeSize>::get(feed)));
+			// archives too big for what is left of the capacity are dropped.
+			if size > capacity_left {
+				continue;
+			}
+			capacity_left -= size;
+			archives_left -= 1;
+			assigned.push(pubkey);
+		}
+		assigned
+	}
+
+	/// Drop the registered archive `pubkey`, at `index`, and its hosters.
+	fn unregister_feed(index: DatIdIndex, pubkey: Public) {
+		Self::release_dat_index(index);
+		// from the last slot down, so no hoster is moved.
+		for slot in (0..<DatHostersCount>::get(&pubkey)).rev() {
+			Self::remove_hoster(&pubkey, &<DatHostersAt<T>>::get(&pubkey, slot));
+		}
+		<DatKey>::remove(&index);
+		<DatIndex>::remove(&pubkey);
+		<UserRequestsMap<T>>::remove(&pubkey);
+		<TreeSize>::remove(&pubkey);
+		<TreeLength>::remove(&pubkey);
+		<TreeVersion>::remove(&pubkey);
+		<MerkleRoot>::remove(&pubkey);
+		<PrivateArchives>::remove(&pubkey);
+		<TrustedOnly>::remove(&pubkey);
+		T::StorageDeals::on_unregister(&pubkey);
+		Self::deposit_event(RawEvent::SomethingUnstored(index, pubkey));
+	}
+
+	/// The feeds hosted when `archive` is: the archive, and its content feed for a hyperdrive.
+	fn feeds_of(archive: Public) -> Vec<Public> {
+		let mut feeds = vec![archive];
+		feeds.extend(<ContentFeed>::get(&archive));
+		feeds
+	}
+
+	/// Bond `account`, unless it already is, and have it pin the `assigned` archives.
+	fn pin_archives(account: &T::AccountId, assigned: Vec<Public>) -> DispatchResult {
+		if !<SeederBonds<T>>::exists(account) {
+			let bond = T::SeederBond::get();
+			T::Currency::reserve(account, bond)
+				.map_err(|_| Error::<T>::InsufficientBond)?;
+			<SeederBonds<T>>::insert(account, bond);
+		}
+		for dat_pubkey in assigned.into_iter().flat_map(Self::feeds_of) {
+			if Self::insert_hoster(&dat_pubkey, account) {
+				Self::deposit_event(RawEvent::NewPin(account.clone(), dat_pubkey));
+			}
+		}
+		<Nonce>::mutate(|m| *m += 1);
+		Self::insert_user(account);
+		Ok(())
+	}
+
+	/// The bytes of the archives `account` hosts, which may outgrow its capacity as they are
+	/// updated.
+	fn used_capacity(account: &T::AccountId) -> DatSize {
+		(0..<UsersStorageCount<T>>::get(account))
+			.map(|slot| <TreeSize>::get(<UsersStorageAt<T>>::get(account, slot)))
+			.fold(0, |used: DatSize, size| used.saturating_add(size))
+	}
+
+	/// The bond of a seeder declaring `capacity` bytes.
+	fn capacity_bond(capacity: DatSize) -> BalanceOf<T> {
+		let gigabytes = capacity / GIGABYTE + if capacity % GIGABYTE == 0 { 0 } else { 1 };
+		T::SeederBond::get().saturating_add(T::BondPerGigabyte::get().saturating_mul(gigabytes.saturated_into()))
+	}
+
+	/// Reserve from, or release to, `account` what it takes for its bond to be `bond`.
+	fn bond_seeder(account: &T::AccountId, bond: BalanceOf<T>) -> DispatchResult {
+		let current = <SeederBonds<T>>::get(account);
+		if bond > current {
+			T::Currency::reserve(account, bond - current)
+				.map_err(|_| Error::<T>::InsufficientBond)?;
+		} else {
+			T::Currency::unreserve(account, current - bond);
+		}
+		<SeederBonds<T>>::insert(account, bond);
+		Ok(())
+	}
+
+	/// `reward` grown by the share of `MaxReputation` the reputation of `account` makes up.
+	fn with_reputation(account: &T::AccountId, reward: BalanceOf<T>) -> BalanceOf<T> {
+		let share = Perbill::from_rational_approximation(
+			<Reputations<T>>::get(account),
+			T::MaxReputation::get().max(1),
+		);
+		reward.saturating_add(share * reward)
+	}
+
+	fn raise_reputation(account: &T::AccountId, gain: Reputation) {
+		Self::set_reputation(account, <Reputations<T>>::get(account).saturating_add(gain));
+	}
+
+	fn lower_reputation(account: &T::AccountId, loss: Reputation) {
+		Self::set_reputation(account, <Reputations<T>>::get(account).saturating_sub(loss));
+	}
+
+	/// Give `account` the `reputation`, up to `MaxReputation`, and rank it among the trusted
+	/// seeders for as long as it seeds. A seeder left without reputation is forgotten.
+	fn set_reputation(account: &T::AccountId, reputation: Reputation) {
+		let reputation = reputation.min(T::MaxReputation::get());
+		if reputation == 0 {
+			<Reputations<T>>::remove(account);
+			Self::remove_reputed(account);
+			T::TrustedSeeders::remove(account);
+			return;
+		}
+		<Reputations<T>>::insert(account, reputation);
+		if !<ReputedIds<T>>::exists(account) {
+			// a new reputation has not been around for the decay of this period.
+			let index = <ReputedCount>::get();
+			<Reputed<T>>::insert(index, account);
+			<ReputedIds<T>>::insert(account, index);
+			<ReputedCount>::put(index + 1);
+		}
+		if <UserIds<T>>::exists(account) {
+			T::TrustedSeeders::score(account, reputation);
+		}
+	}
+
+	/// Remove `account` from the seeders with a reputation, keeping those left to decay this
+	/// period below `DecayCursor`.
+	fn remove_reputed(account: &T::AccountId) {
+		let mut index = match <ReputedIds<T>>::get(account) {
+			Some(index) => index,
+			None => return,
+		};
+		let cursor = <DecayCursor>::get();
+		if index < cursor {
+			// the last reputation left to decay takes its index instead of a decayed one.
+			Self::swap_reputed(index, cursor - 1);
+			<DecayCursor>::put(cursor - 1);
+			index = cursor - 1;
+		}
+		let last = <ReputedCount>::get().saturating_sub(1);
+		Self::swap_reputed(index, last);
+		<Reputed<T>>::remove(last);
+		<ReputedIds<T>>::remove(account);
+		<ReputedCount>::put(last);
+	}
+
+	fn swap_reputed(index: u64, other: u64) {
+		if index == other {
+			return;
+		}
+		let account = <Reputed<T>>::get(index);
+		let other_account = <Reputed<T>>::get(other);
+		<Reputed<T>>::insert(index, &other_account);
+		<ReputedIds<T>>::insert(&other_account, index);
+		<Reputed<T>>::insert(other, &account);
+		<ReputedIds<T>>::insert(&account, other);
+	}
+
+	/// Take `ReputationDecay` of up to `count` of the reputations left to decay this period,
+	/// and at least one, so it has to be kept up.
+	fn decay_reputations(count: u32) {
+		let decay = T::ReputationDecay::get();
+		if decay.is_zero() {
+			<DecayCursor>::kill();
+			return;
+		}
+		for _ in 0..count {
+			let cursor = <DecayCursor>::get();
+			if cursor == 0 {
+				break;
+			}
+			// the cursor moves first, so a reputation decayed to nothing is removed as decayed.
+			<DecayCursor>::put(cursor - 1);
+			let account = <Reputed<T>>::get(cursor - 1);
+			let reputation = <Reputations<T>>::get(&account);
+			Self::set_reputation(&account, reputation.saturating_sub((decay * reputation).max(1)));
+		}
+	}
+
+	/// The index the next registered archive gets: the last one freed, else a new one.
+	fn allocate_dat_index() -> DatIdIndex {
+		match <FreeDatIdCount>::get() {
+			0 => {
+				let index = <DatCount>::get();
+				<DatCount>::put(index + 1);
+				index
+			},
+			free => {
+				<FreeDatIdCount>::put(free - 1);
+				<FreeDatIds>::take(free - 1)
+			},
+		}
+	}
+
+	/// Free the index of an unregistered archive for `allocate_dat_index` to reuse.
+	fn release_dat_index(index: DatIdIndex) {
+		let free = <FreeDatIdCount>::get();
+		<FreeDatIds>::insert(free, index);
+		<FreeDatIdCount>::put(free + 1);
+	}
+
+	/// Add `account` to the users, unless it already is one.
+	fn insert_user(account: &T::AccountId) {
+		if <UserIds<T>>::exists(account) {
+			return;
+		}
+		let index = <UserCount>::get();
+		<Users<T>>::insert(index, account);
+		<UserIds<T>>::insert(account, index);
+		<UserCount>::put(index + 1);
+		// a returning seeder is ranked by the reputation it kept.
+		let reputation = <Reputations<T>>::get(account);
+		if reputation > 0 {
+			T::TrustedSeeders::score(account, reputation);
+		}
+	}
+
+	/// Remove `account` from the users, moving the last user into its index.
+	fn remove_user(account: &T::AccountId) {
+		let index = match <UserIds<T>>::take(account) {
+			Some(index) => index,
+			None => return,
+		};
+		let last = <UserCount>::get().saturating_sub(1);
+		if index != last {
+			let moved = <Users<T>>::get(last);
+			<Users<T>>::insert(index, &moved);
+			<UserIds<T>>::insert(&moved, index);
+		}
+		<Users<T>>::remove(last);
+		<UserCount>::put(last);
+	}
+
+	/// Have `account` pin `archive`, returning whether it did not already.
+	fn insert_hoster(archive: &Public, account: &T::AccountId) -> bool {
+		if <DatHosters<T>>::exists(archive, account) {
+			return false;
+		}
+		let slot = <DatHostersCount>::get(archive);
+		<DatHosters<T>>::insert(archive, account, slot);
+		<DatHostersAt<T>>::insert(archive, slot, account);
+		<DatHostersCount>::insert(archive, slot + 1);
+		let slot = <UsersStorageCount<T>>::get(account);
+		<UsersStorage<T>>::insert(account, archive, slot);
+		<UsersStorageAt<T>>::insert(account, slot, archive);
+		<UsersStorageCount<T>>::insert(account, slot + 1);
+		true
+	}
+
+	/// Have `account` stop pinning `archive`. The last hoster of the archive, and the last
+	/// archive of the account, are moved into the slots this leaves.
+	fn remove_hoster(archive: &Public, account: &T::AccountId) {
+		<KeyEnvelopes<T>>::remove(archive, account);
+		if let Some(slot) = <DatHosters<T>>::take(archive, account) {
+			let last = <DatHostersCount>::get(archive).saturating_sub(1);
+			if slot != last {
+				let moved = <DatHostersAt<T>>::get(archive, last);
+				<DatHosters<T>>::insert(archive, &moved, slot);
+				<DatHostersAt<T>>::insert(archive, slot, moved);
+			}
+			<DatHostersAt<T>>::remove(archive, last);
+			match last {
+				0 => <DatHostersCount>::remove(archive),
+				_ => <DatHostersCount>::insert(archive, last),
+			}
+		}
+		if let Some(slot) = <UsersStorage<T>>::take(account, archive) {
+			let last = <UsersStorageCount<T>>::get(account).saturating_sub(1);
+			if slot != last {
+				let moved = <UsersStorageAt<T>>::get(account, last);
+				<UsersStorage<T>>::insert(account, &moved, slot);
+				<UsersStorageAt<T>>::insert(account, slot, moved);
+			}
+			<UsersStorageAt<T>>::remove(account, last);
+			match last {
+				0 => <UsersStorageCount<T>>::remove(account),
+				_ => <UsersStorageCount<T>>::insert(account, last),
+			}
+		}
+	}
+
+	/// A random number for the assignment of the archive at `round` to `account`.
+	fn assignment_random(account: &T::AccountId, round: u32) -> u64 {
+		let nonce = <Nonce>::get();
+		(T::Randomness::random(b"dat_verify_register"), &nonce, account, round)
+			.using_encoded(|b| Blake2Hasher::hash(b))
+			.using_encoded(|mut b| u64::decode(&mut b))
+			.expect("hash must be of correct size; Qed")
+	}
+
+	/// The seed the challenges of `block` are drawn from, see `draw`. The epoch randomness is
+	/// the same for every block of an epoch, so the block is part of the subject: a draw which
+	/// issued nothing is not drawn again by the next block.
+	pub fn challenge_seed(block: T::BlockNumber) -> T::Hash {
+		T::Randomness::random(&(CHALLENGE_SUBJECT, block).encode())
+	}
+
+	/// The number drawn from `seed` for the `round`th `purpose` of the challenge at
+	/// `challenge_index`. Every choice of a challenge is drawn this way, so seeders and auditors
+	/// recompute who was challenged on which chunks, and who attests, from the seed and the
+	/// index against the state of the block the challenge was issued in.
+	pub fn draw(seed: &T::Hash, challenge_index: u64, purpose: Draw, round: u32) -> u64 {
+		(seed, challenge_index, purpose, round)
+			.using_encoded(|b| Blake2Hasher::hash(b))
+			.using_encoded(|mut b| u64::decode(&mut b))
+			.expect("hash must be of correct size; Qed")
+	}
+
+	/// Challenge a random seeder to prove `chunks_per_challenge` random chunks of their archives
+	/// before a random deadline. `skipped` is the number of seeders already drawn for this
+	/// challenge that had nothing to prove, so each attempt draws the seeder anew.
+	fn issue_challenge(n: T::BlockNumber, skipped: u32) -> ChallengeIssue {
+		// the registered archives, as the indeces handed out less the freed ones.
+		let dat_count = <DatCount>::get().saturating_sub(<FreeDatIdCount>::get());
+		let user_count = <UserCount>::get();
+		let challenge_index = <ChallengeIndex>::get();
+		// if no one is currently selected to give proof, select someone
+		if dat_count == 0 || user_count == 0 || <ChallengeMap>::exists(&challenge_index) {
+			return ChallengeIssue::Stopped;
+		}
+		let seed = Self::challenge_seed(n);
+		let new_random = Self::draw(&seed, challenge_index, Draw::Seeder, skipped);
+		let challenge_length = 1 + Self::draw(&seed, challenge_index, Draw::Deadline, 0) % dat_count;
+		let random_user = <Users<T>>::get(new_random % user_count);
+		let users_dats_len = <UsersStorageCount<T>>::get(&random_user) as u64;
+		// the selected user may have had all of their dats unregistered.
+		if users_dats_len == 0 {
+			return ChallengeIssue::Skipped;
+		}
+		let mut chunks = Vec::new();
+		for sample in 0..Self::chunks_per_challenge() {
+			let sample_random = Self::draw(&seed, challenge_index, Draw::Chunk, sample);
+			let random_dat = <UsersStorageAt<T>>::get(&random_user, (sample_random % users_dats_len) as u32);
+			let dat_tree_len = <TreeLength>::get(&random_dat);
+			// archives registered before lengths were kept cannot be proved until theirs is.
+			if dat_tree_len == 0 {
+				continue;
+			}
+			// chunk `n` is the leaf at index `2 * n` of the flat tree.
+			chunks.push((random_dat, 2 * (sample_random % dat_tree_len)));
+		}
+		if chunks.is_empty() {
+			return ChallengeIssue::Skipped;
+		}
+		// attestors check the archive of the first chunk.
+		let random_dat = chunks[0].0;
+		chunks.sort_unstable();
+		chunks.dedup();
+		// the challenge is issued from here on, so its expiry can be queued under its index.
+		let future_block = Self::schedule_expiry(
+			challenge_index,
+			n + challenge_length.saturated_into(),
+		);
+		let y : u64;
+		if !<SelectedUserIndex<T>>::exists(&random_user) {
+			let user_index = <UserIndex>::get();
+			<SelectedUserIndex<T>>::insert(&random_user, (user_index, 1));
+			<UserIndex>::put(<UserIndex>::get() + 1);
+			y = user_index;
+		} else {
+			let (user_index, count) = <SelectedUserIndex<T>>::get(&random_user);
+			<SelectedUserIndex<T>>::insert(&random_user, (user_index, count+1));
+			y = user_index;
+		}
+		Self::select_attestors(challenge_index, &seed, &random_user, &random_dat, future_block);
+		<SelectedChallenges<T>>::insert(&challenge_index, (chunks, future_block));
+		<SelectedUsers<T>>::insert(&y, &random_user);
+		<ChallengeMap>::insert(challenge_index, y);
+		<OpenChallenges>::mutate(y, |open| open.push(challenge_index));
+		<Nonce>::put(<Nonce>::get() + 1);
+		<ChallengeIndex>::put(<ChallengeIndex>::get() + 1);
+		Self::deposit_event(RawEvent::ChallengeSeed(challenge_index, seed));
+		Self::deposit_event(RawEvent::Challenge(random_user, future_block));
+		ChallengeIssue::Issued
+	}
+
+	/// Queue `challenge_index` to expire at the end of `block`, or of the first block after it
+	/// with room for another expiry, and return that block.
+	fn schedule_expiry(challenge_index: u64, mut block: T::BlockNumber) -> T::BlockNumber {
+		let limit = T::ChallengesPerBlock::get().max(1) as usize;
+		while <ChallengeExpiries<T>>::get(&block).len() >= limit {
+			block += One::one();
+		}
+		<ChallengeExpiries<T>>::mutate(&block, |expiries| expiries.push(challenge_index));
+		block
+	}
+
+	/// Drop `challenge_index` from the challenges open to the user at `user_index`.
+	fn close_challenge(challenge_index: u64, user_index: u64) {
+		<SelectedChallenges<T>>::remove(challenge_index);
+		<ChallengeMap>::remove(challenge_index);
+		let mut open = <OpenChallenges>::take(user_index);
+		open.retain(|index| *index != challenge_index);
+		if !open.is_empty() {
+			<OpenChallenges>::insert(user_index, open);
+		}
+	}
+
+	/// Fail `challenge_index` once its deadline passed without a proof: the seeder is slashed,
+	/// reported and evicted. Challenges on archives the seeder no longer hosts are dropped.
+	fn expire_challenge(challenge_index: u64) {
+		let user_index = <ChallengeMap>::get(challenge_index);
+		let user = <SelectedUsers<T>>::get(user_index);
+		let (chunks, _) = <SelectedChallenges<T>>::get(challenge_index);
+		let temporary_root = system::RawOrigin::Root;
+		if !<SelectedUsers<T>>::exists(user_index) {
+			Self::close_challenge(challenge_index, user_index);
+			return;
+		}
+		let dats: Vec<Public> = Self::challenged_archives(&chunks).into_iter()
+			.map(|(dat, _)| dat)
+			.filter(|dat| <DatHosters<T>>::exists(dat, &user))
+			.collect();
+		if dats.is_empty() {
+			let _ = Self::force_clear_challenge(temporary_root.into(), user, challenge_index);
+			return;
+		}
+		<SelectedUsers<T>>::remove(user_index);
+		<SelectedUserIndex<T>>::remove(&user);
+		Self::close_challenge(challenge_index, user_index);
+		let _ = Self::punish_seeder(temporary_root.into(), user.clone());
+		Self::report_challenge_failure(&user, challenge_index);
+		Self::evict_seeder(&user);
+		Self::lower_reputation(&user, T::FailureReputation::get());
+		Self::deposit_event(RawEvent::ChallengeFailed(user, dats));
+	}
+
+	/// Stop challenging `account`, or asking it to attest, and queue its release from its
+	/// archives and its bond, see `release_evicted`. The work of unregistering it grows with its
+	/// archives, so it is not done in the hook expiring the challenge it failed, nor in the call
+	/// of a seeder leaving.
+	fn evict_seeder(account: &T::AccountId) {
+		Self::remove_user(account);
+		T::TrustedSeeders::remove(account);
+		<Evictions<T>>::mutate(|evictions| if !evictions.contains(account) {
+			evictions.push(account.clone());
+		});
+	}
+
+	/// Release evicted seeders, oldest first, from up to `limit` archives, unreserving the rest
+	/// of the bond of every seeder left without any. Releasing a bond counts as one of them.
+	fn release_evicted(limit: u32) {
+		let mut evictions = <Evictions<T>>::get();
+		if evictions.is_empty() {
+			return;
+		}
+		let mut released = 0;
+		while released < limit && !evictions.is_empty() {
+			let account = &evictions[0];
+			match <UsersStorageCount<T>>::get(account) {
+				0 => {
+					<SeederCapacity<T>>::remove(account);
+					let bond = <SeederBonds<T>>::take(account);
+					T::Currency::unreserve(account, bond);
+					evictions.remove(0);
+				},
+				count => Self::remove_hoster(&<UsersStorageAt<T>>::get(account, count - 1), account),
+			}
+			released += 1;
+		}
+		<Evictions<T>>::put(evictions);
+	}
+
+	/// Ask random seeders, other than the challenged `seeder` and those already attesting, to
+	/// attest whether `seeder` serves `dat` until `deadline`. Every attestor is the first seeder
+	/// free to attest from an index drawn from `seed` on, so only the seeders skipped over are read.
+	///
+	/// No attestations are asked for when fewer seeders than the quorum can attest.
+	fn select_attestors(
+		challenge_index: u64,
+		seed: &T::Hash,
+		seeder: &T::AccountId,
+		dat: &Public,
+		deadline: T::BlockNumber,
+	) {
+		let user_count = <UserCount>::get();
+		let mut attestors = Vec::new();
+		for round in 0..T::AttestorsPerChallenge::get() {
+			let new_random = Self::draw(seed, challenge_index, Draw::Attestor, round);
+			let start = new_random % user_count.max(1);
+			let attestor = (0..user_count)
+				.map(|offset| <Users<T>>::get((start + offset) % user_count))
+				.find(|account| {
+					account != seeder && !<Attestors<T>>::exists(account) && !attestors.contains(account)
+				});
+			match attestor {
+				Some(attestor) => attestors.push(attestor),
+				// every seeder free to attest was picked.
+				None => break,
+			}
+		}
+		if attestors.is_empty() || (attestors.len() as u32) < T::AttestationQuorum::get() {
+			return;
+		}
+		for attestor in &attestors {
+			<Attestors<T>>::insert(attestor, challenge_index);
+		}
+		<ChallengeAttestations<T>>::insert(challenge_index, (&attestors, Vec::<(T::AccountId, Attestation)>::new()));
+		<AttestationRounds<T>>::insert(challenge_index, (seeder, dat, deadline));
+		Self::deposit_event(RawEvent::AttestorsSelected(challenge_index, attestors));
+	}
+
+	/// Decide whether the seeder attested for `challenge_index` serves its archive once a quorum
+	/// of the `seen` attestations agree, then pay the attestors who agreed, take reputation from
+	/// those who did not, and slash the seeder if it does not serve it.
+	fn tally_attestations(challenge_index: u64, seen: &[(T::AccountId, Attestation)]) {
+		let quorum = T::AttestationQuorum::get().max(1) as usize;
+		let (served, not_served): (Vec<_>, Vec<_>) = seen.iter()
+			.partition(|(_, attestation)| attestation.latency.is_some());
+		let (is_served, honest, dissenting) = if served.len() >= quorum {
+			(true, served, not_served)
+		} else if not_served.len() >= quorum {
+			(false, not_served, served)
+		} else {
+			return;
+		};
+		let (seeder, dat, _) = <AttestationRounds<T>>::get(challenge_index);
+		Self::clear_attestations(challenge_index);
+		for (attestor, _) in honest {
+			let _ = Self::reward_attestor(system::RawOrigin::Root.into(), attestor.clone());
+			Self::raise_reputation(attestor, T::AttestationReputation::get());
+		}
+		for (attestor, _) in dissenting {
+			Self::lower_reputation(attestor, T::AttestationReputation::get());
+		}
+		if is_served {
+			Self::deposit_event(RawEvent::AttestationsPassed(seeder, dat));
+		} else {
+			let _ = Self::punish_seeder(system::RawOrigin::Root.into(), seeder.clone());
+			Self::lower_reputation(&seeder, T::FailureReputation::get());
+			Self::deposit_event(RawEvent::AttestationsFailed(seeder, dat));
+		}
+	}
+
+	/// Forget the attestations of `challenge_index`, including the attestors yet to submit one.
+	fn clear_attestations(challenge_index: u64) {
+		let (expected, _) = <ChallengeAttestations<T>>::take(challenge_index);
+		for attestor in expected {
+			if <Attestors<T>>::exists(&attestor) && <Attestors<T>>::get(&attestor) == challenge_index {
+				<Attestors<T>>::remove(&attestor);
+			}
+		}
+		<AttestationRounds<T>>::remove(challenge_index);
+	}
+
+	/// Report `seeder` failing challenge `challenge_index`, if they can be identified as a
+	/// validator for the offence handler.
+	fn report_challenge_failure(seeder: &T::AccountId, challenge_index: u64) {
+		let offender = T::ValidatorIdOf::convert(seeder.clone())
+			.and_then(|id| T::FullIdentificationOf::convert(id.clone()).map(|full_id| (id, full_id)));
+		if let Some(offender) = offender {
+			let offence = ChallengeFailureOffence {
+				session_index: <pallet_session::Module<T>>::current_index(),
+				validator_set_count: <pallet_session::Module<T>>::validators().len() as u32,
+				challenge_index,
+				offender,
+			};
+			T::ReportChallengeFailure::report_offence(vec![], offence);
+		}
+	}
+
+	/// Ensure `call` keeps to the size limits of the module, before any of its data is hashed.
+	pub fn ensure_call_within_limits(call: &Call<T>) -> DispatchResult {
+		match call {
+			Call::submit_proof(_, proofs) => Self::ensure_proofs_within_limits(proofs),
+			Call::register_data(merkle_root, consistency_proof) =>
+				Self::ensure_roots_within_limits(&merkle_root.1.children, consistency_proof.as_ref()),
+			Call::force_register_data(_, merkle_root) =>
+				Self::ensure_roots_within_limits(&merkle_root.1.children, None),
+			Call::backfill_tree_length(_, roots) => Self::ensure_roots_within_limits(roots, None),
+			Call::register_drive(metadata, content, header_proof) =>
+				Self::ensure_drive_within_limits(metadata, content, header_proof),
+			Call::register_private_data(merkle_root, consistency_proof) =>
+				Self::ensure_roots_within_limits(&merkle_root.1.children, consistency_proof.as_ref()),
+			Call::deliver_key(_, _, envelope) if envelope.len() > MAX_ENVELOPE_SIZE =>
+				Err(Error::<T>::EnvelopeTooLarge.into()),
+			_ => Ok(()),
+		}
+	}
+
+	// at most `MaxChunksPerChallenge` chunks of `MaxChunkSize` bytes, and `MaxProofNodes` nodes.
+	fn ensure_proofs_within_limits(proofs: &[Proof]) -> DispatchResult {
+		let max_chunks = T::MaxChunksPerChallenge::get() as usize;
+		let max_chunk_size = T::MaxChunkSize::get() as usize;
+		let (chunks, nodes) = proofs.iter().fold((0usize, 0usize), |(chunks, nodes), proof| {
+			(chunks.saturating_add(proof.chunks.len()), nodes.saturating_add(proof.nodes.len()))
+		});
+		ensure!(
+			proofs.len() <= max_chunks
+				&& chunks <= max_chunks
+				&& nodes <= T::MaxProofNodes::get() as usize
+				&& proofs.iter()
+					.flat_map(|proof| proof.chunks.iter())
+					.all(|chunk| chunk.len() <= max_chunk_size),
+			Error::<T>::ProofTooLarge
+		);
+		Ok(())
+	}
+
+	// at most `MaxRootChildren` roots, old or new, and `MaxProofNodes` consistency nodes.
+	fn ensure_roots_within_limits(
+		roots: &[ParentHashInRoot],
+		consistency_proof: Option<&ConsistencyProof>,
+	) -> DispatchResult {
+		let max_roots = T::MaxRootChildren::get() as usize;
+		ensure!(roots.len() <= max_roots, Error::<T>::TooManyRoots);
+		if let Some(proof) = consistency_proof {
+			ensure!(proof.old_roots.len() <= max_roots, Error::<T>::TooManyRoots);
+			ensure!(proof.nodes.len() <= T::MaxProofNodes::get() as usize, Error::<T>::ProofTooLarge);
+		}
+		Ok(())
+	}
+
+	// both feeds within the root limits, and the header within the proof limits.
+	fn ensure_drive_within_limits(
+		metadata: &(Public, RootHashPayload, H512),
+		content: &(Public, RootHashPayload, H512),
+		header_proof: &Proof,
+	) -> DispatchResult {
+		Self::ensure_roots_within_limits(&metadata.1.children, None)?;
+		Self::ensure_roots_within_limits(&content.1.children, None)?;
+		Self::ensure_proofs_within_limits(sp_std::slice::from_ref(header_proof))
+	}
+
+	/// The hash of the roots of `merkle_root` and the length of their tree, if the key of
+	/// `merkle_root` signed them.
+	fn verify_signed_roots(merkle_root: &(Public, RootHashPayload, H512)) -> Result<(H256, u64), Error<T>> {
+		let (pubkey, payload, signature) = merkle_root;
+		// the hash type is not trusted, the roots are hashed as a root payload.
+		let root_hash = hashing::tree(&payload.children);
+		ensure!(
+			Signature::from_h512(*signature).verify(root_hash.as_bytes(), pubkey),
+			Error::<T>::VerificationFailed
+		);
+		let tree_length = Self::tree_length_of(&payload.children).ok_or(Error::<T>::InvalidTreeSize)?;
+		Ok((root_hash, tree_length))
+	}
+
+	/// The number of chunks in the tree signed with `roots`, if they are the full roots of a
+	/// tree in ascending index order.
+	fn tree_length_of(roots: &[ParentHashInRoot]) -> Option<u64> {
+		let last = roots.last()?;
+		// no feed is this long, and it keeps the span math from overflowing.
+		if last.hash_number >= 1 << 62 {
+			return None;
+		}
+		let tree_length = flat_tree::right_span(last.hash_number) / 2 + 1;
+		let indeces: Vec<u64> = roots.iter().map(|root| root.hash_number).collect();
+		if flat_tree::full_roots(2 * tree_length) == indeces {
+			Some(tree_length)
+		} else {
+			None
+		}
+	}
+
+	/// Check that the tree signed with `new_roots` extends the registered tree of `pubkey`.
+	/// Rejected updates are reported with an `UpdateRejected` event.
+	fn verify_update(
+		pubkey: &Public,
+		new_roots: &[ParentHashInRoot],
+		new_length: u64,
+		proof: Option<ConsistencyProof>
+	) -> DispatchResult {
+		let old_length = <TreeLength>::get(pubkey);
+		let result = Self::verify_consistency(pubkey, new_roots, old_length, new_length, proof);
+		if result.is_err() {
+			Self::deposit_event(RawEvent::UpdateRejected(*pubkey, old_length, new_length));
+		}
+		result
+	}
+
+	/// The old roots of `proof` must be the ones registered for `pubkey`, and each of them must
+	/// be included under one of the `new_roots`.
+	fn verify_consistency(
+		pubkey: &Public,
+		new_roots: &[ParentHashInRoot],
+		old_length: u64,
+		new_length: u64,
+		proof: Option<ConsistencyProof>
+	) -> DispatchResult {
+		ensure!(new_length >= old_length, Error::<T>::StaleRoot);
+		let proof = proof.ok_or(Error::<T>::MissingConsistencyProof)?;
+		let (old_root_hash, _) = <MerkleRoot>::get(pubkey);
+		ensure!(
+			Self::tree_length_of(&proof.old_roots) == Some(old_length)
+				&& hashing::tree(&proof.old_roots) == old_root_hash,
+			Error::<T>::ConsistencyVerificationFailed
+		);
+		// the signed new roots come first, so they are the ones found for their indeces.
+		let mut nodes: Vec<Node> = new_roots.iter().map(|root| Node {
+			index: root.hash_number,
+			hash: root.hash,
+			size: root.total_length,
+		}).collect();
+		let new_indeces: Vec<u64> = nodes.iter().map(|node| node.index).collect();
+		nodes.extend(proof.nodes);
+		for old_root in proof.old_roots.iter() {
+			let old_node = Node {
+				index: old_root.hash_number,
+				hash: old_root.hash,
+				size: old_root.total_length,
+			};
+			Self::verify_inclusion(&old_node, &nodes, &new_indeces)
+				.map_err(|_| Error::<T>::ConsistencyVerificationFailed)?;
+		}
+		Ok(())
+	}
+
+	/// Climb from `leaf` up to one of the `roots`, recomputing every parent on the way from
+	/// the sibling nodes of the proof, and check that we arrive at the root node of the proof.
+	/// Parent nodes included in the proof must match the recomputed ones.
+	fn verify_inclusion(leaf: &Node, nodes: &[Node], roots: &[u64]) -> DispatchResult {
+		let mut current = leaf.clone();
+		while !roots.contains(&current.index) {
+			// no tree we could be asked about is this high, the proof is not for these roots.
+			ensure!(flat_tree::depth(current.index) < 62, Error::<T>::MissingRoot);
+			let sibling = Node::find(nodes, flat_tree::sibling(current.index))
+				.ok_or(Error::<T>::MissingSibling)?;
+			let parent = Self::parent_of(&current, sibling)?;
+			if let Some(given) = Node::find(nodes, parent.index) {
+				ensure!(
+					roots.contains(&given.index) || given == &parent,
+					Error::<T>::ParentHashVerificationFailed
+				);
+			}
+			current = parent;
+		}
+		let root = Node::find(nodes, current.index).ok_or(Error::<T>::MissingRoot)?;
+		ensure!(root == &current, Error::<T>::InclusionVerificationFailed);
+		Ok(())
+	}
+
+	/// Check that the `chunks` of `proof` are the leaves at `leaves` of the registered tree of
+	/// `archive`, see `verify_leaves`.
+	fn verify_chunks(archive: &Public, leaves: &[u64], proof: &Proof) -> DispatchResult {
+		Self::verify_leaves(leaves, proof, <TreeLength>::get(archive), <MerkleRoot>::get(archive).0)
+	}
+
+	/// Check that the `chunks` of `proof` are the leaves at `leaves` of the tree of `tree_length`
+	/// chunks whose roots hash to `root_hash`. The paths of the leaves are recomputed a level at
+	/// a time, so nodes shared by several paths are computed rather than taken from the proof,
+	/// and the roots must hash to `root_hash`.
+	fn verify_leaves(leaves: &[u64], proof: &Proof, tree_length: u64, root_hash: H256) -> DispatchResult {
+		ensure!(proof.chunks.len() == leaves.len(), Error::<T>::ProvesWrongChunk);
+		let mut computed = Vec::new();
+		for (leaf, chunk) in leaves.iter().zip(proof.chunks.iter()) {
+			ensure!(leaf % 2 == 0 && *leaf / 2 < tree_length, Error::<T>::MissingLeaf);
+			computed.push(Node { index: *leaf, hash: hashing::leaf(chunk), size: chunk.len() as u64 });
+		}
+		let root_indeces = flat_tree::full_roots(2 * tree_length);
+		// every node of a level is computed before any sibling of that level is looked up.
+		let mut level = computed.clone();
+		while !level.is_empty() {
+			let mut parents = Vec::new();
+			for node in level.iter().filter(|node| !root_indeces.contains(&node.index)) {
+				let sibling_index = flat_tree::sibling(node.index);
+				let sibling = Node::find(&computed, sibling_index)
+					.or_else(|| Node::find(&proof.nodes, sibling_index))
+					.ok_or(Error::<T>::MissingSibling)?;
+				let parent = Self::parent_of(node, sibling)?;
+				// the parents of two computed siblings are the same node.
+				if Node::find(&computed, parent.index).is_none() {
+					computed.push(parent.clone());
+					parents.push(parent);
+				}
+			}
+			level = parents;
+		}
+		let mut roots = Vec::new();
+		for index in root_indeces {
+			let root = Node::find(&computed, index)
+				.or_else(|| Node::find(&proof.nodes, index))
+				.ok_or(Error::<T>::MissingRoot)?;
+			roots.push(ParentHashInRoot { hash: root.hash, hash_number: root.index, total_length: root.size });
+		}
+		ensure!(hashing::tree(&roots) == root_hash, Error::<T>::RootHashVerificationFailed);
+		Ok(())
+	}
+
+	/// The parent of `node` and its `sibling`.
+	fn parent_of(node: &Node, sibling: &Node) -> Result<Node, Error<T>> {
+		let (left, right) = if node.index < sibling.index {
+			(node, sibling)
+		} else {
+			(sibling, node)
+		};
+		let total_length = left.size.checked_add(right.size)
+			.ok_or(Error::<T>::ParentHashVerificationFailed)?;
+		Ok(Node {
+			index: flat_tree::parent(node.index),
+			hash: ParentHashPayload {
+				hash_type: 1,
+				total_length,
+				child_hashes: [left.hash, right.hash],
+			}.hash(),
+			size: total_length,
+		})
+	}
+}
+
+/// Drops dat_verify calls over the size limits of the module from the transaction pool, see
+/// `Module::ensure_call_within_limits`, so an oversized proof is never gossiped or included.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct CheckProofLimits<T: Trait + Send + Sync>(PhantomData<T>);
+
+impl<T: Trait + Send + Sync> CheckProofLimits<T> {
+	/// Create new `SignedExtension` to check the size limits of dat_verify calls.
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T: Trait + Send + Sync> Default for CheckProofLimits<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Trait + Send + Sync> sp_std::fmt::Debug for CheckProofLimits<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckProofLimits")
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait + Send + Sync> SignedExtension for CheckProofLimits<T> where
+	<T as system::Trait>::Call: IsSubType<Module<T>, T>,
+{
+	const IDENTIFIER: &'static str = "CheckProofLimits";
+	type AccountId = T::AccountId;
+	type Call = <T as system::Trait>::Call;
+	type AdditionalSigned = ();
+	type DispatchInfo = DispatchInfo;
+	type Pre = ();
+
+	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
+
+	fn validate(
+		&self,
+		_who: &Self::AccountId,
+		call: &Self::Call,
+		_info: Self::DispatchInfo,
+		_len: usize,
+	) -> TransactionValidity {
+		match call.is_sub_type() {
+			Some(call) if <Module<T>>::ensure_call_within_limits(call).is_err() =>
+				InvalidTransaction::ExhaustsResources.into(),
+			_ => Ok(ValidTransaction::default()),
+		}
+	}
+}
+
+/// An offence that is filed when a seeder fails to answer a challenge in time.
+#[derive(RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Clone, PartialEq, Eq))]
+pub struct ChallengeFailureOffence<Offender> {
+	/// The session in which the challenge expired.
+	session_index: SessionIndex,
+	/// The size of the validator set in that session.
+	validator_set_count: u32,
+	/// The failed challenge, unique to this offence.
+	challenge_index: u64,
+	/// The seeder that failed the challenge.
+	offender: Offender,
+}
+
+impl<Offender: Clone> Offence<Offender> for ChallengeFailureOffence<Offender> {
+	const ID: Kind = *b"dat-verify:chall";
+	type TimeSlot = u64;
+
+	fn offenders(&self) -> Vec<Offender> {
+		vec![self.offender.clone()]
+	}
+
+	fn session_index(&self) -> SessionIndex {
+		self.session_index
+	}
+
+	fn validator_set_count(&self) -> u32 {
+		self.validator_set_count
+	}
+
+	fn time_slot(&self) -> Self::TimeSlot {
+		self.challenge_index
+	}
+
+	fn slash_fraction(_offenders: u32, _validator_set_count: u32) -> Perbill {
+		// the seeder bond is already slashed by this module, the report is kept as a record.
+		Perbill::zero()
+	}
+}
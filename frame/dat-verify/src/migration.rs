@@ -0,0 +1,145 @@
+//! Storage migrations for the dat_verify module.
+
+use sp_std::prelude::*;
+use codec::Encode;
+use frame_support::{
+	StorageHasher,
+	Twox128,
+	Twox256,
+	Blake2_256,
+	StorageValue,
+	StorageMap,
+	StorageLinkedMap,
+	storage::unhashed,
+};
+use crate::{
+	Trait, Module, DatIdIndex, UserIdIndex, StorageVersion, DatCount, DatKey, DatIndex,
+	ChallengeMap, SelectedChallenges, SelectedUsers, SelectedUserIndex, OpenChallenges, Attestors,
+};
+
+/// Indicator of a version of a storage layout.
+pub type VersionNumber = u32;
+
+// the current expected version of the storage
+pub const CURRENT_VERSION: VersionNumber = 2;
+
+// the key of the item `item` of the v0 layout, under `hashed_key` for a map.
+pub(crate) fn v0_key(item: &[u8], hashed_key: &[u8]) -> Vec<u8> {
+	let mut key = Twox128::hash(b"DatVerify").to_vec();
+	key.extend_from_slice(&Twox128::hash(item));
+	key.extend_from_slice(hashed_key);
+	key
+}
+
+// migrate storage from v0 to v1.
+//
+// this replaces the `DatId` and `UsersCount` vecs with counters, the `Users` linked_map with a
+// map without gaps, and the `DatHosters` and `UsersStorage` vecs with double maps. The items
+// keep their names, so every v0 value is taken before its v1 replacement is written.
+//
+// challenges still open under v0 are dropped. `TreeLength` is not backfilled: v0 only kept the
+// hash of the roots of an archive, which does not give its length, so archives registered
+// under v0 are not challenged until `backfill_tree_length` proves theirs.
+pub fn to_v1<T: Trait>(version: &mut VersionNumber) {
+	if *version != 0 { return }
+	*version += 1;
+
+	// RemovedDats: Vec<Public>, left behind by chains started before it was dropped.
+	unhashed::kill(&v0_key(b"RemovedDats", &[]));
+
+	// ChallengeMap: linked_map u64 => u64, kept as it was. The challenges it lists challenged a
+	// single chunk each, and had no expiry queued in `ChallengeExpiries`.
+	for (challenge_index, user_index) in <ChallengeMap>::enumerate().collect::<Vec<_>>() {
+		<ChallengeMap>::remove(challenge_index);
+		// SelectedChallenges: map u64 => (Public, u64, T::BlockNumber)
+		<SelectedChallenges<T>>::remove(challenge_index);
+		// ChallengeAttestations: map u64 => ([expected attestors], [rewarded friends],
+		// [seen attestations]), of which the expected attestors are decoded.
+		let attestations_key = v0_key(b"ChallengeAttestations", &Twox256::hash(&challenge_index.encode()));
+		for attestor in unhashed::take::<Vec<T::AccountId>>(&attestations_key).unwrap_or_default() {
+			<Attestors<T>>::remove(&attestor);
+		}
+		if <SelectedUsers<T>>::exists(user_index) {
+			<SelectedUserIndex<T>>::remove(<SelectedUsers<T>>::take(user_index));
+		}
+	}
+
+	// DatId: Vec<DatIdIndex>, the free indeces with the last item usable for `len`.
+	let mut free_ids = match unhashed::take::<Vec<DatIdIndex>>(&v0_key(b"DatId", &[])) {
+		Some(free_ids) => free_ids,
+		// nothing was ever registered, or pinned, under v0.
+		None => return,
+	};
+	let dat_count = free_ids.pop().unwrap_or(0);
+	<DatCount>::put(dat_count);
+	// pushed from the highest down, so the lowest free index is still the first reused.
+	for index in free_ids.into_iter().rev() {
+		if index < dat_count && !<DatKey>::exists(index) {
+			<Module<T>>::release_dat_index(index);
+		}
+	}
+
+	// Users: linked_map UserIdIndex => T::AccountId, at the indeces listed in
+	// UsersCount: Vec<UserIdIndex>. The v1 indeces overlap the v0 ones, so every user is read
+	// before any is written.
+	let user_ids = unhashed::take::<Vec<UserIdIndex>>(&v0_key(b"UsersCount", &[])).unwrap_or_default();
+	let users: Vec<T::AccountId> = user_ids.into_iter()
+		.filter_map(|index| {
+			// the linkage following the account is left undecoded.
+			unhashed::take::<T::AccountId>(&v0_key(b"Users", &Twox256::hash(&index.encode())))
+		})
+		.collect();
+	unhashed::kill(&v0_key(b"HeadOfUsers", &[]));
+	for user in users.iter() {
+		// UsersStorage: map T::AccountId => Vec<DatIdIndex>, rebuilt from DatHosters below.
+		unhashed::kill(&v0_key(b"UsersStorage", &Blake2_256::hash(&user.encode())));
+		<Module<T>>::insert_user(user);
+	}
+
+	// DatHosters: map Public => Vec<T::AccountId>
+	for index in 0..dat_count {
+		if !<DatKey>::exists(index) {
+			continue;
+		}
+		let archive = <DatKey>::get(index);
+		<DatIndex>::insert(&archive, index);
+		let hosters_key = v0_key(b"DatHosters", &Blake2_256::hash(&archive.encode()));
+		for hoster in unhashed::take::<Vec<T::AccountId>>(&hosters_key).unwrap_or_default() {
+			unhashed::kill(&v0_key(b"UsersStorage", &Blake2_256::hash(&hoster.encode())));
+			<Module<T>>::insert_hoster(&archive, &hoster);
+		}
+	}
+
+	frame_support::print("Finished migrating DatVerify storage to v1.");
+}
+
+// migrate storage from v1 to v2.
+//
+// this adds `OpenChallenges`, the challenges open to every challenged seeder, filled in from
+// `ChallengeMap` so the challenges open at the upgrade are still found by their seeders.
+pub fn to_v2<T: Trait>(version: &mut VersionNumber) {
+	if *version != 1 { return }
+	*version += 1;
+
+	let mut open: Vec<(u64, u64)> = <ChallengeMap>::enumerate()
+		.map(|(challenge_index, user_index)| (user_index, challenge_index))
+		.collect();
+	// oldest first, as `issue_challenge` pushes them.
+	open.sort_unstable();
+	for (user_index, challenge_index) in open {
+		<OpenChallenges>::mutate(user_index, |open| open.push(challenge_index));
+	}
+
+	frame_support::print("Finished migrating DatVerify storage to v2.");
+}
+
+/// Perform all necessary storage migrations to get storage into the expected state for current
+/// logic. No-op if fully upgraded.
+pub(crate) fn perform_migrations<T: Trait>() {
+	StorageVersion::mutate(|version| {
+		if *version == CURRENT_VERSION { return }
+
+		to_v1::<T>(version);
+		to_v2::<T>(version);
+	});
+}
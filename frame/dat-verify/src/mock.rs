@@ -0,0 +1,505 @@
+//! Test utilities
+
+use super::*;
+
+use std::{cell::RefCell, sync::Arc};
+use parking_lot::RwLock;
+use frame_support::{
+	impl_outer_origin, impl_outer_event, parameter_types, ord_parameter_types,
+	weights::Weight,
+};
+use sp_core::{
+	H256, Pair, sr25519,
+	offchain::{
+		OffchainExt, TransactionPoolExt,
+		testing::{TestOffchainExt, TestTransactionPoolExt, OffchainState, PoolState},
+	},
+	testing::KeyStore,
+	traits::{KeystoreExt, BareCryptoStorePtr},
+};
+use sp_runtime::{
+	Perbill,
+	traits::{BlakeTwo256, IdentityLookup, ConvertInto, IdentifyAccount},
+	testing::{Header, UintAuthorityId, TestXt},
+};
+use sp_staking::offence::ReportOffence;
+use frame_system::{EnsureSignedBy, offchain::{CreateTransaction, Signer, TransactionSubmitter}};
+use crate as dat_verify;
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		pallet_balances<T>,
+		pallet_session,
+		pallet_scored_pool<T>,
+		dat_verify<T>,
+	}
+}
+
+// For testing the module, we construct most of a mock runtime. This means
+// first constructing a configuration type (`Test`) which `impl`s each of the
+// configuration traits of modules we want to use.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = Call<Test>;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const CreationFee: u64 = 0;
+}
+
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type OnReapAccount = System;
+	type OnNewAccount = ();
+	type Event = TestEvent;
+	type TransferPayment = ();
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type CreationFee = CreationFee;
+}
+
+parameter_types! {
+	pub const Period: u64 = 1;
+	pub const Offset: u64 = 0;
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(33);
+}
+
+impl pallet_session::Trait for Test {
+	type Event = TestEvent;
+	type ValidatorId = u64;
+	// every account can be identified, so every failed challenge is reported.
+	type ValidatorIdOf = ConvertInto;
+	type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
+	type SessionManager = ();
+	type SessionHandler = pallet_session::TestSessionHandler;
+	type Keys = UintAuthorityId;
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+}
+
+impl pallet_session::historical::Trait for Test {
+	type FullIdentification = u64;
+	type FullIdentificationOf = ConvertInto;
+}
+
+parameter_types! {
+	pub const TrustedPeriod: u64 = 1;
+}
+
+impl pallet_scored_pool::Trait for Test {
+	type Currency = Balances;
+	type Score = Reputation;
+	type Event = TestEvent;
+	type CandidateDeposit = NoCandidateDeposit;
+	type Period = TrustedPeriod;
+	type MembershipInitialized = ();
+	type MembershipChanged = ();
+	type ScoreOrigin = frame_system::EnsureRoot<u64>;
+	type KickOrigin = frame_system::EnsureRoot<u64>;
+}
+
+type IdentificationTuple = (u64, u64);
+pub type TestOffence = ChallengeFailureOffence<IdentificationTuple>;
+
+thread_local! {
+	pub static OFFENCES: RefCell<Vec<(Vec<u64>, TestOffence)>> = RefCell::new(vec![]);
+	pub static DEALS: RefCell<Vec<(Public, u32, u64)>> = RefCell::new(vec![]);
+	pub static CHALLENGES_PER_BLOCK: RefCell<u32> = RefCell::new(1);
+	pub static DETECTION_PROBABILITY: RefCell<Perbill> = RefCell::new(Perbill::from_percent(50));
+	pub static LOCAL_SEEDERS: RefCell<Vec<(sr25519::Public, u64)>> = RefCell::new(vec![]);
+}
+
+/// The most challenges in a block, set with `set_challenges_per_block`.
+pub struct ChallengesPerBlock;
+impl Get<u32> for ChallengesPerBlock {
+	fn get() -> u32 {
+		CHALLENGES_PER_BLOCK.with(|v| *v.borrow())
+	}
+}
+
+/// Allow `challenges` challenges to be issued, and to expire, in every block.
+pub fn set_challenges_per_block(challenges: u32) {
+	CHALLENGES_PER_BLOCK.with(|v| *v.borrow_mut() = challenges);
+}
+
+/// The chance of catching a seeder who lost half of their archives, set with
+/// `set_detection_probability`. It starts at a single chunk per challenge.
+pub struct DetectionProbability;
+impl Get<Perbill> for DetectionProbability {
+	fn get() -> Perbill {
+		DETECTION_PROBABILITY.with(|v| *v.borrow())
+	}
+}
+
+/// Have challenges catch a seeder who lost half of their archives with `probability`.
+pub fn set_detection_probability(probability: Perbill) {
+	DETECTION_PROBABILITY.with(|v| *v.borrow_mut() = probability);
+}
+
+/// A mock offence report handler.
+pub struct OffenceHandler;
+impl ReportOffence<u64, IdentificationTuple, TestOffence> for OffenceHandler {
+	fn report_offence(reporters: Vec<u64>, offence: TestOffence) {
+		OFFENCES.with(|l| l.borrow_mut().push((reporters, offence)));
+	}
+}
+
+/// Mock storage deals, set with `set_deal`.
+pub struct MockDeals;
+impl StorageDeals for MockDeals {
+	fn replication(archive: &Public) -> u32 {
+		DEALS.with(|l| l.borrow().iter().find(|deal| deal.0 == *archive).map_or(0, |deal| deal.1))
+	}
+
+	fn price_per_block(archive: &Public) -> u64 {
+		DEALS.with(|l| l.borrow().iter().find(|deal| deal.0 == *archive).map_or(0, |deal| deal.2))
+	}
+
+	fn on_unregister(archive: &Public) {
+		DEALS.with(|l| l.borrow_mut().retain(|deal| deal.0 != *archive));
+	}
+}
+
+/// An extrinsic type used for tests.
+pub type Extrinsic = TestXt<Call<Test>, ()>;
+type SubmitTransaction = TransactionSubmitter<offchain::crypto::SeederId, Test, Extrinsic>;
+
+/// A seeder key in the keystore, standing for the account it was added for with
+/// `add_local_seeder`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SeederKey(sr25519::Public);
+
+impl From<sr25519::Public> for SeederKey {
+	fn from(public: sr25519::Public) -> Self {
+		SeederKey(public)
+	}
+}
+
+impl From<SeederKey> for sr25519::Public {
+	fn from(key: SeederKey) -> Self {
+		key.0
+	}
+}
+
+impl IdentifyAccount for SeederKey {
+	type AccountId = u64;
+
+	fn into_account(self) -> u64 {
+		LOCAL_SEEDERS.with(|l| l.borrow().iter()
+			.find(|(public, _)| *public == self.0)
+			.map_or(0, |(_, account)| *account))
+	}
+}
+
+impl CreateTransaction<Test, Extrinsic> for Test {
+	type Public = SeederKey;
+	type Signature = sr25519::Signature;
+
+	fn create_transaction<F: Signer<Self::Public, Self::Signature>>(
+		call: Call<Test>,
+		public: Self::Public,
+		account: u64,
+		_index: u64,
+	) -> Option<(Call<Test>, (u64, ()))> {
+		// test extrinsics carry no signature, but the key must be able to make one.
+		F::sign(public, &call)?;
+		Some((call, (account, ())))
+	}
+}
+
+ord_parameter_types! {
+	pub const ForceAccount: u64 = 100;
+}
+
+parameter_types! {
+	pub const SeederBond: u64 = 10;
+	pub const BondPerGigabyte: u64 = 2;
+	pub const ChallengeSlash: Perbill = Perbill::from_percent(50);
+	pub const ChallengeReward: u64 = 5;
+	pub const DetectableLoss: Perbill = Perbill::from_percent(50);
+	pub const MaxChunksPerChallenge: u32 = 8;
+	pub const MaxChunkSize: u32 = 1024;
+	pub const MaxProofNodes: u32 = 32;
+	pub const MaxRootChildren: u32 = 8;
+	pub const AttestorsPerChallenge: u32 = 3;
+	pub const AttestationQuorum: u32 = 2;
+	pub const AttestationReward: u64 = 3;
+	pub const ProofReputation: Reputation = 10;
+	pub const FailureReputation: Reputation = 30;
+	pub const AttestationReputation: Reputation = 5;
+	pub const MaxReputation: Reputation = 100;
+	pub const ReputationDecay: Perbill = Perbill::from_percent(10);
+	pub const ReputationPeriod: u64 = 10;
+	pub const ReputationsDecayedPerBlock: u32 = 2;
+	pub const HostingsReleasedPerBlock: u32 = 2;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type Randomness = ();
+	type ForceOrigin = EnsureSignedBy<ForceAccount, u64>;
+	type SeederMembership = ();
+	type UserMembership = ();
+	type Currency = Balances;
+	type SeederBond = SeederBond;
+	type BondPerGigabyte = BondPerGigabyte;
+	type ChallengeSlash = ChallengeSlash;
+	type ChallengeReward = ChallengeReward;
+	type ChallengesPerBlock = ChallengesPerBlock;
+	type DetectableLoss = DetectableLoss;
+	type DetectionProbability = DetectionProbability;
+	type MaxChunksPerChallenge = MaxChunksPerChallenge;
+	type MaxChunkSize = MaxChunkSize;
+	type MaxProofNodes = MaxProofNodes;
+	type MaxRootChildren = MaxRootChildren;
+	type AttestorsPerChallenge = AttestorsPerChallenge;
+	type AttestationQuorum = AttestationQuorum;
+	type AttestationReward = AttestationReward;
+	type ProofReputation = ProofReputation;
+	type FailureReputation = FailureReputation;
+	type AttestationReputation = AttestationReputation;
+	type MaxReputation = MaxReputation;
+	type ReputationDecay = ReputationDecay;
+	type ReputationPeriod = ReputationPeriod;
+	type ReputationsDecayedPerBlock = ReputationsDecayedPerBlock;
+	type HostingsReleasedPerBlock = HostingsReleasedPerBlock;
+	type TrustedSeeders = ScoredPool<Test, pallet_scored_pool::DefaultInstance>;
+	type ReportChallengeFailure = OffenceHandler;
+	type StorageDeals = MockDeals;
+	type Call = Call<Test>;
+	type SubmitTransaction = SubmitTransaction;
+}
+
+// the module is the whole runtime, so every call is one of its own.
+impl IsSubType<Module<Test>, Test> for Call<Test> {
+	fn is_sub_type(&self) -> Option<&Call<Test>> {
+		Some(self)
+	}
+}
+
+pub type DatVerify = Module<Test>;
+pub type System = frame_system::Module<Test>;
+pub type Balances = pallet_balances::Module<Test>;
+pub type TrustedPool = pallet_scored_pool::Module<Test>;
+
+/// The account publishing archives in the tests.
+pub const PUBLISHER: u64 = 1;
+/// The account seeding archives in the tests.
+pub const SEEDER: u64 = 2;
+/// An account without funds.
+pub const PAUPER: u64 = 3;
+/// The accounts seeding alongside `SEEDER` to attest it.
+pub const ATTESTORS: [u64; 3] = [4, 5, 6];
+/// What every funded account, and the reward pot, starts with.
+pub const INITIAL_BALANCE: u64 = 100;
+/// The number of trusted seeders.
+pub const TRUSTED_SEEDERS: u32 = 2;
+
+/// The keypair of the archive with the given seed byte.
+pub fn archive_pair(seed: u8) -> ed25519::Pair {
+	ed25519::Pair::from_seed(&[seed; 32])
+}
+
+/// The registration key of the feed of `pair`, which registers it privately.
+pub fn registration_pair(pair: &ed25519::Pair) -> ed25519::Pair {
+	ed25519::Pair::from_seed(&hashing::registration_seed(pair.seed()))
+}
+
+/// A single leaf archive holding `chunk`, with its leaf node and signed root.
+pub fn single_chunk_archive(pair: &ed25519::Pair, chunk: &[u8]) -> (Node, RootHashPayload, H512) {
+	let leaf_hash = ChunkHashPayload {
+		hash_type: 0,
+		chunk_length: chunk.len() as u64,
+		chunk_content: chunk.to_vec(),
+	}.hash();
+	let leaf = Node {
+		index: 0,
+		hash: leaf_hash,
+		size: chunk.len() as u64,
+	};
+	let root = RootHashPayload {
+		hash_type: 2,
+		children: vec![ParentHashInRoot {
+			hash: leaf_hash,
+			hash_number: 0,
+			total_length: chunk.len() as u64,
+		}],
+	};
+	let signature = pair.sign(root.hash().as_bytes());
+	(leaf, root, H512::from(signature))
+}
+
+/// Every full node of the Merkle tree over `chunks`, leaves first.
+pub fn tree_nodes(chunks: &[&[u8]]) -> Vec<Node> {
+	let mut nodes: Vec<Node> = chunks.iter().enumerate().map(|(i, chunk)| Node {
+		index: 2 * i as u64,
+		hash: hashing::leaf(chunk),
+		size: chunk.len() as u64,
+	}).collect();
+	let mut level = nodes.clone();
+	while level.len() > 1 {
+		level = level.chunks(2).filter(|pair| pair.len() == 2).map(|pair| Node {
+			index: (pair[0].index + pair[1].index) / 2,
+			hash: hashing::parent(&pair[0].hash, &pair[1].hash, pair[0].size + pair[1].size),
+			size: pair[0].size + pair[1].size,
+		}).collect();
+		nodes.extend(level.iter().cloned());
+	}
+	nodes
+}
+
+/// The root payload made of the `roots` in `nodes`, and its signature by `pair`.
+pub fn signed_roots(pair: &ed25519::Pair, nodes: &[Node], roots: &[u64]) -> (RootHashPayload, H512) {
+	let root = RootHashPayload {
+		hash_type: 2,
+		children: roots.iter().map(|index| {
+			let node = Node::find(nodes, *index).expect("roots are part of the tree");
+			ParentHashInRoot {
+				hash: node.hash,
+				hash_number: node.index,
+				total_length: node.size,
+			}
+		}).collect(),
+	};
+	let signature = pair.sign(root.hash().as_bytes());
+	(root, H512::from(signature))
+}
+
+/// A proof that the tree signed with `old_root` is extended by the tree of `nodes`, made of the
+/// `proof` nodes of that tree.
+pub fn consistency_proof(old_root: &RootHashPayload, nodes: &[Node], proof: &[u64]) -> ConsistencyProof {
+	ConsistencyProof {
+		old_roots: old_root.children.clone(),
+		nodes: proof.iter()
+			.map(|index| Node::find(nodes, *index).expect("proof nodes are part of the tree").clone())
+			.collect(),
+	}
+}
+
+/// Have the deal of `archive` pay `price_per_block` to `replication` hosters.
+pub fn set_deal(archive: Public, replication: u32, price_per_block: u64) {
+	MockDeals::on_unregister(&archive);
+	DEALS.with(|l| l.borrow_mut().push((archive, replication, price_per_block)));
+}
+
+/// Challenge `SEEDER` to prove `leaf` of `dat` as challenge 0.
+pub fn challenge_seeder(dat: Public, leaf: u64) {
+	challenge_chunks(&[(dat, leaf)]);
+}
+
+/// Challenge `SEEDER` to prove the leaves of `chunks` as challenge 0, until block 10.
+pub fn challenge_chunks(chunks: &[(Public, u64)]) {
+	<ChallengeMap>::insert(0, 0);
+	<SelectedUsers<Test>>::insert(0, SEEDER);
+	<SelectedUserIndex<Test>>::insert(SEEDER, (0, 1));
+	<OpenChallenges>::insert(0, vec![0]);
+	<SelectedChallenges<Test>>::insert(0, (chunks.to_vec(), 10));
+	<ChallengeExpiries<Test>>::insert(10, vec![0]);
+}
+
+/// Ask `attestors` to attest `SEEDER` serving `dat` for challenge 0, until block 10.
+pub fn request_attestations(dat: Public, attestors: &[u64]) {
+	for attestor in attestors {
+		<Attestors<Test>>::insert(attestor, 0);
+	}
+	<ChallengeAttestations<Test>>::insert(0, (attestors.to_vec(), Vec::<(u64, Attestation)>::new()));
+	<AttestationRounds<Test>>::insert(0, (SEEDER, dat, 10));
+	<ChallengeExpiries<Test>>::insert(10, vec![0]);
+}
+
+/// Add a seeder key for `account` to `keystore`.
+pub fn add_local_seeder(keystore: &BareCryptoStorePtr, account: u64) {
+	let public = keystore.write()
+		.sr25519_generate_new(offchain::KEY_TYPE, Some(&format!("//Seeder{}", account)))
+		.expect("test keys can be generated");
+	LOCAL_SEEDERS.with(|l| l.borrow_mut().push((public, account)));
+}
+
+/// The events deposited by this module so far.
+pub fn dat_verify_events() -> Vec<Event<Test>> {
+	System::events().into_iter().filter_map(|record| match record.event {
+		TestEvent::dat_verify(inner) => Some(inner),
+		_ => None,
+	}).collect()
+}
+
+/// The offences reported so far.
+pub fn reported_offences() -> Vec<(Vec<u64>, TestOffence)> {
+	OFFENCES.with(|l| l.borrow().clone())
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	genesis_test_ext(Default::default())
+}
+
+/// `new_test_ext` starting from the archives and seeders of `dat_verify`.
+pub fn genesis_test_ext(dat_verify: GenesisConfig<Test>) -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![
+			(PUBLISHER, INITIAL_BALANCE),
+			(SEEDER, INITIAL_BALANCE),
+			(ATTESTORS[0], INITIAL_BALANCE),
+			(ATTESTORS[1], INITIAL_BALANCE),
+			(ATTESTORS[2], INITIAL_BALANCE),
+			(DatVerify::pot_account(), INITIAL_BALANCE),
+		],
+	}.assimilate_storage(&mut t).unwrap();
+	pallet_scored_pool::GenesisConfig::<Test> {
+		pool: vec![],
+		member_count: TRUSTED_SEEDERS,
+		members: vec![],
+		phantom: Default::default(),
+	}.assimilate_storage(&mut t).unwrap();
+	dat_verify.assimilate_storage(&mut t).unwrap();
+	let mut ext: sp_io::TestExternalities = t.into();
+	// events are not stored at genesis.
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// `new_test_ext` with offchain storage, a transaction pool, and a keystore holding the keys
+/// of `local_seeders`.
+pub fn new_offchain_test_ext(
+	local_seeders: &[u64],
+) -> (sp_io::TestExternalities, Arc<RwLock<OffchainState>>, Arc<RwLock<PoolState>>) {
+	let mut ext = new_test_ext();
+	let (offchain, offchain_state) = TestOffchainExt::new();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	let keystore = KeyStore::new();
+	for account in local_seeders {
+		add_local_seeder(&keystore, *account);
+	}
+	ext.register_extension(OffchainExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+	ext.register_extension(KeystoreExt(keystore));
+	(ext, offchain_state, pool_state)
+}
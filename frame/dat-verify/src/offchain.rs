@@ -0,0 +1,246 @@
+//! Answering challenges from the offchain worker.
+//!
+//! The offchain worker looks for open challenges on the seeder accounts in the local keystore,
+//! under the `datv` key type. The chunks and proof nodes a challenge asks for live in hypercore
+//! feeds on the node's disk, which the worker cannot read itself. Instead, the worker queues a
+//! `ChunkRequest` for every challenged archive in the persistent offchain storage under
+//! `REQUESTS_KEY`, and whatever serves the feeds answers with a `ChunkResponse` under
+//! `RESPONSES_KEY`. The node does so when started with a `--dat-dir`.
+//!
+//! The worker never waits for the node: it queues the requests of a challenge and moves on, and
+//! the worker of a later block builds a proof of every archive once all the responses are in,
+//! signs `submit_proof` with the challenged account and submits it, once per challenge.
+
+use sp_std::prelude::*;
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::{RuntimeDebug, offchain::storage::StorageValueRef};
+use frame_support::{debug, StorageMap, StorageDoubleMap, StorageLinkedMap};
+use frame_system::offchain::SubmitSignedTransaction;
+use codec::{Encode, Decode};
+use crate::{Trait, Module, Call, Public, Node, Proof, ChallengeMap, DatHosters, TreeLength};
+
+/// The key type of the seeder accounts the offchain worker answers challenges for.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"datv");
+
+/// The offchain storage key of the queued `ChunkRequest`s.
+pub const REQUESTS_KEY: &[u8] = b"dat_verify::requests";
+
+/// The offchain storage key of the `ChunkResponse`s yet to be used by the worker.
+pub const RESPONSES_KEY: &[u8] = b"dat_verify::responses";
+
+// the challenges the worker submitted a proof for, and which may still be open.
+const SUBMITTED_KEY: &[u8] = b"dat_verify::submitted";
+
+pub mod crypto {
+	mod app_sr25519 {
+		use sp_application_crypto::{app_crypto, sr25519};
+		app_crypto!(sr25519, crate::offchain::KEY_TYPE);
+	}
+
+	/// A seeder keypair using sr25519 as its crypto.
+	#[cfg(feature = "std")]
+	pub type SeederPair = app_sr25519::Pair;
+
+	/// A seeder signature using sr25519 as its crypto.
+	pub type SeederSignature = app_sr25519::Signature;
+
+	/// A seeder identifier using sr25519 as its crypto.
+	pub type SeederId = app_sr25519::Public;
+}
+
+/// Chunks of a feed, and the tree nodes proving them, for the node to read.
+#[derive(Decode, PartialEq, Eq, Encode, Clone, RuntimeDebug)]
+pub struct ChunkRequest {
+	/// The key of the feed.
+	pub archive: Public,
+	/// The flat-tree indeces of the leaves of the chunks, in ascending order.
+	pub leaves: Vec<u64>,
+	/// The flat-tree indeces of the nodes to read, see `proof_nodes`.
+	pub nodes: Vec<u64>,
+}
+
+/// What the node read for a `ChunkRequest`.
+#[derive(Decode, PartialEq, Eq, Encode, Clone, RuntimeDebug)]
+pub struct ChunkResponse {
+	/// The key of the feed.
+	pub archive: Public,
+	/// The flat-tree indeces of the leaves of the chunks.
+	pub leaves: Vec<u64>,
+	/// The content of every chunk, in the order of `leaves`.
+	pub chunks: Vec<Vec<u8>>,
+	/// The requested nodes.
+	pub nodes: Vec<Node>,
+}
+
+/// The flat-tree indeces of the nodes proving the `leaves` of a tree of `length` chunks, in
+/// ascending order: the siblings on the paths of the leaves, and the roots of the tree, but for
+/// the nodes on those paths, which are recomputed from the chunks.
+///
+/// Leaves past the end of the tree are ignored.
+pub fn proof_nodes(leaves: &[u64], length: u64) -> Vec<u64> {
+	let roots = flat_tree::full_roots(2 * length);
+	let mut paths = Vec::new();
+	for leaf in leaves.iter().filter(|leaf| **leaf < 2 * length) {
+		let mut current = *leaf;
+		paths.push(current);
+		while !roots.contains(&current) {
+			current = flat_tree::parent(current);
+			paths.push(current);
+		}
+	}
+	let mut nodes: Vec<u64> = paths.iter()
+		.filter(|index| !roots.contains(index))
+		.map(|index| flat_tree::sibling(*index))
+		.chain(roots.iter().cloned())
+		.filter(|index| !paths.contains(index))
+		.collect();
+	nodes.sort_unstable();
+	nodes.dedup();
+	nodes
+}
+
+impl<T: Trait> Module<T> {
+	/// Submit a proof for every open challenge of the seeders in the local keystore.
+	pub(crate) fn answer_challenges() {
+		if !T::SubmitTransaction::can_sign() {
+			return;
+		}
+		let submitted = Self::submitted_challenges();
+		for (account, _) in T::SubmitTransaction::find_all_local_keys() {
+			for challenge in Self::challenges_for(account.clone()) {
+				if submitted.contains(&challenge.index) {
+					continue;
+				}
+				// archives unregistered since the challenge need no proof.
+				let archives: Vec<(Public, Vec<u64>, bool)> = Self::challenged_archives(&challenge.chunks)
+					.into_iter()
+					.map(|(archive, leaves)| {
+						let hosted = <DatHosters<T>>::exists(&archive, &account);
+						(archive, leaves, hosted)
+					})
+					.collect();
+				// challenges on archives all unregistered lapse without a proof.
+				if !archives.iter().any(|(_, _, hosted)| *hosted) {
+					continue;
+				}
+				let mut waiting = false;
+				for (archive, leaves, _) in archives.iter().filter(|(_, _, hosted)| *hosted) {
+					if !Self::has_response(archive, leaves) {
+						let nodes = proof_nodes(leaves, <TreeLength>::get(archive));
+						Self::request_chunks(ChunkRequest { archive: *archive, leaves: leaves.clone(), nodes });
+						waiting = true;
+					}
+				}
+				if waiting {
+					debug::info!(
+						target: "dat_verify",
+						"Waiting for the node to read the chunks of challenge {}",
+						challenge.index,
+					);
+					continue;
+				}
+				let proofs: Option<Vec<Proof>> = archives.iter()
+					.map(|(archive, leaves, hosted)| if *hosted {
+						Self::take_response(archive, leaves)
+							.map(|response| Proof { chunks: response.chunks, nodes: response.nodes })
+					} else {
+						Some(Proof::default())
+					})
+					.collect();
+				match proofs {
+					Some(proofs) => Self::submit_proofs(account.clone(), challenge.index, proofs),
+					None => debug::warn!(
+						target: "dat_verify",
+						"The chunks of challenge {} were taken while proving it",
+						challenge.index,
+					),
+				}
+			}
+		}
+	}
+
+	/// Whether the node answered the request for the chunks at `leaves` of `archive`.
+	fn has_response(archive: &Public, leaves: &[u64]) -> bool {
+		StorageValueRef::persistent(RESPONSES_KEY)
+			.get::<Vec<ChunkResponse>>()
+			.and_then(|stored| stored)
+			.unwrap_or_default()
+			.iter()
+			.any(|stored| stored.archive == *archive && stored.leaves == leaves)
+	}
+
+	/// Queue `request` for the node, unless it already is.
+	fn request_chunks(request: ChunkRequest) {
+		let requests = StorageValueRef::persistent(REQUESTS_KEY);
+		let queued = requests.mutate(|stored: Option<Option<Vec<ChunkRequest>>>| -> Result<_, ()> {
+			let mut stored = stored.and_then(|stored| stored).unwrap_or_default();
+			if !stored.contains(&request) {
+				stored.push(request);
+			}
+			Ok(stored)
+		});
+		if let Ok(Err(_)) = queued {
+			debug::warn!(target: "dat_verify", "Chunk requests changed while queueing one");
+		}
+	}
+
+	/// Remove and return the response for the chunks at `leaves` of `archive`, if there is one.
+	fn take_response(archive: &Public, leaves: &[u64]) -> Option<ChunkResponse> {
+		let responses = StorageValueRef::persistent(RESPONSES_KEY);
+		let mut response = None;
+		let taken = responses.mutate(|stored: Option<Option<Vec<ChunkResponse>>>| -> Result<_, ()> {
+			let mut stored = stored.and_then(|stored| stored).unwrap_or_default();
+			let position = stored.iter()
+				.position(|stored| stored.archive == *archive && stored.leaves == leaves)
+				.ok_or(())?;
+			response = Some(stored.remove(position));
+			Ok(stored)
+		});
+		// a response taken concurrently is only used once.
+		match taken {
+			Ok(Ok(_)) => response,
+			_ => None,
+		}
+	}
+
+	/// Sign and submit the `proofs` of the archives of `challenge_index` with `account`.
+	fn submit_proofs(account: T::AccountId, challenge_index: u64, proofs: Vec<Proof>) {
+		let call = Call::<T>::submit_proof(challenge_index, proofs);
+		for (account, result) in T::SubmitTransaction::submit_signed_from(call, vec![account]) {
+			match result {
+				Ok(()) => Self::note_submitted(challenge_index),
+				Err(()) => debug::warn!(
+					target: "dat_verify",
+					"Failed to submit the proof of {:?} for challenge {}",
+					account,
+					challenge_index,
+				),
+			}
+		}
+	}
+
+	/// The challenges a proof was submitted for, which are still open.
+	fn submitted_challenges() -> Vec<u64> {
+		StorageValueRef::persistent(SUBMITTED_KEY)
+			.get::<Vec<u64>>()
+			.and_then(|submitted| submitted)
+			.unwrap_or_default()
+			.into_iter()
+			.filter(|challenge_index| <ChallengeMap>::exists(challenge_index))
+			.collect()
+	}
+
+	/// Remember that a proof was submitted for `challenge_index`, forgetting closed challenges.
+	fn note_submitted(challenge_index: u64) {
+		let submitted = StorageValueRef::persistent(SUBMITTED_KEY);
+		let noted = submitted.mutate(|stored: Option<Option<Vec<u64>>>| -> Result<_, ()> {
+			let mut stored = stored.and_then(|stored| stored).unwrap_or_default();
+			stored.retain(|index| <ChallengeMap>::exists(index));
+			stored.push(challenge_index);
+			Ok(stored)
+		});
+		if let Ok(Err(_)) = noted {
+			debug::warn!(target: "dat_verify", "Submitted challenges changed while noting one");
+		}
+	}
+}
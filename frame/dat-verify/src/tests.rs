@@ -0,0 +1,2562 @@
+//! Tests for the module.
+
+use super::*;
+use mock::*;
+
+use frame_support::{assert_ok, assert_noop, weights::{WeighBlock, GetDispatchInfo}, StorageHasher, Blake2_256, Twox256};
+use sp_core::Pair;
+use sp_runtime::{
+	offchain::storage::StorageValueRef,
+	traits::{OnInitialize, OnFinalize, OffchainWorker, BadOrigin},
+};
+use offchain::{ChunkRequest, ChunkResponse, REQUESTS_KEY, RESPONSES_KEY, proof_nodes};
+
+/// Register a single chunk archive for `PUBLISHER`.
+fn register_single_chunk(pair: &ed25519::Pair, chunk: &[u8]) -> Node {
+	let (leaf, root, signature) = single_chunk_archive(pair, chunk);
+	assert_ok!(DatVerify::register_data(
+		Origin::signed(PUBLISHER),
+		(pair.public(), root, signature),
+		None,
+	));
+	leaf
+}
+
+/// Register a single chunk archive for `PUBLISHER` and have `SEEDER` pin it.
+fn register_and_seed(pair: &ed25519::Pair, chunk: &[u8]) -> Node {
+	let leaf = register_single_chunk(pair, chunk);
+	assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+	leaf
+}
+
+/// The indeces of the archives hosted by `account`, in ascending order.
+fn hosted_indeces(account: u64) -> Vec<DatIdIndex> {
+	let mut indeces: Vec<DatIdIndex> = DatVerify::archives_hosted_by(account).into_iter()
+		.map(|archive| DatVerify::dat_index(archive).expect("hosted archives are registered"))
+		.collect();
+	indeces.sort_unstable();
+	indeces
+}
+
+#[test]
+fn register_data_works() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let (_, root, signature) = single_chunk_archive(&pair, b"x");
+		assert_ok!(DatVerify::register_data(
+			Origin::signed(PUBLISHER),
+			(pair.public(), root.clone(), signature),
+			None,
+		));
+
+		assert_eq!(DatVerify::public_key(0), pair.public());
+		assert_eq!(DatVerify::tree_size(pair.public()), 1);
+		assert_eq!(DatVerify::tree_length(pair.public()), 1);
+		assert_eq!(DatVerify::tree_version(pair.public()), 0);
+		assert_eq!(
+			DatVerify::merkle_root(pair.public()),
+			(root.hash(), Signature::from_h512(signature)),
+		);
+		assert_eq!(<UserRequestsMap<Test>>::get(pair.public()), PUBLISHER);
+		assert_eq!(DatVerify::dat_index(pair.public()), Some(0));
+		assert_eq!(DatVerify::dat_count(), 1);
+		assert_eq!(dat_verify_events(), vec![RawEvent::SomethingStored(0, pair.public())]);
+	});
+}
+
+#[test]
+fn register_data_rejects_bad_signature() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let (_, root, _) = single_chunk_archive(&pair, b"x");
+		let signature = H512::from(pair.sign(b"not the root hash"));
+		assert_noop!(
+			DatVerify::register_data(Origin::signed(PUBLISHER), (pair.public(), root, signature), None),
+			Error::<Test>::VerificationFailed,
+		);
+	});
+}
+
+#[test]
+fn register_data_reuses_freed_indeces() {
+	new_test_ext().execute_with(|| {
+		for seed in 1..=3 {
+			let pair = archive_pair(seed);
+			let (_, root, signature) = single_chunk_archive(&pair, b"x");
+			assert_ok!(DatVerify::register_data(
+				Origin::signed(PUBLISHER),
+				(pair.public(), root, signature),
+				None,
+			));
+		}
+		assert_eq!(DatVerify::dat_count(), 3);
+
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 0));
+		assert_eq!(DatVerify::free_dat_id_count(), 1);
+		assert_eq!(<FreeDatIds>::get(0), 0);
+		// an index is only freed once.
+		assert_noop!(
+			DatVerify::unregister_data(Origin::signed(PUBLISHER), 0),
+			Error::<Test>::PermissionError,
+		);
+
+		let pair = archive_pair(4);
+		let (_, root, signature) = single_chunk_archive(&pair, b"x");
+		assert_ok!(DatVerify::register_data(
+			Origin::signed(PUBLISHER),
+			(pair.public(), root, signature),
+			None,
+		));
+		assert_eq!(DatVerify::public_key(0), pair.public());
+		assert_eq!(DatVerify::public_key(1), archive_pair(2).public());
+		assert_eq!(DatVerify::dat_index(pair.public()), Some(0));
+		assert_eq!(DatVerify::free_dat_id_count(), 0);
+		assert_eq!(DatVerify::dat_count(), 3);
+	});
+}
+
+#[test]
+fn updating_a_known_archive_keeps_its_index() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let (_, root, signature) = single_chunk_archive(&pair, b"x");
+		assert_ok!(DatVerify::register_data(
+			Origin::signed(PUBLISHER),
+			(pair.public(), root.clone(), signature),
+			None,
+		));
+		// the same tree trivially extends itself.
+		let proof = consistency_proof(&root, &[], &[]);
+		assert_ok!(DatVerify::register_data(
+			Origin::signed(PUBLISHER),
+			(pair.public(), root, signature),
+			Some(proof),
+		));
+		assert_eq!(DatVerify::dat_count(), 1);
+		assert_eq!(DatVerify::public_key(0), pair.public());
+		assert_eq!(DatVerify::tree_version(pair.public()), 1);
+	});
+}
+
+/// Register the two chunk archive `hello`, `world` of `pair`, returning its signed roots.
+fn register_two_chunk_archive(pair: &ed25519::Pair) -> RootHashPayload {
+	let nodes = tree_nodes(&[b"hello", b"world"]);
+	let (root, signature) = signed_roots(pair, &nodes, &[1]);
+	assert_ok!(DatVerify::register_data(
+		Origin::signed(PUBLISHER),
+		(pair.public(), root.clone(), signature),
+		None,
+	));
+	root
+}
+
+#[test]
+fn register_data_tracks_tree_length_and_version() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let old_root = register_two_chunk_archive(&pair);
+		assert_eq!(DatVerify::tree_length(pair.public()), 2);
+		assert_eq!(DatVerify::tree_version(pair.public()), 0);
+
+		let nodes = tree_nodes(&[b"hello", b"world", b"this is", b"a hypercore"]);
+		let (root, signature) = signed_roots(&pair, &nodes, &[3]);
+		// the old root 1 and its sibling 5 make the new root 3.
+		let proof = consistency_proof(&old_root, &nodes, &[5]);
+		assert_ok!(DatVerify::register_data(
+			Origin::signed(PUBLISHER),
+			(pair.public(), root.clone(), signature),
+			Some(proof),
+		));
+		assert_eq!(DatVerify::tree_length(pair.public()), 4);
+		assert_eq!(DatVerify::tree_size(pair.public()), 28);
+		assert_eq!(DatVerify::tree_version(pair.public()), 1);
+		assert_eq!(DatVerify::merkle_root(pair.public()).0, root.hash());
+		assert_eq!(DatVerify::dat_count(), 1);
+	});
+}
+
+#[test]
+fn register_data_updates_come_from_the_publisher() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let old_root = register_two_chunk_archive(&pair);
+		let nodes = tree_nodes(&[b"hello", b"world", b"this is", b"a hypercore"]);
+		let (root, signature) = signed_roots(&pair, &nodes, &[3]);
+		let merkle_root = (pair.public(), root, signature);
+		let proof = consistency_proof(&old_root, &nodes, &[5]);
+
+		// the signed update is public, yet replaying it does not take the archive over.
+		assert_noop!(
+			DatVerify::register_data(Origin::signed(SEEDER), merkle_root.clone(), Some(proof.clone())),
+			Error::<Test>::PermissionError,
+		);
+		assert_eq!(<UserRequestsMap<Test>>::get(pair.public()), PUBLISHER);
+		assert_ok!(DatVerify::register_data(Origin::signed(PUBLISHER), merkle_root, Some(proof)));
+		assert_eq!(DatVerify::tree_length(pair.public()), 4);
+	});
+}
+
+#[test]
+fn register_data_rejects_stale_root() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let nodes = tree_nodes(&[b"hello", b"world", b"this is", b"a hypercore"]);
+		let (root, signature) = signed_roots(&pair, &nodes, &[3]);
+		assert_ok!(DatVerify::register_data(
+			Origin::signed(PUBLISHER),
+			(pair.public(), root.clone(), signature),
+			None,
+		));
+
+		// rolling back to the first two chunks.
+		let (old_root, old_signature) = signed_roots(&pair, &nodes, &[1]);
+		let proof = consistency_proof(&root, &[], &[]);
+		assert_eq!(
+			DatVerify::register_data(
+				Origin::signed(PUBLISHER),
+				(pair.public(), old_root, old_signature),
+				Some(proof),
+			),
+			Err(Error::<Test>::StaleRoot.into()),
+		);
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::UpdateRejected(pair.public(), 4, 2)),
+		);
+		assert_eq!(DatVerify::tree_length(pair.public()), 4);
+		assert_eq!(DatVerify::tree_version(pair.public()), 0);
+		assert_eq!(DatVerify::merkle_root(pair.public()).0, root.hash());
+	});
+}
+
+#[test]
+fn register_data_requires_consistency_proof_for_updates() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let old_root = register_two_chunk_archive(&pair);
+		let (root, signature) = signed_roots(&pair, &tree_nodes(&[b"hello", b"world"]), &[1]);
+		assert_eq!(root, old_root);
+		assert_eq!(
+			DatVerify::register_data(Origin::signed(PUBLISHER), (pair.public(), root, signature), None),
+			Err(Error::<Test>::MissingConsistencyProof.into()),
+		);
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::UpdateRejected(pair.public(), 2, 2)),
+		);
+	});
+}
+
+#[test]
+fn register_data_rejects_forked_tree() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let old_root = register_two_chunk_archive(&pair);
+
+		// the second chunk was rewritten.
+		let nodes = tree_nodes(&[b"hello", b"earth", b"this is", b"a hypercore"]);
+		let (root, signature) = signed_roots(&pair, &nodes, &[3]);
+		let proof = consistency_proof(&old_root, &nodes, &[5]);
+		assert_eq!(
+			DatVerify::register_data(
+				Origin::signed(PUBLISHER),
+				(pair.public(), root.clone(), signature),
+				Some(proof),
+			),
+			Err(Error::<Test>::ConsistencyVerificationFailed.into()),
+		);
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::UpdateRejected(pair.public(), 2, 4)),
+		);
+
+		// nor can the fork claim its own old roots were the registered ones.
+		let (forked_old_root, _) = signed_roots(&pair, &nodes, &[1]);
+		let proof = consistency_proof(&forked_old_root, &nodes, &[5]);
+		assert_eq!(
+			DatVerify::register_data(Origin::signed(PUBLISHER), (pair.public(), root, signature), Some(proof)),
+			Err(Error::<Test>::ConsistencyVerificationFailed.into()),
+		);
+		assert_eq!(DatVerify::tree_length(pair.public()), 2);
+		assert_eq!(DatVerify::merkle_root(pair.public()).0, old_root.hash());
+	});
+}
+
+#[test]
+fn register_data_rejects_roots_out_of_order() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let nodes = tree_nodes(&[b"hello", b"world", b"this is"]);
+		let (root, signature) = signed_roots(&pair, &nodes, &[4, 1]);
+		assert_noop!(
+			DatVerify::register_data(Origin::signed(PUBLISHER), (pair.public(), root, signature), None),
+			Error::<Test>::InvalidTreeSize,
+		);
+	});
+}
+
+#[test]
+fn force_register_data_requires_force_origin() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let (_, root, _) = single_chunk_archive(&pair, b"x");
+		// no signature checks are done when forcing.
+		let merkle_root = (pair.public(), root, H512::zero());
+		assert_noop!(
+			DatVerify::force_register_data(Origin::signed(PUBLISHER), PUBLISHER, merkle_root.clone()),
+			BadOrigin,
+		);
+		assert_ok!(DatVerify::force_register_data(
+			Origin::signed(ForceAccount::get()),
+			PUBLISHER,
+			merkle_root.clone(),
+		));
+		assert_ok!(DatVerify::force_register_data(Origin::ROOT, PUBLISHER, merkle_root));
+		assert_eq!(DatVerify::public_key(0), pair.public());
+	});
+}
+
+#[test]
+fn force_register_data_rejects_empty_tree() {
+	new_test_ext().execute_with(|| {
+		let root = RootHashPayload {
+			hash_type: 2,
+			children: vec![],
+		};
+		assert_noop!(
+			DatVerify::force_register_data(Origin::ROOT, PUBLISHER, (archive_pair(1).public(), root, H512::zero())),
+			Error::<Test>::InvalidTreeSize,
+		);
+	});
+}
+
+#[test]
+fn unregister_data_works() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+
+		assert_noop!(
+			DatVerify::unregister_data(Origin::signed(SEEDER), 0),
+			Error::<Test>::PermissionError,
+		);
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 0));
+
+		assert!(!<MerkleRoot>::exists(pair.public()));
+		assert!(!<TreeSize>::exists(pair.public()));
+		assert!(!<TreeLength>::exists(pair.public()));
+		assert!(!<DatKey>::exists(0));
+		assert!(!<DatIndex>::exists(pair.public()));
+		assert!(DatVerify::archives_hosted_by(SEEDER).is_empty());
+		assert!(DatVerify::hosters_of(pair.public()).is_empty());
+		assert!(!<DatHosters<Test>>::exists(pair.public(), SEEDER));
+		assert_eq!(DatVerify::hoster_count(pair.public()), 0);
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::SomethingUnstored(0, pair.public())),
+		);
+	});
+}
+
+#[test]
+fn register_seeder_without_dats_does_nothing() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert!(DatVerify::archives_hosted_by(SEEDER).is_empty());
+		assert_eq!(DatVerify::user_count(), 0);
+		assert!(dat_verify_events().is_empty());
+	});
+}
+
+#[test]
+fn register_seeder_works() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+
+		assert_eq!(hosted_indeces(SEEDER), vec![0]);
+		assert_eq!(DatVerify::hosters_of(pair.public()), vec![SEEDER]);
+		assert_eq!(DatVerify::user(0), SEEDER);
+		assert_eq!(DatVerify::user_id(SEEDER), Some(0));
+		assert_eq!(DatVerify::user_count(), 1);
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::NewPin(SEEDER, pair.public())),
+		);
+
+		// seeding the same dat again does not duplicate anything.
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_eq!(hosted_indeces(SEEDER), vec![0]);
+		assert_eq!(DatVerify::hosters_of(pair.public()), vec![SEEDER]);
+		assert_eq!(DatVerify::user_count(), 1);
+	});
+}
+
+#[test]
+fn register_seeder_reserves_bond_once() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		assert_eq!(DatVerify::seeder_bond(SEEDER), SeederBond::get());
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get());
+
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get());
+	});
+}
+
+#[test]
+fn register_seeder_requires_bond() {
+	new_test_ext().execute_with(|| {
+		let (_, root, signature) = single_chunk_archive(&archive_pair(1), b"x");
+		assert_ok!(DatVerify::register_data(
+			Origin::signed(PUBLISHER),
+			(archive_pair(1).public(), root, signature),
+			None,
+		));
+		assert_noop!(
+			DatVerify::register_seeder(Origin::signed(PAUPER), SeedRequest::Archives(1)),
+			Error::<Test>::InsufficientBond,
+		);
+	});
+}
+
+/// The archives pinned by `SEEDER` according to the `NewPin` events.
+fn pinned_archives() -> Vec<Public> {
+	dat_verify_events().into_iter().filter_map(|event| match event {
+		RawEvent::NewPin(SEEDER, archive) => Some(archive),
+		_ => None,
+	}).collect()
+}
+
+#[test]
+fn register_seeder_skips_hosted_archives() {
+	new_test_ext().execute_with(|| {
+		register_single_chunk(&archive_pair(1), b"x");
+		register_single_chunk(&archive_pair(2), b"y");
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_eq!(hosted_indeces(SEEDER), vec![0, 1]);
+
+		// every archive is hosted already, so nothing is left to assign.
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_eq!(hosted_indeces(SEEDER), vec![0, 1]);
+		assert_eq!(pinned_archives().len(), 2);
+	});
+}
+
+#[test]
+fn register_seeder_prefers_archives_below_target() {
+	new_test_ext().execute_with(|| {
+		register_single_chunk(&archive_pair(1), b"x");
+		register_single_chunk(&archive_pair(2), b"y");
+		// the better paid archive already has the hosters its deal pays for.
+		set_deal(archive_pair(1).public(), 0, 1_000);
+		set_deal(archive_pair(2).public(), 2, 1);
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_eq!(pinned_archives(), vec![archive_pair(2).public()]);
+
+		// archives at their target are still assigned once none are below theirs.
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_eq!(pinned_archives(), vec![archive_pair(2).public(), archive_pair(1).public()]);
+	});
+}
+
+#[test]
+fn register_seeder_weights_archives_by_price() {
+	new_test_ext().execute_with(|| {
+		register_single_chunk(&archive_pair(1), b"x");
+		register_single_chunk(&archive_pair(2), b"y");
+		register_single_chunk(&archive_pair(3), b"z");
+		set_deal(archive_pair(2).public(), 1, 1_000_000);
+		set_deal(archive_pair(3).public(), 1, 0);
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_eq!(pinned_archives(), vec![archive_pair(2).public()]);
+	});
+}
+
+#[test]
+fn register_seeder_assigns_many_archives() {
+	new_test_ext().execute_with(|| {
+		register_single_chunk(&archive_pair(1), b"x");
+		register_single_chunk(&archive_pair(2), b"y");
+		register_single_chunk(&archive_pair(3), b"z");
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(2)));
+		assert_eq!(DatVerify::hosted_count(SEEDER), 2);
+		assert_eq!(pinned_archives().len(), 2);
+		assert_eq!(DatVerify::user_count(), 1);
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get());
+
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(2)));
+		assert_eq!(hosted_indeces(SEEDER), vec![0, 1, 2]);
+		assert_eq!(DatVerify::user_count(), 1);
+	});
+}
+
+#[test]
+fn register_seeder_weighs_the_archives_it_considers() {
+	new_test_ext().execute_with(|| {
+		let weight = |request| Call::<Test>::register_seeder(request).get_dispatch_info().weight;
+		assert_eq!(weight(SeedRequest::Archives(5)), SEEDING_CALL_WEIGHT);
+		for pair in (1..=3).map(archive_pair) {
+			register_single_chunk(&pair, b"x");
+		}
+		// every archive is considered, and up to as many as asked for pinned.
+		assert_eq!(weight(SeedRequest::Archives(1)), SEEDING_CALL_WEIGHT + 4 * WEIGHT_PER_CANDIDATE);
+		assert_eq!(weight(SeedRequest::Archives(5)), SEEDING_CALL_WEIGHT + 6 * WEIGHT_PER_CANDIDATE);
+		assert_eq!(weight(SeedRequest::Capacity(1)), SEEDING_CALL_WEIGHT + 6 * WEIGHT_PER_CANDIDATE);
+	});
+}
+
+#[test]
+fn weighted_draws_take_every_position_once() {
+	let mut draw = WeightedDraw::new(vec![3, 0, 1, 5, 2]);
+	// 0 to 2 fall into the first position, 3 into the third, 4 to 8 into the fourth.
+	assert_eq!(draw.take(3), 2);
+	// the positions left keep their order.
+	assert_eq!(draw.take(4), 3);
+	assert_eq!(draw.take(3), 4);
+	assert_eq!(draw.take(7), 0);
+	assert!(draw.is_empty());
+}
+
+#[test]
+fn register_seeder_fills_capacity() {
+	new_test_ext().execute_with(|| {
+		register_single_chunk(&archive_pair(1), b"x");
+		register_single_chunk(&archive_pair(2), b"large");
+		register_single_chunk(&archive_pair(3), b"y");
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Capacity(2)));
+		assert_eq!(hosted_indeces(SEEDER), vec![0, 2]);
+
+		// no archive fits in no capacity.
+		assert_ok!(DatVerify::register_seeder(Origin::signed(PUBLISHER), SeedRequest::Capacity(0)));
+		assert!(DatVerify::archives_hosted_by(PUBLISHER).is_empty());
+		assert!(!<SeederBonds<Test>>::exists(PUBLISHER));
+	});
+}
+
+#[test]
+fn declare_capacity_bonds_by_the_gigabyte() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DatVerify::declare_capacity(Origin::signed(SEEDER), 1));
+		assert_eq!(DatVerify::seeder_capacity(SEEDER), Some(1));
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get() + BondPerGigabyte::get());
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::CapacityUsed(SEEDER, 0, 1)),
+		);
+
+		// the bond is topped up for a larger capacity and released for a smaller one.
+		assert_ok!(DatVerify::declare_capacity(Origin::signed(SEEDER), 3 * GIGABYTE));
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get() + 3 * BondPerGigabyte::get());
+		assert_ok!(DatVerify::declare_capacity(Origin::signed(SEEDER), 0));
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get());
+		assert_eq!(DatVerify::seeder_bond(SEEDER), SeederBond::get());
+
+		assert_noop!(
+			DatVerify::declare_capacity(Origin::signed(PAUPER), 1),
+			Error::<Test>::InsufficientBond,
+		);
+	});
+}
+
+#[test]
+fn declare_capacity_keeps_hosted_archives() {
+	new_test_ext().execute_with(|| {
+		register_single_chunk(&archive_pair(1), b"large");
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_noop!(
+			DatVerify::declare_capacity(Origin::signed(SEEDER), 4),
+			Error::<Test>::CapacityInUse,
+		);
+		assert_ok!(DatVerify::declare_capacity(Origin::signed(SEEDER), 5));
+		assert_eq!(DatVerify::capacity_of(SEEDER), Some(CapacityInfo { declared: 5, used: 5 }));
+		assert_eq!(DatVerify::capacity_of(PUBLISHER), None);
+	});
+}
+
+#[test]
+fn register_seeder_stays_within_declared_capacity() {
+	new_test_ext().execute_with(|| {
+		register_single_chunk(&archive_pair(1), b"x");
+		register_single_chunk(&archive_pair(2), b"large");
+		register_single_chunk(&archive_pair(3), b"y");
+		assert_ok!(DatVerify::declare_capacity(Origin::signed(SEEDER), 2));
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(3)));
+		assert_eq!(hosted_indeces(SEEDER), vec![0, 2]);
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::CapacityUsed(SEEDER, 2, 2)),
+		);
+		// the capacity bond covers the seeder bond.
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get() + BondPerGigabyte::get());
+
+		// a full seeder is assigned nothing more.
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Capacity(10)));
+		assert_eq!(hosted_indeces(SEEDER), vec![0, 2]);
+	});
+}
+
+#[test]
+fn unregister_seeder_clears_capacity() {
+	new_test_ext().execute_with(|| {
+		register_single_chunk(&archive_pair(1), b"x");
+		assert_ok!(DatVerify::declare_capacity(Origin::signed(SEEDER), GIGABYTE + 1));
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+
+		assert_ok!(DatVerify::unregister_seeder(Origin::signed(SEEDER)));
+		DatVerify::release_evicted(HostingsReleasedPerBlock::get());
+		assert_eq!(DatVerify::capacity_of(SEEDER), None);
+		assert_eq!(Balances::reserved_balance(SEEDER), 0);
+		assert_eq!(Balances::free_balance(SEEDER), INITIAL_BALANCE);
+	});
+}
+
+#[test]
+fn unregister_seeder_works() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+
+		assert_ok!(DatVerify::unregister_seeder(Origin::signed(SEEDER)));
+		assert!(!<Users<Test>>::exists(0));
+		assert!(!<UserIds<Test>>::exists(SEEDER));
+		assert_eq!(DatVerify::user_count(), 0);
+		assert_eq!(DatVerify::evictions(), vec![SEEDER]);
+		// the seeder cannot come back before it is released.
+		assert_noop!(
+			DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)),
+			Error::<Test>::SeederEvicted,
+		);
+
+		// its archive, and then its bond, are released with those of evicted seeders.
+		DatVerify::on_initialize(1);
+		assert!(DatVerify::hosters_of(pair.public()).is_empty());
+		assert!(DatVerify::archives_hosted_by(SEEDER).is_empty());
+		assert_eq!(DatVerify::hosted_count(SEEDER), 0);
+		assert!(DatVerify::evictions().is_empty());
+		// the bond is returned.
+		assert!(!<SeederBonds<Test>>::exists(SEEDER));
+		assert_eq!(Balances::reserved_balance(SEEDER), 0);
+		assert_eq!(Balances::free_balance(SEEDER), INITIAL_BALANCE);
+	});
+}
+
+#[test]
+fn unregister_seeder_moves_the_last_user_into_its_index() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		for attestor in ATTESTORS[..2].iter() {
+			assert_ok!(DatVerify::register_seeder(Origin::signed(*attestor), SeedRequest::Archives(1)));
+		}
+		assert_eq!(DatVerify::user_count(), 3);
+
+		assert_ok!(DatVerify::unregister_seeder(Origin::signed(SEEDER)));
+		assert_eq!(DatVerify::user_count(), 2);
+		assert_eq!(DatVerify::user(0), ATTESTORS[1]);
+		assert_eq!(DatVerify::user_id(ATTESTORS[1]), Some(0));
+		assert_eq!(DatVerify::user(1), ATTESTORS[0]);
+		assert!(!<Users<Test>>::exists(2));
+		DatVerify::release_evicted(HostingsReleasedPerBlock::get());
+		assert_eq!(DatVerify::hosters_of(archive_pair(1).public()), vec![ATTESTORS[1], ATTESTORS[0]]);
+	});
+}
+
+#[test]
+fn unregister_data_moves_the_last_archive_into_its_slot() {
+	new_test_ext().execute_with(|| {
+		for seed in 1..=3 {
+			register_single_chunk(&archive_pair(seed), b"x");
+		}
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(3)));
+		let hosted = DatVerify::archives_hosted_by(SEEDER);
+		let index = DatVerify::dat_index(hosted[0]).unwrap();
+
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), index));
+		assert_eq!(DatVerify::archives_hosted_by(SEEDER), vec![hosted[2], hosted[1]]);
+		assert_eq!(<UsersStorage<Test>>::get(SEEDER, hosted[2]), Some(0));
+		assert_eq!(<UsersStorage<Test>>::get(SEEDER, hosted[1]), Some(1));
+		assert!(!<UsersStorage<Test>>::exists(SEEDER, hosted[0]));
+		assert!(!<UsersStorageAt<Test>>::exists(SEEDER, 2));
+	});
+}
+
+type SignedRoots = (Public, RootHashPayload, H512);
+
+/// The arguments of `register_drive` for a drive whose metadata feed of `metadata_pair` holds
+/// the header naming the single chunk content feed of `content_pair`, and an entry.
+fn drive_args(metadata_pair: &ed25519::Pair, content_pair: &ed25519::Pair) -> (SignedRoots, SignedRoots, Proof) {
+	let header = hyperdrive::header(&content_pair.public());
+	let nodes = tree_nodes(&[&header[..], b"an entry"]);
+	let (metadata_root, metadata_signature) = signed_roots(metadata_pair, &nodes, &[1]);
+	let (_, content_root, content_signature) = single_chunk_archive(content_pair, b"content");
+	let header_proof = Proof {
+		chunks: vec![header],
+		nodes: vec![Node::find(&nodes, 2).expect("the entry is part of the tree").clone()],
+	};
+	(
+		(metadata_pair.public(), metadata_root, metadata_signature),
+		(content_pair.public(), content_root, content_signature),
+		header_proof,
+	)
+}
+
+/// Register the drive of `drive_args` for `PUBLISHER`, returning the keys of its feeds.
+fn register_drive(metadata_pair: &ed25519::Pair, content_pair: &ed25519::Pair) -> (Public, Public) {
+	let (metadata, content, header_proof) = drive_args(metadata_pair, content_pair);
+	assert_ok!(DatVerify::register_drive(Origin::signed(PUBLISHER), metadata, content, header_proof));
+	(metadata_pair.public(), content_pair.public())
+}
+
+#[test]
+fn register_drive_links_both_feeds() {
+	new_test_ext().execute_with(|| {
+		let (metadata, content) = register_drive(&archive_pair(1), &archive_pair(2));
+
+		assert_eq!(DatVerify::content_feed(metadata), Some(content));
+		assert_eq!(DatVerify::metadata_feed(content), Some(metadata));
+		assert_eq!(DatVerify::dat_index(metadata), Some(0));
+		assert_eq!(DatVerify::dat_index(content), Some(1));
+		assert_eq!(DatVerify::tree_length(metadata), 2);
+		assert_eq!(DatVerify::tree_length(content), 1);
+		assert_eq!(<UserRequestsMap<Test>>::get(content), PUBLISHER);
+		assert_eq!(dat_verify_events().last(), Some(&RawEvent::DriveRegistered(metadata, content)));
+	});
+}
+
+#[test]
+fn register_drive_requires_the_header_of_the_content_feed() {
+	new_test_ext().execute_with(|| {
+		let (metadata, _, header_proof) = drive_args(&archive_pair(1), &archive_pair(2));
+		// the header names another content feed.
+		let (_, other_content, _) = drive_args(&archive_pair(1), &archive_pair(3));
+		assert_noop!(
+			DatVerify::register_drive(
+				Origin::signed(PUBLISHER), metadata.clone(), other_content.clone(), header_proof.clone(),
+			),
+			Error::<Test>::InvalidDriveHeader,
+		);
+		// the header is proved against the signed roots of the metadata feed.
+		let (_, _, other_header_proof) = drive_args(&archive_pair(1), &archive_pair(3));
+		assert_noop!(
+			DatVerify::register_drive(
+				Origin::signed(PUBLISHER), metadata.clone(), other_content, other_header_proof,
+			),
+			Error::<Test>::RootHashVerificationFailed,
+		);
+		// a feed registered on its own is not made part of a drive.
+		let (_, content, _) = drive_args(&archive_pair(1), &archive_pair(2));
+		register_single_chunk(&archive_pair(2), b"content");
+		assert_noop!(
+			DatVerify::register_drive(Origin::signed(PUBLISHER), metadata, content, header_proof),
+			Error::<Test>::DriveAlreadyRegistered,
+		);
+	});
+}
+
+#[test]
+fn seeders_are_assigned_both_feeds_of_a_drive() {
+	new_test_ext().execute_with(|| {
+		let (metadata, content) = register_drive(&archive_pair(1), &archive_pair(2));
+		let drive_size = DatVerify::tree_size(metadata) + DatVerify::tree_size(content);
+		// the drive only fits whole.
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Capacity(drive_size - 1)));
+		assert!(DatVerify::archives_hosted_by(SEEDER).is_empty());
+
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_eq!(DatVerify::archives_hosted_by(SEEDER), vec![metadata, content]);
+		assert_eq!(DatVerify::hosters_of(content), vec![SEEDER]);
+	});
+}
+
+#[test]
+fn challenges_target_either_feed_of_a_drive() {
+	new_test_ext().execute_with(|| {
+		let (_, content) = register_drive(&archive_pair(1), &archive_pair(2));
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		challenge_seeder(content, 0);
+
+		assert_ok!(DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![single_chunk_proof(b"content")]));
+		assert!(!<ChallengeMap>::exists(0));
+	});
+}
+
+#[test]
+fn unregister_data_drops_both_feeds_of_a_drive() {
+	new_test_ext().execute_with(|| {
+		let (metadata, content) = register_drive(&archive_pair(1), &archive_pair(2));
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+
+		// either feed unregisters the drive.
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 1));
+		for feed in [metadata, content].iter() {
+			assert_eq!(DatVerify::dat_index(*feed), None);
+			assert!(!<MerkleRoot>::exists(feed));
+			assert!(DatVerify::hosters_of(*feed).is_empty());
+		}
+		assert_eq!(DatVerify::content_feed(metadata), None);
+		assert_eq!(DatVerify::metadata_feed(content), None);
+		assert!(DatVerify::archives_hosted_by(SEEDER).is_empty());
+		assert_eq!(DatVerify::free_dat_id_count(), 2);
+	});
+}
+
+/// Register the single chunk archive of `pair` privately for `PUBLISHER`, returning its
+/// registration key.
+fn register_private_archive(pair: &ed25519::Pair, chunk: &[u8]) -> Public {
+	let registration = registration_pair(pair);
+	let (_, root, signature) = single_chunk_archive(&registration, chunk);
+	assert_ok!(DatVerify::register_private_data(
+		Origin::signed(PUBLISHER),
+		(registration.public(), root, signature),
+		None,
+	));
+	registration.public()
+}
+
+#[test]
+fn register_private_data_keeps_the_feed_key_off_chain() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let archive = register_private_archive(&pair, b"x");
+
+		assert!(DatVerify::is_private(archive));
+		assert_eq!(DatVerify::dat_index(archive), Some(0));
+		assert_eq!(DatVerify::dat_index(pair.public()), None);
+		assert_eq!(DatVerify::dat_index(hashing::discovery_key(&pair.public())), None);
+		assert_eq!(DatVerify::tree_length(archive), 1);
+		assert_eq!(<UserRequestsMap<Test>>::get(archive), PUBLISHER);
+		assert_eq!(dat_verify_events(), vec![RawEvent::SomethingStored(0, archive)]);
+
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 0));
+		assert!(!DatVerify::is_private(archive));
+		assert!(!<MerkleRoot>::exists(archive));
+	});
+}
+
+#[test]
+fn private_archives_are_updated_by_their_publisher() {
+	new_test_ext().execute_with(|| {
+		let registration = registration_pair(&archive_pair(1));
+		let archive = registration.public();
+		let old_nodes = tree_nodes(&[b"hello", b"world"]);
+		let (old_root, old_signature) = signed_roots(&registration, &old_nodes, &[1]);
+		assert_ok!(DatVerify::register_private_data(
+			Origin::signed(PUBLISHER),
+			(archive, old_root.clone(), old_signature),
+			None,
+		));
+
+		let nodes = tree_nodes(&[b"hello", b"world", b"this is", b"a hypercore"]);
+		let (root, signature) = signed_roots(&registration, &nodes, &[3]);
+		let merkle_root = (archive, root.clone(), signature);
+		let proof = consistency_proof(&old_root, &nodes, &[5]);
+		assert_noop!(
+			DatVerify::register_private_data(Origin::signed(SEEDER), merkle_root.clone(), Some(proof.clone())),
+			Error::<Test>::PermissionError,
+		);
+		// updates extend the tree, as they do for public archives.
+		assert_eq!(
+			DatVerify::register_private_data(Origin::signed(PUBLISHER), merkle_root.clone(), None),
+			Err(Error::<Test>::MissingConsistencyProof.into()),
+		);
+		assert_ok!(DatVerify::register_private_data(Origin::signed(PUBLISHER), merkle_root, Some(proof)));
+		assert_eq!(DatVerify::tree_length(archive), 4);
+		assert_eq!(DatVerify::tree_version(archive), 1);
+		assert_eq!(DatVerify::merkle_root(archive).0, root.hash());
+	});
+}
+
+#[test]
+fn private_registrations_are_signed_by_the_registration_key() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let registration = registration_pair(&pair);
+		let nodes = tree_nodes(&[b"hello", b"world"]);
+		// roots signed by another key, the feed key included, do not register it.
+		let (root, signature) = signed_roots(&pair, &nodes, &[1]);
+		assert_noop!(
+			DatVerify::register_private_data(Origin::signed(PUBLISHER), (registration.public(), root, signature), None),
+			Error::<Test>::VerificationFailed,
+		);
+	});
+}
+
+#[test]
+fn public_archives_cannot_be_taken_over_privately() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_two_chunk_archive(&pair);
+		// whoever holds the key of a public feed signs it, yet it stays public.
+		let nodes = tree_nodes(&[b"hello", b"world"]);
+		let (root, signature) = signed_roots(&pair, &nodes, &[1]);
+		assert_noop!(
+			DatVerify::register_private_data(Origin::signed(SEEDER), (pair.public(), root, signature), None),
+			Error::<Test>::PermissionError,
+		);
+		assert!(!DatVerify::is_private(pair.public()));
+		assert_eq!(<UserRequestsMap<Test>>::get(pair.public()), PUBLISHER);
+	});
+}
+
+#[test]
+fn private_archives_cannot_be_taken_over_publicly() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let registration = registration_pair(&pair);
+		let archive = register_private_archive(&pair, b"x");
+		// not even with roots signed by the registration key.
+		let (_, root, signature) = single_chunk_archive(&registration, b"x");
+		assert_noop!(
+			DatVerify::register_data(Origin::signed(SEEDER), (archive, root, signature), None),
+			Error::<Test>::PermissionError,
+		);
+		assert!(DatVerify::is_private(archive));
+		assert_eq!(<UserRequestsMap<Test>>::get(archive), PUBLISHER);
+	});
+}
+
+#[test]
+fn private_archives_are_seeded_and_proved() {
+	new_test_ext().execute_with(|| {
+		let archive = register_private_archive(&archive_pair(1), b"x");
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_eq!(DatVerify::archives_hosted_by(SEEDER), vec![archive]);
+		challenge_seeder(archive, 0);
+
+		assert_ok!(DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![single_chunk_proof(b"x")]));
+		assert!(!<ChallengeMap>::exists(0));
+	});
+}
+
+#[test]
+fn deliver_key_reaches_the_hosters_only() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let archive = register_private_archive(&pair, b"x");
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		let envelope = pair.public().0.to_vec();
+
+		assert_noop!(
+			DatVerify::deliver_key(Origin::signed(SEEDER), archive, SEEDER, envelope.clone()),
+			Error::<Test>::PermissionError,
+		);
+		assert_noop!(
+			DatVerify::deliver_key(Origin::signed(PUBLISHER), archive, ATTESTORS[0], envelope.clone()),
+			Error::<Test>::NotHoster,
+		);
+		assert_noop!(
+			DatVerify::deliver_key(Origin::signed(PUBLISHER), archive, SEEDER, vec![0u8; MAX_ENVELOPE_SIZE + 1]),
+			Error::<Test>::EnvelopeTooLarge,
+		);
+		assert_ok!(DatVerify::deliver_key(Origin::signed(PUBLISHER), archive, SEEDER, envelope.clone()));
+		assert_eq!(DatVerify::key_envelope(archive, SEEDER), Some(envelope.clone()));
+		assert_eq!(dat_verify_events().last(), Some(&RawEvent::KeyDelivered(archive, SEEDER)));
+
+		// the keys of public archives are known already.
+		let public_pair = archive_pair(2);
+		register_single_chunk(&public_pair, b"y");
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_noop!(
+			DatVerify::deliver_key(Origin::signed(PUBLISHER), public_pair.public(), SEEDER, envelope),
+			Error::<Test>::PermissionError,
+		);
+
+		// hosters who leave lose their envelope.
+		assert_ok!(DatVerify::unregister_seeder(Origin::signed(SEEDER)));
+		DatVerify::release_evicted(HostingsReleasedPerBlock::get());
+		assert_eq!(DatVerify::key_envelope(archive, SEEDER), None);
+	});
+}
+
+/// A genesis config registering single chunk archives of `pairs` for `PUBLISHER`.
+fn genesis_archives(pairs: &[ed25519::Pair]) -> GenesisConfig<Test> {
+	GenesisConfig {
+		archives: pairs.iter().map(|pair| {
+			let (_, root, signature) = single_chunk_archive(pair, b"x");
+			(PUBLISHER, (pair.public(), root, signature))
+		}).collect(),
+		seeders: vec![],
+	}
+}
+
+#[test]
+fn genesis_registers_archives() {
+	let pairs = [archive_pair(1), archive_pair(2)];
+	genesis_test_ext(genesis_archives(&pairs)).execute_with(|| {
+		assert_eq!(DatVerify::public_key(0), pairs[0].public());
+		assert_eq!(DatVerify::public_key(1), pairs[1].public());
+		assert_eq!(DatVerify::dat_count(), 2);
+		assert_eq!(DatVerify::dat_index(pairs[1].public()), Some(1));
+		assert_eq!(DatVerify::tree_length(pairs[1].public()), 1);
+		assert_eq!(<UserRequestsMap<Test>>::get(pairs[1].public()), PUBLISHER);
+		// nothing is pinned without seeders.
+		assert!(DatVerify::hosters_of(pairs[0].public()).is_empty());
+		assert_eq!(DatVerify::user_count(), 0);
+		assert_eq!(<StorageVersion>::get(), migration::CURRENT_VERSION);
+	});
+}
+
+#[test]
+fn genesis_bonds_seeders_of_their_archives() {
+	let pairs = [archive_pair(1), archive_pair(2)];
+	let config = GenesisConfig {
+		seeders: vec![(SEEDER, vec![pairs[1].public()])],
+		..genesis_archives(&pairs)
+	};
+	genesis_test_ext(config).execute_with(|| {
+		assert_eq!(hosted_indeces(SEEDER), vec![1]);
+		assert_eq!(DatVerify::hosters_of(pairs[1].public()), vec![SEEDER]);
+		assert!(DatVerify::hosters_of(pairs[0].public()).is_empty());
+		assert_eq!(DatVerify::user(0), SEEDER);
+		assert_eq!(DatVerify::user_count(), 1);
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get());
+
+		// genesis seeders are challenged like any other.
+		DatVerify::on_initialize(1);
+		assert_eq!(<SelectedUsers<Test>>::get(<ChallengeMap>::get(0)), SEEDER);
+		assert_eq!(<SelectedChallenges<Test>>::get(0).0, vec![(pairs[1].public(), 0)]);
+	});
+}
+
+/// Write `value` where the v0 layout kept `item`, under `hashed_key` for a map.
+fn put_v0<V: Encode>(item: &[u8], hashed_key: &[u8], value: V) {
+	frame_support::storage::unhashed::put(&migration::v0_key(item, hashed_key), &value);
+}
+
+/// Whether anything is left where the v0 layout kept `item`, under `hashed_key` for a map.
+fn v0_exists(item: &[u8], hashed_key: &[u8]) -> bool {
+	frame_support::storage::unhashed::exists(&migration::v0_key(item, hashed_key))
+}
+
+#[test]
+fn migration_v1_moves_the_vecs_into_maps() {
+	new_test_ext().execute_with(|| {
+		let pairs = [archive_pair(1), archive_pair(2)];
+		let hashed = |key: &[u8]| Blake2_256::hash(key);
+		// archives 0 and 2 are registered, and index 1 was freed.
+		<DatKey>::insert(0, pairs[0].public());
+		<DatKey>::insert(2, pairs[1].public());
+		put_v0(b"DatId", &[], vec![1u64, 3]);
+		put_v0(b"RemovedDats", &[], vec![pairs[0].public()]);
+		put_v0(b"DatHosters", &hashed(&pairs[0].public().encode()), vec![SEEDER, ATTESTORS[0]]);
+		put_v0(b"DatHosters", &hashed(&pairs[1].public().encode()), vec![SEEDER]);
+		put_v0(b"UsersStorage", &hashed(&SEEDER.encode()), vec![0u64, 2]);
+		put_v0(b"UsersStorage", &hashed(&ATTESTORS[0].encode()), vec![0u64]);
+		// users were indexed from one, each followed by its (previous, next) linkage.
+		put_v0(b"UsersCount", &[], vec![1u64, 2]);
+		put_v0(b"Users", &Twox256::hash(&1u64.encode()), (SEEDER, (None::<u64>, Some(2u64))));
+		put_v0(b"Users", &Twox256::hash(&2u64.encode()), (ATTESTORS[0], (Some(1u64), None::<u64>)));
+		put_v0(b"HeadOfUsers", &[], 1u64);
+
+		DatVerify::on_initialize(1);
+		assert_eq!(<StorageVersion>::get(), migration::CURRENT_VERSION);
+
+		assert_eq!(DatVerify::dat_count(), 3);
+		assert_eq!(DatVerify::free_dat_id_count(), 1);
+		assert_eq!(<FreeDatIds>::get(0), 1);
+		assert_eq!(DatVerify::dat_index(pairs[1].public()), Some(2));
+		assert_eq!(DatVerify::hosters_of(pairs[0].public()), vec![SEEDER, ATTESTORS[0]]);
+		assert_eq!(DatVerify::hosters_of(pairs[1].public()), vec![SEEDER]);
+		assert_eq!(hosted_indeces(SEEDER), vec![0, 2]);
+		assert_eq!(hosted_indeces(ATTESTORS[0]), vec![0]);
+		assert_eq!(DatVerify::user_count(), 2);
+		assert_eq!(DatVerify::user(0), SEEDER);
+		assert_eq!(DatVerify::user(1), ATTESTORS[0]);
+		assert_eq!(DatVerify::user_id(ATTESTORS[0]), Some(1));
+		assert!(!<Users<Test>>::exists(2));
+		assert!(!v0_exists(b"DatId", &[]));
+		assert!(!v0_exists(b"RemovedDats", &[]));
+		assert!(!v0_exists(b"UsersCount", &[]));
+		assert!(!v0_exists(b"HeadOfUsers", &[]));
+		assert!(!v0_exists(b"DatHosters", &hashed(&pairs[0].public().encode())));
+		assert!(!v0_exists(b"UsersStorage", &hashed(&SEEDER.encode())));
+
+		// the index freed under v0 is reused.
+		register_single_chunk(&archive_pair(3), b"x");
+		assert_eq!(DatVerify::dat_index(archive_pair(3).public()), Some(1));
+	});
+}
+
+#[test]
+fn migration_v1_leaves_a_new_layout_alone() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		DatVerify::on_initialize(1);
+		assert_eq!(<StorageVersion>::get(), migration::CURRENT_VERSION);
+		assert_eq!(DatVerify::dat_count(), 1);
+		assert_eq!(DatVerify::user(0), SEEDER);
+		assert_eq!(DatVerify::hosters_of(archive_pair(1).public()), vec![SEEDER]);
+	});
+}
+
+#[test]
+fn migration_v2_indexes_the_open_challenges() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		// challenges issued under v1, the seeder's on either side of the other's.
+		<StorageVersion>::put(1);
+		for (challenge_index, user_index, seeder) in [(0u64, 0u64, SEEDER), (1, 1, ATTESTORS[0]), (2, 0, SEEDER)].iter() {
+			<ChallengeMap>::insert(challenge_index, user_index);
+			<SelectedUsers<Test>>::insert(user_index, seeder);
+			<SelectedChallenges<Test>>::insert(challenge_index, (vec![(pair.public(), 0)], 10 + challenge_index));
+		}
+		<SelectedUserIndex<Test>>::insert(SEEDER, (0, 2));
+		<SelectedUserIndex<Test>>::insert(ATTESTORS[0], (1, 1));
+
+		DatVerify::on_initialize(1);
+		assert_eq!(<StorageVersion>::get(), migration::CURRENT_VERSION);
+		assert_eq!(<OpenChallenges>::get(0), vec![0, 2]);
+		assert_eq!(<OpenChallenges>::get(1), vec![1]);
+		let open_to = |seeder| DatVerify::challenges_for(seeder).into_iter()
+			.map(|challenge| challenge.index)
+			.collect::<Vec<_>>();
+		assert_eq!(open_to(SEEDER), vec![0, 2]);
+		assert_eq!(open_to(ATTESTORS[0]), vec![1]);
+	});
+}
+
+#[test]
+fn migration_v1_lets_pre_upgrade_archives_be_challenged() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let (_, root, signature) = single_chunk_archive(&pair, b"x");
+		let challenge_key = Twox256::hash(&0u64.encode());
+		// an archive registered and pinned under v0, which kept no length.
+		<DatKey>::insert(0, pair.public());
+		<MerkleRoot>::insert(pair.public(), (root.hash(), Signature::from_h512(signature)));
+		<TreeSize>::insert(pair.public(), 1);
+		<UserRequestsMap<Test>>::insert(pair.public(), PUBLISHER);
+		put_v0(b"DatId", &[], vec![1u64]);
+		put_v0(b"DatHosters", &Blake2_256::hash(&pair.public().encode()), vec![SEEDER]);
+		put_v0(b"UsersStorage", &Blake2_256::hash(&SEEDER.encode()), vec![0u64]);
+		put_v0(b"UsersCount", &[], vec![1u64]);
+		put_v0(b"Users", &Twox256::hash(&1u64.encode()), (SEEDER, (None::<u64>, None::<u64>)));
+		put_v0(b"HeadOfUsers", &[], 1u64);
+		// and a challenge of a single chunk of it, which an attestor was asked about.
+		<ChallengeMap>::insert(0, 0);
+		put_v0(b"SelectedChallenges", &challenge_key, (pair.public(), 0u64, 5u64));
+		<SelectedUsers<Test>>::insert(0, SEEDER);
+		<SelectedUserIndex<Test>>::insert(SEEDER, (0, 1));
+		put_v0(
+			b"ChallengeAttestations",
+			&challenge_key,
+			(vec![ATTESTORS[0]], Vec::<u64>::new(), Vec::<(u64, Attestation)>::new()),
+		);
+		<Attestors<Test>>::insert(ATTESTORS[0], 0);
+
+		// the open challenge is dropped, and none is issued while the length is unknown.
+		DatVerify::on_initialize(1);
+		assert!(!<ChallengeMap>::exists(0));
+		assert!(!<SelectedChallenges<Test>>::exists(0));
+		assert!(!v0_exists(b"ChallengeAttestations", &challenge_key));
+		assert!(!<Attestors<Test>>::exists(ATTESTORS[0]));
+		assert!(!<SelectedUsers<Test>>::exists(0));
+		assert!(!<SelectedUserIndex<Test>>::exists(SEEDER));
+		assert_eq!(DatVerify::tree_length(pair.public()), 0);
+
+		// anyone can prove the length with the roots the registered root hash was made of.
+		let mut forged = root.children.clone();
+		forged[0].total_length = 2;
+		assert_noop!(
+			DatVerify::backfill_tree_length(Origin::signed(ATTESTORS[1]), pair.public(), forged),
+			Error::<Test>::RootHashVerificationFailed,
+		);
+		assert_ok!(DatVerify::backfill_tree_length(Origin::signed(ATTESTORS[1]), pair.public(), root.children.clone()));
+		assert_eq!(DatVerify::tree_length(pair.public()), 1);
+		assert_noop!(
+			DatVerify::backfill_tree_length(Origin::signed(ATTESTORS[1]), pair.public(), root.children.clone()),
+			Error::<Test>::InvalidState,
+		);
+
+		// from then on the seeder is challenged on the archive, and proves it.
+		DatVerify::on_initialize(2);
+		assert_eq!(<SelectedChallenges<Test>>::get(0).0, vec![(pair.public(), 0)]);
+		assert_ok!(DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![single_chunk_proof(b"x")]));
+		assert!(!<ChallengeMap>::exists(0));
+		assert_eq!(DatVerify::reputation(SEEDER), ProofReputation::get());
+	});
+}
+
+#[test]
+#[should_panic(expected = "genesis archives must be signed by their key")]
+fn genesis_rejects_unsigned_archives() {
+	let mut config = genesis_archives(&[archive_pair(1)]);
+	(config.archives[0].1).0 = archive_pair(2).public();
+	genesis_test_ext(config);
+}
+
+#[test]
+#[should_panic(expected = "genesis seeders can only pin genesis archives")]
+fn genesis_rejects_seeders_of_unknown_archives() {
+	let config = GenesisConfig {
+		seeders: vec![(SEEDER, vec![archive_pair(2).public()])],
+		..genesis_archives(&[archive_pair(1)])
+	};
+	genesis_test_ext(config);
+}
+
+#[test]
+fn no_challenge_without_seeders() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let (_, root, signature) = single_chunk_archive(&pair, b"x");
+		assert_ok!(DatVerify::register_data(
+			Origin::signed(PUBLISHER),
+			(pair.public(), root, signature),
+			None,
+		));
+		DatVerify::on_initialize(1);
+		assert!(!<ChallengeMap>::exists(0));
+		assert_eq!(<ChallengeIndex>::get(), 0);
+	});
+}
+
+#[test]
+fn challenge_is_issued_on_initialize() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+
+		DatVerify::on_initialize(1);
+
+		assert_eq!(<ChallengeIndex>::get(), 1);
+		assert_eq!(<ChallengeMap>::get(0), 0);
+		assert_eq!(<SelectedUsers<Test>>::get(0), SEEDER);
+		assert_eq!(<SelectedUserIndex<Test>>::get(SEEDER), (0, 1));
+		// a single leaf archive can only be challenged on leaf 0.
+		assert_eq!(<SelectedChallenges<Test>>::get(0), (vec![(pair.public(), 0)], 2));
+		assert_eq!(dat_verify_events().last(), Some(&RawEvent::Challenge(SEEDER, 2)));
+	});
+}
+
+#[test]
+fn challenges_pick_a_leaf_of_the_signed_tree() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let nodes = tree_nodes(&[b"hello", b"world", b"this is", b"a hypercore"]);
+		let (root, signature) = signed_roots(&pair, &nodes, &[3]);
+		assert_ok!(DatVerify::register_data(
+			Origin::signed(PUBLISHER),
+			(pair.public(), root, signature),
+			None,
+		));
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+
+		DatVerify::on_initialize(1);
+
+		let (chunks, _) = <SelectedChallenges<Test>>::get(0);
+		assert_eq!(chunks.len(), 1);
+		let (dat, leaf) = chunks[0];
+		assert_eq!(dat, pair.public());
+		assert_eq!(leaf % 2, 0);
+		assert!(leaf < 8);
+	});
+}
+
+#[test]
+fn chunks_per_challenge_follow_the_detection_probability() {
+	new_test_ext().execute_with(|| {
+		// a seeder who lost half of their archives proves a chunk half of the time.
+		assert_eq!(DatVerify::chunks_per_challenge(), 1);
+		set_detection_probability(Perbill::from_percent(75));
+		assert_eq!(DatVerify::chunks_per_challenge(), 2);
+		set_detection_probability(Perbill::from_percent(90));
+		assert_eq!(DatVerify::chunks_per_challenge(), 4);
+		// certainty is out of reach, the challenge is capped instead.
+		set_detection_probability(Perbill::one());
+		assert_eq!(DatVerify::chunks_per_challenge(), MaxChunksPerChallenge::get());
+		set_detection_probability(Perbill::zero());
+		assert_eq!(DatVerify::chunks_per_challenge(), 1);
+	});
+}
+
+#[test]
+fn challenges_sample_chunks_across_archives() {
+	new_test_ext().execute_with(|| {
+		let pairs = [archive_pair(1), archive_pair(2)];
+		register_four_chunk_archive(&pairs[0]);
+		register_single_chunk(&pairs[1], b"x");
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(2)));
+		set_detection_probability(Perbill::one());
+
+		DatVerify::on_initialize(1);
+
+		let (chunks, _) = <SelectedChallenges<Test>>::get(0);
+		assert!(!chunks.is_empty() && chunks.len() <= MaxChunksPerChallenge::get() as usize);
+		let mut sorted = chunks.clone();
+		sorted.sort_unstable();
+		sorted.dedup();
+		assert_eq!(chunks, sorted);
+		for (dat, leaf) in chunks {
+			let tree_length = if dat == pairs[0].public() { 4 } else { 1 };
+			assert_eq!(leaf % 2, 0);
+			assert!(leaf < 2 * tree_length);
+		}
+	});
+}
+
+#[test]
+fn challenges_are_recomputed_from_their_seed() {
+	new_test_ext().execute_with(|| {
+		let pairs = [archive_pair(1), archive_pair(2)];
+		register_four_chunk_archive(&pairs[0]);
+		register_single_chunk(&pairs[1], b"x");
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(2)));
+		set_detection_probability(Perbill::one());
+
+		DatVerify::on_initialize(1);
+
+		let seed = DatVerify::challenge_seed(1);
+		assert!(dat_verify_events().contains(&RawEvent::ChallengeSeed(0, seed)));
+		// the seeder, and every chunk, from the seed and the index of the challenge alone.
+		let seeder = <Users<Test>>::get(DatVerify::draw(&seed, 0, Draw::Seeder, 0) % DatVerify::user_count());
+		assert_eq!(<SelectedUsers<Test>>::get(0), seeder);
+		let mut chunks: Vec<(Public, u64)> = (0..DatVerify::chunks_per_challenge()).map(|sample| {
+			let random = DatVerify::draw(&seed, 0, Draw::Chunk, sample);
+			let archive = <UsersStorageAt<Test>>::get(&seeder, (random % 2) as u32);
+			(archive, 2 * (random % DatVerify::tree_length(archive)))
+		}).collect();
+		chunks.sort_unstable();
+		chunks.dedup();
+		assert_eq!(<SelectedChallenges<Test>>::get(0).0, chunks);
+		// and the deadline, within a block for every archive.
+		assert_eq!(<SelectedChallenges<Test>>::get(0).1, 2 + DatVerify::draw(&seed, 0, Draw::Deadline, 0) % 2);
+
+		// every draw of a challenge is its own.
+		assert_ne!(DatVerify::draw(&seed, 0, Draw::Chunk, 0), DatVerify::draw(&seed, 0, Draw::Chunk, 1));
+		assert_ne!(DatVerify::draw(&seed, 0, Draw::Chunk, 0), DatVerify::draw(&seed, 1, Draw::Chunk, 0));
+		assert_ne!(DatVerify::draw(&seed, 0, Draw::Chunk, 0), DatVerify::draw(&seed, 0, Draw::Attestor, 0));
+	});
+}
+
+#[test]
+fn challenged_archives_group_the_chunks() {
+	let (a, b) = (archive_pair(1).public(), archive_pair(2).public());
+	let (first, second) = if a < b { (a, b) } else { (b, a) };
+	assert_eq!(
+		DatVerify::challenged_archives(&[(second, 2), (first, 4), (second, 0), (first, 4)]),
+		vec![(first, vec![4]), (second, vec![0, 2])],
+	);
+	assert!(DatVerify::challenged_archives(&[]).is_empty());
+}
+
+#[test]
+fn expired_challenge_fails_and_evicts_seeder() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		DatVerify::on_initialize(1);
+
+		// the deadline has not been reached yet.
+		DatVerify::on_finalize(1);
+		assert!(<ChallengeMap>::exists(0));
+
+		DatVerify::on_finalize(2);
+		assert!(!<ChallengeMap>::exists(0));
+		assert!(!<SelectedChallenges<Test>>::exists(0));
+		assert!(!<SelectedUserIndex<Test>>::exists(SEEDER));
+		assert_eq!(DatVerify::user_count(), 0);
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::ChallengeFailed(SEEDER, vec![pair.public()])),
+		);
+		// the seeder is released from its archives in the blocks after.
+		assert_eq!(DatVerify::evictions(), vec![SEEDER]);
+		assert_eq!(DatVerify::hosters_of(pair.public()), vec![SEEDER]);
+		assert_noop!(
+			DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)),
+			Error::<Test>::SeederEvicted,
+		);
+
+		DatVerify::on_initialize(3);
+		assert!(DatVerify::hosters_of(pair.public()).is_empty());
+		assert!(DatVerify::evictions().is_empty());
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+	});
+}
+
+#[test]
+fn evicted_seeders_are_released_a_few_hostings_a_block() {
+	new_test_ext().execute_with(|| {
+		let other = ATTESTORS[0];
+		for pair in (1..=3).map(archive_pair) {
+			register_single_chunk(&pair, b"x");
+		}
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(3)));
+		assert_ok!(DatVerify::register_seeder(Origin::signed(other), SeedRequest::Archives(1)));
+		DatVerify::evict_seeder(&SEEDER);
+		DatVerify::evict_seeder(&other);
+		DatVerify::evict_seeder(&SEEDER);
+		assert_eq!(DatVerify::evictions(), vec![SEEDER, other]);
+		assert_eq!(DatVerify::user_count(), 0);
+
+		// `HostingsReleasedPerBlock` at a time, releasing a bond counting as one.
+		DatVerify::release_evicted(HostingsReleasedPerBlock::get());
+		assert_eq!(DatVerify::archives_hosted_by(SEEDER).len(), 1);
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get());
+		DatVerify::release_evicted(HostingsReleasedPerBlock::get());
+		assert!(DatVerify::archives_hosted_by(SEEDER).is_empty());
+		assert_eq!(Balances::reserved_balance(SEEDER), 0);
+		assert_eq!(DatVerify::evictions(), vec![other]);
+		assert_eq!(DatVerify::archives_hosted_by(other).len(), 1);
+		DatVerify::release_evicted(HostingsReleasedPerBlock::get());
+		assert!(DatVerify::archives_hosted_by(other).is_empty());
+		assert_eq!(Balances::reserved_balance(other), 0);
+		assert!(DatVerify::evictions().is_empty());
+	});
+}
+
+#[test]
+fn expired_challenge_slashes_bond_and_reports_offence() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		DatVerify::on_initialize(1);
+		DatVerify::on_finalize(2);
+
+		// half of the bond goes to the pot, the rest is returned once the seeder is released.
+		let slashed = ChallengeSlash::get() * SeederBond::get();
+		assert!(dat_verify_events().contains(&RawEvent::SeederSlashed(SEEDER, slashed)));
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get() - slashed);
+		DatVerify::on_initialize(3);
+		assert_eq!(Balances::reserved_balance(SEEDER), 0);
+		assert_eq!(Balances::free_balance(SEEDER), INITIAL_BALANCE - slashed);
+		assert_eq!(Balances::free_balance(DatVerify::pot_account()), INITIAL_BALANCE + slashed);
+
+		assert_eq!(reported_offences(), vec![(vec![], ChallengeFailureOffence {
+			session_index: 0,
+			validator_set_count: 0,
+			challenge_index: 0,
+			offender: (SEEDER, SEEDER),
+		})]);
+	});
+}
+
+#[test]
+fn punish_seeder_requires_root_and_bond() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		assert_noop!(DatVerify::punish_seeder(Origin::signed(PUBLISHER), SEEDER), BadOrigin);
+		assert_noop!(DatVerify::punish_seeder(Origin::ROOT, PUBLISHER), Error::<Test>::NotBonded);
+
+		assert_ok!(DatVerify::punish_seeder(Origin::ROOT, SEEDER));
+		assert_eq!(DatVerify::seeder_bond(SEEDER), SeederBond::get() - ChallengeSlash::get() * SeederBond::get());
+	});
+}
+
+#[test]
+fn challenges_on_removed_dats_are_cleared() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		DatVerify::on_initialize(1);
+
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 0));
+		DatVerify::on_finalize(2);
+
+		// the seeder is not punished for an archive it no longer hosts.
+		assert!(!<ChallengeMap>::exists(0));
+		assert!(!<SelectedChallenges<Test>>::exists(0));
+		assert!(!<SelectedUserIndex<Test>>::exists(SEEDER));
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get());
+		assert!(!dat_verify_events().iter().any(|event| match event {
+			RawEvent::ChallengeFailed(..) => true,
+			_ => false,
+		}));
+	});
+}
+
+#[test]
+fn challenges_per_block_are_bounded() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		set_challenges_per_block(3);
+		DatVerify::on_initialize(1);
+
+		assert_eq!(<ChallengeIndex>::get(), 3);
+		assert!(<ChallengeMap>::exists(2));
+		assert_eq!(<SelectedUserIndex<Test>>::get(SEEDER), (0, 3));
+		assert_eq!(<OpenChallenges>::get(0), vec![0, 1, 2]);
+		assert_eq!(<ChallengeExpiries<Test>>::get(2), vec![0, 1, 2]);
+	});
+}
+
+#[test]
+fn expiries_are_queued_in_blocks_with_room() {
+	new_test_ext().execute_with(|| {
+		set_challenges_per_block(2);
+		assert_eq!(DatVerify::schedule_expiry(0, 5), 5);
+		assert_eq!(DatVerify::schedule_expiry(1, 5), 5);
+		assert_eq!(DatVerify::schedule_expiry(2, 5), 6);
+		assert_eq!(DatVerify::schedule_expiry(3, 4), 4);
+		assert_eq!(<ChallengeExpiries<Test>>::get(5), vec![0, 1]);
+		assert_eq!(<ChallengeExpiries<Test>>::get(6), vec![2]);
+
+		DatVerify::on_finalize(5);
+		assert!(!<ChallengeExpiries<Test>>::exists(5));
+		assert_eq!(<ChallengeExpiries<Test>>::get(6), vec![2]);
+	});
+}
+
+#[test]
+fn only_due_challenges_expire() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		challenge_seeder(pair.public(), 0);
+
+		DatVerify::on_finalize(9);
+		assert!(<ChallengeMap>::exists(0));
+		DatVerify::on_finalize(10);
+		assert!(!<ChallengeMap>::exists(0));
+		assert!(!<OpenChallenges>::exists(0));
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::ChallengeFailed(SEEDER, vec![pair.public()])),
+		);
+	});
+}
+
+#[test]
+fn draws_of_seeders_without_archives_queue_no_expiry() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		register_single_chunk(&archive_pair(2), b"y");
+		// the only seeder is left without archives, but stays a user.
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 0));
+		DatVerify::on_initialize(1);
+		assert!(!<ChallengeMap>::exists(0));
+		assert!(<ChallengeExpiries<Test>>::get(2).is_empty());
+
+		// the next challenge keeps its whole window.
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		DatVerify::on_initialize(2);
+		assert_eq!(<SelectedChallenges<Test>>::get(0).1, 3);
+		DatVerify::on_finalize(2);
+		assert!(<ChallengeMap>::exists(0));
+		assert_eq!(DatVerify::hosters_of(archive_pair(2).public()), vec![SEEDER]);
+	});
+}
+
+#[test]
+fn seeders_without_archives_are_skipped_for_another_draw() {
+	new_test_ext().execute_with(|| {
+		let seed = DatVerify::challenge_seed(1);
+		let idle = ATTESTORS[0];
+		// `idle` is a seeder left without archives, in the place drawn first.
+		let first = DatVerify::draw(&seed, 0, Draw::Seeder, 0) % 2;
+		if first == 0 {
+			DatVerify::insert_user(&idle);
+		}
+		register_and_seed(&archive_pair(1), b"x");
+		if first == 1 {
+			DatVerify::insert_user(&idle);
+		}
+		assert_eq!(<Users<Test>>::get(first), idle);
+		let skipped = (1..).find(|round| DatVerify::draw(&seed, 0, Draw::Seeder, *round) % 2 != first).unwrap();
+
+		// every skipped seeder takes up the place of a challenge.
+		set_challenges_per_block(skipped);
+		DatVerify::on_initialize(1);
+		assert!(!<ChallengeMap>::exists(0));
+		set_challenges_per_block(skipped + 1);
+		DatVerify::on_initialize(1);
+		assert_eq!(<SelectedUsers<Test>>::get(<ChallengeMap>::get(0)), SEEDER);
+		assert!(!<ChallengeMap>::exists(1));
+	});
+}
+
+#[test]
+fn hooks_weigh_the_challenges_they_handle() {
+	new_test_ext().execute_with(|| {
+		// up to `ReputationsDecayedPerBlock` reputations decay, and `HostingsReleasedPerBlock`
+		// hostings are released, along with the challenges.
+		let background = ReputationsDecayedPerBlock::get() * REPUTATION_DECAY_WEIGHT
+			+ HostingsReleasedPerBlock::get() * HOSTING_RELEASE_WEIGHT;
+		assert_eq!(<DatVerify as WeighBlock<u64>>::on_initialize(1), CHALLENGE_HOOK_WEIGHT + background);
+		assert_eq!(<DatVerify as WeighBlock<u64>>::on_finalize(1), CHALLENGE_HOOK_WEIGHT);
+		set_challenges_per_block(4);
+		assert_eq!(<DatVerify as WeighBlock<u64>>::on_initialize(1), 4 * CHALLENGE_HOOK_WEIGHT + background);
+		assert_eq!(<DatVerify as WeighBlock<u64>>::on_finalize(1), 4 * CHALLENGE_HOOK_WEIGHT);
+	});
+}
+
+#[test]
+fn force_clear_challenge_requires_force_origin() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		DatVerify::on_initialize(1);
+
+		assert_noop!(DatVerify::force_clear_challenge(Origin::signed(SEEDER), SEEDER, 0), BadOrigin);
+		assert_ok!(DatVerify::force_clear_challenge(Origin::signed(ForceAccount::get()), SEEDER, 0));
+		assert!(!<ChallengeMap>::exists(0));
+		assert!(!<SelectedUsers<Test>>::exists(0));
+		assert!(!<SelectedUserIndex<Test>>::exists(SEEDER));
+	});
+}
+
+/// The proof of `chunk` of a single chunk archive, which is its own root.
+fn single_chunk_proof(chunk: &[u8]) -> Proof {
+	Proof { chunks: vec![chunk.to_vec()], nodes: vec![] }
+}
+
+#[test]
+fn submit_proof_clears_challenge() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		DatVerify::on_initialize(1);
+
+		assert_ok!(DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![single_chunk_proof(b"x")]));
+
+		assert!(!<ChallengeMap>::exists(0));
+		assert!(!<SelectedChallenges<Test>>::exists(0));
+		// passing a challenge keeps the seeder around.
+		DatVerify::on_finalize(2);
+		assert_eq!(DatVerify::hosters_of(pair.public()), vec![SEEDER]);
+	});
+}
+
+/// Have `SEEDER` pass the challenge on a single chunk archive.
+fn pass_challenge() {
+	register_and_seed(&archive_pair(1), b"x");
+	DatVerify::on_initialize(1);
+	assert_ok!(DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![single_chunk_proof(b"x")]));
+}
+
+#[test]
+fn submit_proof_pays_reward_from_pot() {
+	new_test_ext().execute_with(|| {
+		pass_challenge();
+
+		let reward = ChallengeReward::get();
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::SeederRewarded(SEEDER, reward)),
+		);
+		assert_eq!(Balances::free_balance(SEEDER), INITIAL_BALANCE - SeederBond::get() + reward);
+		assert_eq!(Balances::free_balance(DatVerify::pot_account()), INITIAL_BALANCE - reward);
+	});
+}
+
+#[test]
+fn submit_proof_with_empty_pot_pays_nothing() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&DatVerify::pot_account(), 0);
+		pass_challenge();
+
+		assert!(!<ChallengeMap>::exists(0));
+		assert_eq!(Balances::free_balance(SEEDER), INITIAL_BALANCE - SeederBond::get());
+	});
+}
+
+#[test]
+fn submit_proof_checks_challenged_account() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		DatVerify::on_initialize(1);
+
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(PUBLISHER), 0, vec![single_chunk_proof(b"x")]),
+			Error::<Test>::PermissionError,
+		);
+	});
+}
+
+#[test]
+fn submit_proof_rejects_invalid_proofs() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		DatVerify::on_initialize(1);
+
+		// one proof per challenged archive, with every challenged chunk.
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![]),
+			Error::<Test>::ProvesWrongChunk,
+		);
+		assert_noop!(
+			DatVerify::submit_proof(
+				Origin::signed(SEEDER), 0, vec![single_chunk_proof(b"x"), single_chunk_proof(b"x")],
+			),
+			Error::<Test>::ProvesWrongChunk,
+		);
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![Proof::default()]),
+			Error::<Test>::ProvesWrongChunk,
+		);
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![single_chunk_proof(b"xx")]),
+			Error::<Test>::RootHashVerificationFailed,
+		);
+		// the chunk content is hashed, not only its length.
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![single_chunk_proof(b"y")]),
+			Error::<Test>::RootHashVerificationFailed,
+		);
+		// leaves past the registered tree are not part of it.
+		challenge_seeder(pair.public(), 2);
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![single_chunk_proof(b"x")]),
+			Error::<Test>::MissingLeaf,
+		);
+	});
+}
+
+const FOUR_CHUNKS: [&[u8]; 4] = [b"hello", b"world", b"this is", b"a hypercore"];
+
+/// Register a four chunk archive for `PUBLISHER`, returning the nodes of its tree.
+fn register_four_chunk_archive(pair: &ed25519::Pair) -> Vec<Node> {
+	let nodes = tree_nodes(&FOUR_CHUNKS);
+	let (root, signature) = signed_roots(pair, &nodes, &[3]);
+	assert_ok!(DatVerify::register_data(
+		Origin::signed(PUBLISHER),
+		(pair.public(), root, signature),
+		None,
+	));
+	nodes
+}
+
+/// The proof of the `leaves` of the tree of `nodes`, made of the `proof` nodes.
+fn chunks_proof(nodes: &[Node], leaves: &[u64], proof: &[u64]) -> Proof {
+	Proof {
+		chunks: leaves.iter().map(|leaf| FOUR_CHUNKS[*leaf as usize / 2].to_vec()).collect(),
+		nodes: proof.iter()
+			.map(|index| Node::find(nodes, *index).expect("node is part of the tree").clone())
+			.collect(),
+	}
+}
+
+/// A four chunk archive seeded by `SEEDER` and challenged on its last leaf, with the proof of
+/// that leaf.
+fn challenged_four_chunk_archive(pair: &ed25519::Pair) -> Proof {
+	let nodes = register_four_chunk_archive(pair);
+	assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+	challenge_seeder(pair.public(), 6);
+	// its sibling and its uncle, the root is recomputed.
+	chunks_proof(&nodes, &[6], &[4, 1])
+}
+
+#[test]
+fn submit_proof_verifies_inclusion_path() {
+	new_test_ext().execute_with(|| {
+		let proof = challenged_four_chunk_archive(&archive_pair(1));
+		assert_ok!(DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![proof]));
+		assert!(!<ChallengeMap>::exists(0));
+	});
+}
+
+#[test]
+fn submit_proof_requires_every_sibling() {
+	new_test_ext().execute_with(|| {
+		let mut proof = challenged_four_chunk_archive(&archive_pair(1));
+		proof.nodes.retain(|node| node.index != 4);
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![proof]),
+			Error::<Test>::MissingSibling,
+		);
+	});
+}
+
+#[test]
+fn submit_proof_requires_the_roots_off_the_paths() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let nodes = tree_nodes(&FOUR_CHUNKS[..3]);
+		let (root, signature) = signed_roots(&pair, &nodes, &[1, 4]);
+		assert_ok!(DatVerify::register_data(
+			Origin::signed(PUBLISHER),
+			(pair.public(), root, signature),
+			None,
+		));
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		// the third leaf is a root of its own.
+		challenge_seeder(pair.public(), 4);
+
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![chunks_proof(&nodes, &[4], &[])]),
+			Error::<Test>::MissingRoot,
+		);
+		assert_ok!(DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![chunks_proof(&nodes, &[4], &[1])]));
+	});
+}
+
+#[test]
+fn submit_proof_rejects_leaf_not_under_the_root() {
+	new_test_ext().execute_with(|| {
+		let mut proof = challenged_four_chunk_archive(&archive_pair(1));
+		// a seeder not holding the tree can't come up with a sibling leading to the registered root.
+		proof.nodes.iter_mut().filter(|node| node.index == 1).for_each(|node| node.hash = H256::zero());
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![proof]),
+			Error::<Test>::RootHashVerificationFailed,
+		);
+	});
+}
+
+#[test]
+fn submit_proof_recomputes_the_nodes_on_the_path() {
+	new_test_ext().execute_with(|| {
+		let mut proof = challenged_four_chunk_archive(&archive_pair(1));
+		// nodes the chunks give are never taken from the proof.
+		proof.nodes.push(Node {
+			index: 5,
+			hash: H256::zero(),
+			size: 18,
+		});
+		assert_ok!(DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![proof]));
+	});
+}
+
+#[test]
+fn submit_proof_proves_several_chunks_at_once() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let nodes = register_four_chunk_archive(&pair);
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		challenge_chunks(&[(pair.public(), 0), (pair.public(), 6)]);
+
+		// the paths of both leaves meet below the root, so only their siblings are needed.
+		assert_eq!(proof_nodes(&[0, 6], 4), vec![2, 4]);
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![chunks_proof(&nodes, &[0, 6], &[2])]),
+			Error::<Test>::MissingSibling,
+		);
+		// every challenged chunk is proved.
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![chunks_proof(&nodes, &[6], &[4, 1])]),
+			Error::<Test>::ProvesWrongChunk,
+		);
+		assert_ok!(DatVerify::submit_proof(
+			Origin::signed(SEEDER), 0, vec![chunks_proof(&nodes, &[0, 6], &[2, 4])],
+		));
+		assert!(!<ChallengeMap>::exists(0));
+	});
+}
+
+/// `SEEDER` seeding a four chunk archive and a single chunk one, challenged on a chunk of each,
+/// with the challenged chunks grouped by archive.
+fn challenged_two_archives(pairs: &[ed25519::Pair; 2]) -> Vec<(Public, Vec<u64>)> {
+	register_four_chunk_archive(&pairs[0]);
+	register_single_chunk(&pairs[1], b"x");
+	assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(2)));
+	challenge_chunks(&[(pairs[0].public(), 2), (pairs[1].public(), 0)]);
+	DatVerify::challenged_archives(&<SelectedChallenges<Test>>::get(0).0)
+}
+
+#[test]
+fn submit_proof_proves_every_challenged_archive() {
+	new_test_ext().execute_with(|| {
+		let pairs = [archive_pair(1), archive_pair(2)];
+		let archives = challenged_two_archives(&pairs);
+		let nodes = tree_nodes(&FOUR_CHUNKS);
+		let proofs: Vec<Proof> = archives.iter().map(|(archive, _)| if *archive == pairs[0].public() {
+			chunks_proof(&nodes, &[2], &[0, 5])
+		} else {
+			single_chunk_proof(b"x")
+		}).collect();
+
+		let mut reversed = proofs.clone();
+		reversed.reverse();
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, reversed),
+			Error::<Test>::ProvesWrongChunk,
+		);
+		assert_ok!(DatVerify::submit_proof(Origin::signed(SEEDER), 0, proofs));
+		assert!(!<ChallengeMap>::exists(0));
+	});
+}
+
+#[test]
+fn submit_proof_skips_unregistered_archives() {
+	new_test_ext().execute_with(|| {
+		let pairs = [archive_pair(1), archive_pair(2)];
+		let archives = challenged_two_archives(&pairs);
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 0));
+		let proofs: Vec<Proof> = archives.iter().map(|(archive, _)| if *archive == pairs[0].public() {
+			Proof::default()
+		} else {
+			single_chunk_proof(b"x")
+		}).collect();
+
+		assert_ok!(DatVerify::submit_proof(Origin::signed(SEEDER), 0, proofs));
+		assert!(!<ChallengeMap>::exists(0));
+	});
+}
+
+/// The proof of a single chunk carrying `nodes` made up nodes.
+fn proof_with_nodes(nodes: u32) -> Proof {
+	Proof {
+		chunks: vec![b"x".to_vec()],
+		nodes: (0..nodes as u64).map(|index| Node { index, hash: H256::zero(), size: 1 }).collect(),
+	}
+}
+
+#[test]
+fn submit_proof_rejects_oversized_proofs() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		DatVerify::on_initialize(1);
+
+		let max_chunk_size = MaxChunkSize::get() as usize;
+		assert_noop!(
+			DatVerify::submit_proof(
+				Origin::signed(SEEDER), 0, vec![single_chunk_proof(&vec![0u8; max_chunk_size + 1])],
+			),
+			Error::<Test>::ProofTooLarge,
+		);
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![proof_with_nodes(MaxProofNodes::get() + 1)]),
+			Error::<Test>::ProofTooLarge,
+		);
+		let max_chunks = MaxChunksPerChallenge::get() as usize;
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![single_chunk_proof(b"x"); max_chunks + 1]),
+			Error::<Test>::ProofTooLarge,
+		);
+		// within the limits, the proof is verified as usual.
+		assert_noop!(
+			DatVerify::submit_proof(
+				Origin::signed(SEEDER), 0, vec![single_chunk_proof(&vec![0u8; max_chunk_size])],
+			),
+			Error::<Test>::RootHashVerificationFailed,
+		);
+	});
+}
+
+/// `roots` made up roots of one byte each, which do not make a tree.
+fn made_up_roots(roots: u32) -> RootHashPayload {
+	RootHashPayload {
+		hash_type: hashing::ROOT_TYPE,
+		children: (0..roots as u64)
+			.map(|index| ParentHashInRoot { hash: H256::zero(), hash_number: 2 * index, total_length: 1 })
+			.collect(),
+	}
+}
+
+#[test]
+fn register_data_rejects_too_many_roots() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		let root = made_up_roots(MaxRootChildren::get() + 1);
+		// the roots are counted before the signature is checked.
+		assert_noop!(
+			DatVerify::register_data(Origin::signed(PUBLISHER), (pair.public(), root.clone(), H512::zero()), None),
+			Error::<Test>::TooManyRoots,
+		);
+		assert_noop!(
+			DatVerify::force_register_data(
+				Origin::signed(ForceAccount::get()), PUBLISHER, (pair.public(), root, H512::zero()),
+			),
+			Error::<Test>::TooManyRoots,
+		);
+
+		let old_root = register_two_chunk_archive(&pair);
+		let consistency_proof = ConsistencyProof {
+			old_roots: old_root.children,
+			nodes: proof_with_nodes(MaxProofNodes::get() + 1).nodes,
+		};
+		let (root, signature) = signed_roots(&pair, &tree_nodes(&FOUR_CHUNKS), &[3]);
+		assert_noop!(
+			DatVerify::register_data(
+				Origin::signed(PUBLISHER), (pair.public(), root, signature), Some(consistency_proof),
+			),
+			Error::<Test>::ProofTooLarge,
+		);
+	});
+}
+
+#[test]
+fn weights_scale_with_the_size_of_the_call() {
+	let weight = |call: Call<Test>| call.get_dispatch_info().weight;
+
+	let small = weight(Call::submit_proof(0, vec![single_chunk_proof(b"x")]));
+	assert_eq!(small, verification_weight(1, 1));
+	assert_eq!(
+		weight(Call::submit_proof(0, vec![single_chunk_proof(&[0u8; 100])])),
+		small + 99 * WEIGHT_PER_CHUNK_BYTE,
+	);
+	assert_eq!(weight(Call::submit_proof(0, vec![proof_with_nodes(2)])), small + 2 * WEIGHT_PER_NODE);
+
+	let pair = archive_pair(1);
+	let register = |roots: u32| {
+		weight(Call::register_data((pair.public(), made_up_roots(roots), H512::zero()), None))
+	};
+	assert_eq!(register(2), register(1) + WEIGHT_PER_NODE);
+
+	let info = Call::<Test>::force_register_data(PUBLISHER, (pair.public(), made_up_roots(1), H512::zero()))
+		.get_dispatch_info();
+	assert_eq!(info.class, DispatchClass::Operational);
+	assert_eq!(info.weight, verification_weight(0, MaxRootChildren::get() as usize));
+}
+
+#[test]
+fn check_proof_limits_drops_oversized_calls() {
+	let validate = |call: Call<Test>| {
+		CheckProofLimits::<Test>::new().validate(&SEEDER, &call, Default::default(), 0)
+	};
+
+	let exhausted: TransactionValidity = InvalidTransaction::ExhaustsResources.into();
+
+	let oversized = vec![proof_with_nodes(MaxProofNodes::get() + 1)];
+	assert_eq!(validate(Call::submit_proof(0, oversized)), exhausted);
+	let root = made_up_roots(MaxRootChildren::get() + 1);
+	let call = Call::register_data((archive_pair(1).public(), root.clone(), H512::zero()), None);
+	assert_eq!(validate(call), exhausted);
+	assert_eq!(validate(Call::register_private_data((archive_pair(1).public(), root, H512::zero()), None)), exhausted);
+	let envelope = vec![0u8; MAX_ENVELOPE_SIZE + 1];
+	assert_eq!(validate(Call::deliver_key(archive_pair(1).public(), SEEDER, envelope)), exhausted);
+
+	// proofs within the limits are left for the module to verify.
+	let proof = vec![single_chunk_proof(b"x")];
+	assert_eq!(validate(Call::submit_proof(0, proof)), Ok(ValidTransaction::default()));
+	assert_eq!(validate(Call::unregister_seeder()), Ok(ValidTransaction::default()));
+}
+
+#[test]
+fn challenges_without_hosted_archives_lapse() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		challenge_seeder(pair.public(), 0);
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 0));
+
+		// nothing is left to prove, so nothing is paid.
+		assert_noop!(
+			DatVerify::submit_proof(Origin::signed(SEEDER), 0, vec![Proof::default()]),
+			Error::<Test>::NothingToProve,
+		);
+		DatVerify::on_finalize(10);
+		assert!(!<ChallengeMap>::exists(0));
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get());
+	});
+}
+
+#[test]
+fn expired_challenges_fail_on_the_archives_still_hosted() {
+	new_test_ext().execute_with(|| {
+		let pairs = [archive_pair(1), archive_pair(2)];
+		challenged_two_archives(&pairs);
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 0));
+
+		DatVerify::on_finalize(10);
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::ChallengeFailed(SEEDER, vec![pairs[1].public()])),
+		);
+	});
+}
+
+fn served() -> Attestation {
+	Attestation { location: 0, latency: Some(10) }
+}
+
+fn not_served() -> Attestation {
+	Attestation { location: 0, latency: None }
+}
+
+#[test]
+fn challenge_selects_attestors() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		for attestor in ATTESTORS.iter() {
+			assert_ok!(DatVerify::register_seeder(Origin::signed(*attestor), SeedRequest::Archives(1)));
+		}
+		DatVerify::on_initialize(1);
+
+		// every other seeder attests the challenged one.
+		let challenged = <SelectedUsers<Test>>::get(0);
+		let expected: Vec<u64> = [SEEDER, ATTESTORS[0], ATTESTORS[1], ATTESTORS[2]].iter()
+			.cloned()
+			.filter(|account| *account != challenged)
+			.collect();
+		let (mut attestors, seen) = <ChallengeAttestations<Test>>::get(0);
+		attestors.sort_unstable();
+		assert_eq!(attestors, expected);
+		assert!(seen.is_empty());
+		for attestor in &attestors {
+			assert!(<Attestors<Test>>::exists(attestor));
+		}
+		assert_eq!(<AttestationRounds<Test>>::get(0), (challenged, pair.public(), 2));
+		assert!(dat_verify_events().iter().any(|event| match event {
+			RawEvent::AttestorsSelected(0, _) => true,
+			_ => false,
+		}));
+	});
+}
+
+#[test]
+fn challenge_without_enough_seeders_asks_no_attestations() {
+	new_test_ext().execute_with(|| {
+		register_and_seed(&archive_pair(1), b"x");
+		assert_ok!(DatVerify::register_seeder(Origin::signed(ATTESTORS[0]), SeedRequest::Archives(1)));
+		DatVerify::on_initialize(1);
+
+		// a single attestor cannot make a quorum of two.
+		assert!(<ChallengeMap>::exists(0));
+		assert!(!<AttestationRounds<Test>>::exists(0));
+		assert!(!<ChallengeAttestations<Test>>::exists(0));
+	});
+}
+
+#[test]
+fn submit_attestation_requires_selection() {
+	new_test_ext().execute_with(|| {
+		request_attestations(archive_pair(1).public(), &ATTESTORS[..2]);
+		assert_noop!(
+			DatVerify::submit_attestation(Origin::signed(ATTESTORS[2]), served()),
+			Error::<Test>::NotAttestor,
+		);
+		assert_ok!(DatVerify::submit_attestation(Origin::signed(ATTESTORS[0]), served()));
+		assert_eq!(dat_verify_events().last(), Some(&RawEvent::Attest(ATTESTORS[0], served())));
+		assert_eq!(<ChallengeAttestations<Test>>::get(0).1, vec![(ATTESTORS[0], served())]);
+
+		// every attestor attests once.
+		assert_noop!(
+			DatVerify::submit_attestation(Origin::signed(ATTESTORS[0]), served()),
+			Error::<Test>::NotAttestor,
+		);
+	});
+}
+
+#[test]
+fn quorum_of_attestations_pays_agreeing_attestors() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		request_attestations(pair.public(), &ATTESTORS);
+		assert_ok!(DatVerify::submit_attestation(Origin::signed(ATTESTORS[0]), served()));
+		assert_ok!(DatVerify::submit_attestation(Origin::signed(ATTESTORS[1]), not_served()));
+		assert!(<AttestationRounds<Test>>::exists(0));
+		assert_ok!(DatVerify::submit_attestation(Origin::signed(ATTESTORS[2]), served()));
+
+		let reward = AttestationReward::get();
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::AttestationsPassed(SEEDER, pair.public())),
+		);
+		assert!(dat_verify_events().contains(&RawEvent::AttestorRewarded(ATTESTORS[0], reward)));
+		assert_eq!(Balances::free_balance(ATTESTORS[0]), INITIAL_BALANCE + reward);
+		assert_eq!(Balances::free_balance(ATTESTORS[1]), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(ATTESTORS[2]), INITIAL_BALANCE + reward);
+		assert_eq!(Balances::free_balance(DatVerify::pot_account()), INITIAL_BALANCE - 2 * reward);
+		assert!(!<AttestationRounds<Test>>::exists(0));
+		assert!(!<ChallengeAttestations<Test>>::exists(0));
+	});
+}
+
+#[test]
+fn quorum_of_failed_attestations_slashes_seeder() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		request_attestations(pair.public(), &ATTESTORS);
+		assert_ok!(DatVerify::submit_attestation(Origin::signed(ATTESTORS[0]), not_served()));
+		assert_ok!(DatVerify::submit_attestation(Origin::signed(ATTESTORS[1]), not_served()));
+
+		let slashed = ChallengeSlash::get() * SeederBond::get();
+		let reward = AttestationReward::get();
+		assert!(dat_verify_events().contains(&RawEvent::SeederSlashed(SEEDER, slashed)));
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::AttestationsFailed(SEEDER, pair.public())),
+		);
+		assert_eq!(Balances::free_balance(ATTESTORS[0]), INITIAL_BALANCE + reward);
+		assert_eq!(Balances::free_balance(ATTESTORS[1]), INITIAL_BALANCE + reward);
+		assert_eq!(
+			Balances::free_balance(DatVerify::pot_account()),
+			INITIAL_BALANCE + slashed - 2 * reward,
+		);
+
+		// the attestor yet to attest is no longer asked to.
+		assert!(!<Attestors<Test>>::exists(ATTESTORS[2]));
+		assert_noop!(
+			DatVerify::submit_attestation(Origin::signed(ATTESTORS[2]), served()),
+			Error::<Test>::NotAttestor,
+		);
+	});
+}
+
+#[test]
+fn attestations_without_quorum_expire() {
+	new_test_ext().execute_with(|| {
+		request_attestations(archive_pair(1).public(), &ATTESTORS);
+		assert_ok!(DatVerify::submit_attestation(Origin::signed(ATTESTORS[0]), served()));
+		DatVerify::on_finalize(9);
+		assert!(<AttestationRounds<Test>>::exists(0));
+
+		DatVerify::on_finalize(10);
+		assert!(!<AttestationRounds<Test>>::exists(0));
+		assert!(!<ChallengeAttestations<Test>>::exists(0));
+		assert!(!<Attestors<Test>>::exists(ATTESTORS[1]));
+		assert_eq!(Balances::free_balance(ATTESTORS[0]), INITIAL_BALANCE);
+	});
+}
+
+#[test]
+fn passed_challenges_build_reputation() {
+	new_test_ext().execute_with(|| {
+		pass_challenge();
+		assert_eq!(DatVerify::reputation(SEEDER), ProofReputation::get());
+		assert_eq!(TrustedPool::pool(), vec![(SEEDER, Some(ProofReputation::get()))]);
+		// entering the pool on its behalf takes nothing from the seeder but its bond.
+		assert_eq!(Balances::reserved_balance(SEEDER), SeederBond::get());
+		// the pool takes its members every period.
+		assert!(!<Test as Trait>::TrustedSeeders::is_trusted(&SEEDER));
+		TrustedPool::on_initialize(1);
+		assert!(<Test as Trait>::TrustedSeeders::is_trusted(&SEEDER));
+
+		// a seeder leaving the pool keeps its reputation.
+		assert_ok!(DatVerify::unregister_seeder(Origin::signed(SEEDER)));
+		assert!(TrustedPool::pool().is_empty());
+		assert!(TrustedPool::members().is_empty());
+		assert_eq!(DatVerify::reputation(SEEDER), ProofReputation::get());
+	});
+}
+
+#[test]
+fn reputation_grows_rewards() {
+	new_test_ext().execute_with(|| {
+		DatVerify::set_reputation(&SEEDER, MaxReputation::get());
+		pass_challenge();
+
+		let reward = 2 * ChallengeReward::get();
+		assert_eq!(
+			dat_verify_events().last(),
+			Some(&RawEvent::SeederRewarded(SEEDER, reward)),
+		);
+		assert_eq!(Balances::free_balance(SEEDER), INITIAL_BALANCE - SeederBond::get() + reward);
+		// reputation stops at its most.
+		assert_eq!(DatVerify::reputation(SEEDER), MaxReputation::get());
+	});
+}
+
+#[test]
+fn failed_challenges_cost_reputation() {
+	new_test_ext().execute_with(|| {
+		DatVerify::set_reputation(&SEEDER, 50);
+		register_and_seed(&archive_pair(1), b"x");
+		assert_eq!(TrustedPool::pool(), vec![(SEEDER, Some(50))]);
+		DatVerify::on_initialize(1);
+		DatVerify::on_finalize(2);
+
+		assert_eq!(DatVerify::reputation(SEEDER), 50 - FailureReputation::get());
+		assert!(TrustedPool::pool().is_empty());
+
+		// a returning seeder is ranked by the reputation it kept.
+		DatVerify::on_initialize(3);
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+		assert_eq!(TrustedPool::pool(), vec![(SEEDER, Some(50 - FailureReputation::get()))]);
+
+		// a seeder left without reputation is forgotten.
+		DatVerify::on_initialize(4);
+		DatVerify::on_finalize(5);
+		assert!(!<Reputations<Test>>::exists(SEEDER));
+	});
+}
+
+#[test]
+fn attestations_move_reputation() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		DatVerify::set_reputation(&SEEDER, 50);
+		DatVerify::set_reputation(&ATTESTORS[1], 10);
+		request_attestations(pair.public(), &ATTESTORS);
+		assert_ok!(DatVerify::submit_attestation(Origin::signed(ATTESTORS[0]), not_served()));
+		assert_ok!(DatVerify::submit_attestation(Origin::signed(ATTESTORS[1]), served()));
+		assert_ok!(DatVerify::submit_attestation(Origin::signed(ATTESTORS[2]), not_served()));
+
+		let gain = AttestationReputation::get();
+		assert_eq!(DatVerify::reputation(ATTESTORS[0]), gain);
+		assert_eq!(DatVerify::reputation(ATTESTORS[1]), 10 - gain);
+		assert_eq!(DatVerify::reputation(ATTESTORS[2]), gain);
+		assert_eq!(DatVerify::reputation(SEEDER), 50 - FailureReputation::get());
+		// only seeders are ranked.
+		assert_eq!(TrustedPool::pool(), vec![(SEEDER, Some(50 - FailureReputation::get()))]);
+	});
+}
+
+#[test]
+fn reputation_decays_every_period() {
+	new_test_ext().execute_with(|| {
+		DatVerify::set_reputation(&SEEDER, 50);
+		DatVerify::set_reputation(&PUBLISHER, 5);
+		DatVerify::set_reputation(&PAUPER, 1);
+		DatVerify::on_initialize(ReputationPeriod::get() - 1);
+		assert_eq!(DatVerify::reputation(SEEDER), 50);
+
+		// the decay goes on over as many blocks as it takes.
+		DatVerify::on_initialize(ReputationPeriod::get());
+		// every reputation loses at least one.
+		assert_eq!(DatVerify::reputation(PUBLISHER), 4);
+		assert!(!<Reputations<Test>>::exists(PAUPER));
+		assert_eq!(DatVerify::reputation(SEEDER), 50);
+		assert_eq!(DatVerify::decay_cursor(), 1);
+		DatVerify::on_initialize(ReputationPeriod::get() + 1);
+		assert_eq!(DatVerify::reputation(SEEDER), 45);
+		assert_eq!(DatVerify::decay_cursor(), 0);
+
+		DatVerify::on_initialize(2 * ReputationPeriod::get());
+		assert_eq!(DatVerify::reputation(SEEDER), 41);
+		assert_eq!(DatVerify::reputation(PUBLISHER), 3);
+		assert_eq!(DatVerify::reputed_count(), 2);
+	});
+}
+
+#[test]
+fn reputations_lost_during_a_decay_decay_the_others_once() {
+	new_test_ext().execute_with(|| {
+		DatVerify::set_reputation(&SEEDER, 50);
+		DatVerify::set_reputation(&PUBLISHER, 50);
+		DatVerify::set_reputation(&PAUPER, 50);
+		DatVerify::on_initialize(ReputationPeriod::get());
+		assert_eq!(DatVerify::reputation(PAUPER), 45);
+		assert_eq!(DatVerify::reputation(PUBLISHER), 45);
+
+		// the seeder left to decay loses its reputation, and a decayed one takes its index.
+		DatVerify::lower_reputation(&SEEDER, 50);
+		assert_eq!(DatVerify::reputed_count(), 2);
+		assert_eq!(DatVerify::decay_cursor(), 0);
+		DatVerify::on_initialize(ReputationPeriod::get() + 1);
+		assert_eq!(DatVerify::reputation(PAUPER), 45);
+		assert_eq!(DatVerify::reputation(PUBLISHER), 45);
+	});
+}
+
+#[test]
+fn require_trusted_is_for_publishers() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_single_chunk(&pair, b"x");
+		assert_noop!(
+			DatVerify::require_trusted(Origin::signed(SEEDER), pair.public(), true),
+			Error::<Test>::PermissionError,
+		);
+		assert_noop!(
+			DatVerify::require_trusted(Origin::signed(PUBLISHER), archive_pair(2).public(), true),
+			Error::<Test>::PermissionError,
+		);
+		assert_ok!(DatVerify::require_trusted(Origin::signed(PUBLISHER), pair.public(), true));
+		assert!(DatVerify::is_trusted_only(pair.public()));
+		assert_ok!(DatVerify::require_trusted(Origin::signed(PUBLISHER), pair.public(), false));
+		assert!(!DatVerify::is_trusted_only(pair.public()));
+
+		assert_ok!(DatVerify::require_trusted(Origin::signed(PUBLISHER), pair.public(), true));
+		assert_ok!(DatVerify::unregister_data(Origin::signed(PUBLISHER), 0));
+		assert!(!DatVerify::is_trusted_only(pair.public()));
+	});
+}
+
+#[test]
+fn trusted_only_archives_go_to_trusted_seeders_first() {
+	new_test_ext().execute_with(|| {
+		register_single_chunk(&archive_pair(1), b"x");
+		register_single_chunk(&archive_pair(2), b"y");
+		assert_ok!(DatVerify::require_trusted(Origin::signed(PUBLISHER), archive_pair(1).public(), true));
+		// the better paid archive is further below its target.
+		set_deal(archive_pair(1).public(), 1, 0);
+		set_deal(archive_pair(2).public(), 2, 1_000_000);
+
+		assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(2)));
+		assert_eq!(pinned_archives(), vec![archive_pair(2).public()]);
+
+		<Test as Trait>::TrustedSeeders::score(&ATTESTORS[0], ProofReputation::get());
+		TrustedPool::on_initialize(1);
+		assert_ok!(DatVerify::register_seeder(Origin::signed(ATTESTORS[0]), SeedRequest::Archives(1)));
+		assert_eq!(DatVerify::hosters_of(archive_pair(1).public()), vec![ATTESTORS[0]]);
+	});
+}
+
+#[test]
+fn runtime_api_queries_work() {
+	new_test_ext().execute_with(|| {
+		let pair = archive_pair(1);
+		register_and_seed(&pair, b"x");
+		DatVerify::on_initialize(1);
+
+		assert_eq!(DatVerify::archives_hosted_by(SEEDER), vec![pair.public()]);
+		assert!(DatVerify::archives_hosted_by(PUBLISHER).is_empty());
+		assert_eq!(DatVerify::hosters_of(pair.public()), vec![SEEDER]);
+		assert_eq!(DatVerify::challenges_for(SEEDER), vec![ChallengeInfo {
+			index: 0,
+			chunks: vec![(pair.public(), 0)],
+			deadline: 2,
+		}]);
+		assert!(DatVerify::challenges_for(PUBLISHER).is_empty());
+
+		let (_, root, _) = single_chunk_archive(&pair, b"x");
+		assert_eq!(DatVerify::archive_info(pair.public()), Some(ArchiveInfo {
+			index: 0,
+			publisher: PUBLISHER,
+			merkle_root: root.hash(),
+			tree_size: 1,
+			tree_length: 1,
+			tree_version: 0,
+			hosters: vec![SEEDER],
+		}));
+		assert_eq!(DatVerify::archive_info(archive_pair(2).public()), None);
+	});
+}
+
+#[test]
+fn proof_nodes_are_the_siblings_and_roots_off_the_paths() {
+	// a single leaf is the root.
+	assert!(proof_nodes(&[0], 1).is_empty());
+	assert_eq!(proof_nodes(&[2], 2), vec![0]);
+	// the third leaf is a root of its own.
+	assert_eq!(proof_nodes(&[4], 3), vec![1]);
+	assert_eq!(proof_nodes(&[6], 4), vec![1, 4]);
+	assert_eq!(proof_nodes(&[10], 6), vec![3, 8]);
+	// siblings on another path are recomputed.
+	assert_eq!(proof_nodes(&[0, 2], 2), Vec::<u64>::new());
+	assert_eq!(proof_nodes(&[0, 6], 4), vec![2, 4]);
+	assert_eq!(proof_nodes(&[0, 10], 6), vec![2, 5, 8]);
+	// leaves past the tree are ignored.
+	assert_eq!(proof_nodes(&[8], 4), vec![3]);
+}
+
+/// Challenge `SEEDER` on the second chunk of a four chunk archive it seeds, with what the node
+/// reads to answer it.
+fn challenged_second_chunk(pair: &ed25519::Pair) -> ChunkResponse {
+	let nodes = register_four_chunk_archive(pair);
+	assert_ok!(DatVerify::register_seeder(Origin::signed(SEEDER), SeedRequest::Archives(1)));
+	challenge_seeder(pair.public(), 2);
+	ChunkResponse {
+		archive: pair.public(),
+		leaves: vec![2],
+		chunks: vec![b"world".to_vec()],
+		nodes: proof_nodes(&[2], 4).iter()
+			.map(|index| Node::find(&nodes, *index).expect("node is part of the tree").clone())
+			.collect(),
+	}
+}
+
+#[test]
+fn offchain_worker_submits_proofs_of_local_seeders() {
+	let (mut ext, _, pool_state) = new_offchain_test_ext(&[SEEDER]);
+	ext.execute_with(|| {
+		let pair = archive_pair(1);
+		let response = challenged_second_chunk(&pair);
+		StorageValueRef::persistent(RESPONSES_KEY).set(&vec![response]);
+
+		DatVerify::offchain_worker(1);
+
+		let transactions = &pool_state.read().transactions;
+		assert_eq!(transactions.len(), 1);
+		let xt = Extrinsic::decode(&mut &*transactions[0]).unwrap();
+		assert_eq!(xt.0, Some((SEEDER, ())));
+		match xt.1 {
+			Call::submit_proof(0, proofs) => assert_ok!(
+				DatVerify::submit_proof(Origin::signed(SEEDER), 0, proofs)
+			),
+			_ => panic!("the worker only submits proofs"),
+		}
+		assert!(!<ChallengeMap>::exists(0));
+		// the response was used up.
+		assert_eq!(
+			StorageValueRef::persistent(RESPONSES_KEY).get::<Vec<ChunkResponse>>(),
+			Some(Some(vec![])),
+		);
+	});
+}
+
+#[test]
+fn offchain_worker_answers_once_the_chunks_are_read() {
+	let (mut ext, _, pool_state) = new_offchain_test_ext(&[SEEDER]);
+	ext.execute_with(|| {
+		let pair = archive_pair(1);
+		let response = challenged_second_chunk(&pair);
+
+		// the worker asks the node for the chunks and moves on.
+		DatVerify::offchain_worker(1);
+		assert!(pool_state.read().transactions.is_empty());
+		let request = ChunkRequest { archive: pair.public(), leaves: vec![2], nodes: proof_nodes(&[2], 4) };
+		assert_eq!(
+			StorageValueRef::persistent(REQUESTS_KEY).get::<Vec<ChunkRequest>>(),
+			Some(Some(vec![request.clone()])),
+		);
+		// and asks only once.
+		DatVerify::offchain_worker(2);
+		assert_eq!(
+			StorageValueRef::persistent(REQUESTS_KEY).get::<Vec<ChunkRequest>>(),
+			Some(Some(vec![request])),
+		);
+
+		StorageValueRef::persistent(RESPONSES_KEY).set(&vec![response]);
+		DatVerify::offchain_worker(3);
+		assert_eq!(pool_state.read().transactions.len(), 1);
+	});
+}
+
+#[test]
+fn offchain_worker_submits_once_per_challenge() {
+	let (mut ext, _, pool_state) = new_offchain_test_ext(&[SEEDER]);
+	ext.execute_with(|| {
+		let pair = archive_pair(1);
+		let response = challenged_second_chunk(&pair);
+		StorageValueRef::persistent(RESPONSES_KEY).set(&vec![response.clone()]);
+		DatVerify::offchain_worker(1);
+
+		// the challenge is still open until the proof is included.
+		StorageValueRef::persistent(RESPONSES_KEY).set(&vec![response]);
+		DatVerify::offchain_worker(2);
+
+		assert_eq!(pool_state.read().transactions.len(), 1);
+		assert_eq!(StorageValueRef::persistent(REQUESTS_KEY).get::<Vec<ChunkRequest>>(), None);
+	});
+}
+
+#[test]
+fn offchain_worker_ignores_other_seeders() {
+	let (mut ext, _, pool_state) = new_offchain_test_ext(&[ATTESTORS[0]]);
+	ext.execute_with(|| {
+		let pair = archive_pair(1);
+		challenged_second_chunk(&pair);
+
+		DatVerify::offchain_worker(1);
+
+		assert!(pool_state.read().transactions.is_empty());
+		assert_eq!(StorageValueRef::persistent(REQUESTS_KEY).get::<Vec<ChunkRequest>>(), None);
+	});
+}
+
+#[test]
+fn type_definitions_follow_the_code() {
+	use types::{Definition, TypeDefinition};
+	assert_eq!(
+		ParentHashPayload::type_definition(),
+		("ParentHashPayload", Definition::Struct(vec![
+			("hash_type", "u8"),
+			("total_length", "u64"),
+			("child_hashes", "[H256; 2]"),
+		])),
+	);
+	assert_eq!(
+		Proof::type_definition().1,
+		Definition::Struct(vec![("chunks", "Vec<Vec<u8>>"), ("nodes", "Vec<Node>")]),
+	);
+	assert_eq!(
+		SeedRequest::type_definition().1,
+		Definition::Enum(vec![("Archives", "u32"), ("Capacity", "DatSize")]),
+	);
+	assert!(types::definitions().contains(&("DatIdIndex", Definition::Alias("u64"))));
+}
+
+#[test]
+fn public_keys_are_encoded_like_hashes() {
+	assert_eq!(archive_pair(1).public().encode(), H256::from(archive_pair(1).public().0).encode());
+}
+
+#[test]
+fn type_definitions_are_written_as_json() {
+	let json = types::to_json(&[
+		("DatSize", types::Definition::Alias("u64")),
+		("SeedRequest", types::Definition::Enum(vec![("Archives", "u32"), ("Capacity", "DatSize")])),
+	]);
+	assert_eq!(
+		json,
+		"{\n\t\"DatSize\": \"u64\",\n\t\"SeedRequest\": {\n\t\t\"_enum\": {\n\t\t\t\"Archives\": \"u32\",\n\t\t\t\"Capacity\": \"DatSize\"\n\t\t}\n\t}\n}\n",
+	);
+}
@@ -0,0 +1,80 @@
+//! The types of the module as Polkadot.js defines custom types, for front ends to decode its
+//! calls, events and storage.
+//!
+//! Structs and enums derive their `TypeDefinition` from their fields and variants, and aliases
+//! only compile while they alias the type they are defined as, so the definitions follow the
+//! code. The runtime checks them in as `types.json`.
+
+use crate::*;
+
+pub use pallet_dat_verify_types_derive::TypeDefinition;
+
+/// How Polkadot.js defines a type.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Definition {
+	/// Another name of a type.
+	Alias(&'static str),
+	/// Named fields, in the order they are encoded in.
+	Struct(Vec<(&'static str, &'static str)>),
+	/// Variants holding a single value, or `Null`, in the order of their indeces.
+	Enum(Vec<(&'static str, &'static str)>),
+}
+
+/// A type front ends need the definition of.
+pub trait TypeDefinition {
+	/// The name of the type and its definition.
+	fn type_definition() -> (&'static str, Definition);
+}
+
+// the definition of an alias, failing to compile unless it aliases `$ty`.
+macro_rules! alias {
+	($name:ident = $ty:ty) => {{
+		let _: fn($name) -> $ty = |value| value;
+		(stringify!($name), Definition::Alias(stringify!($ty)))
+	}};
+}
+
+/// The definitions of the types the module uses.
+pub fn definitions() -> Vec<(&'static str, Definition)> {
+	vec![
+		Node::type_definition(),
+		Proof::type_definition(),
+		ChunkHashPayload::type_definition(),
+		ParentHashPayload::type_definition(),
+		ParentHashInRoot::type_definition(),
+		RootHashPayload::type_definition(),
+		ConsistencyProof::type_definition(),
+		SeedRequest::type_definition(),
+		Attestation::type_definition(),
+		alias!(DatIdIndex = u64),
+		alias!(UserIdIndex = u64),
+		alias!(DatSize = u64),
+		alias!(Reputation = u32),
+		// ed25519 keys are encoded as their 32 bytes, like hashes.
+		("Public", Definition::Alias("H256")),
+		// the seeds of challenges are hashes of the runtime, which hashes with Blake2-256.
+		("Hash", Definition::Alias("H256")),
+	]
+}
+
+/// `definitions` as the JSON object Polkadot.js takes, indented with tabs.
+pub fn to_json(definitions: &[(&'static str, Definition)]) -> String {
+	let entries = definitions.iter().map(|(name, definition)| {
+		let value = match definition {
+			Definition::Alias(ty) => format!("\"{}\"", ty),
+			Definition::Struct(fields) => json_object(fields, 1),
+			Definition::Enum(variants) => format!("{{\n\t\t\"_enum\": {}\n\t}}", json_object(variants, 2)),
+		};
+		format!("\t\"{}\": {}", name, value)
+	}).collect::<Vec<_>>();
+	format!("{{\n{}\n}}\n", entries.join(",\n"))
+}
+
+// a JSON object of strings, nested `depth` levels deep.
+fn json_object(entries: &[(&'static str, &'static str)], depth: usize) -> String {
+	let indent = "\t".repeat(depth);
+	let entries = entries.iter()
+		.map(|(key, value)| format!("{}\t\"{}\": \"{}\"", indent, key, value))
+		.collect::<Vec<_>>();
+	format!("{{\n{}\n{}}}", entries.join(",\n"), indent)
+}
@@ -0,0 +1,76 @@
+//! Derive of `pallet_dat_verify::types::TypeDefinition`, defining structs by their named fields
+//! and enums by their variants, with every type written as in the code.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use proc_macro_crate::crate_name;
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput, Error, Fields, Ident, Result, Type, parse_macro_input};
+use frame_support_procedural_tools::clean_type_string;
+
+const TYPES_CRATE: &str = "pallet-dat-verify";
+
+/// Implement `TypeDefinition` for a struct with named fields, or an enum whose variants hold at
+/// most one value.
+#[proc_macro_derive(TypeDefinition)]
+pub fn derive_type_definition(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	type_definition(input).unwrap_or_else(|error| error.to_compile_error()).into()
+}
+
+fn type_definition(input: DeriveInput) -> Result<TokenStream2> {
+	let types = types_access()?;
+	let definition = match &input.data {
+		Data::Struct(data) => match &data.fields {
+			Fields::Named(fields) => {
+				let fields = fields.named.iter().map(|field| {
+					let name = field.ident.as_ref().map(ToString::to_string).unwrap_or_default();
+					let ty = type_string(&field.ty);
+					quote!( (#name, #ty) )
+				});
+				quote!( #types::Definition::Struct(vec![ #( #fields ),* ]) )
+			},
+			_ => return Err(Error::new_spanned(&input.ident, "only structs with named fields are defined")),
+		},
+		Data::Enum(data) => {
+			let variants = data.variants.iter().map(|variant| {
+				let name = variant.ident.to_string();
+				let ty = match &variant.fields {
+					Fields::Unit => "Null".to_string(),
+					Fields::Unnamed(fields) if fields.unnamed.len() == 1 => type_string(&fields.unnamed[0].ty),
+					_ => return Err(Error::new_spanned(variant, "only variants holding at most one value are defined")),
+				};
+				Ok(quote!( (#name, #ty) ))
+			}).collect::<Result<Vec<_>>>()?;
+			quote!( #types::Definition::Enum(vec![ #( #variants ),* ]) )
+		},
+		Data::Union(_) => return Err(Error::new_spanned(&input.ident, "unions are not defined")),
+	};
+	let ident = &input.ident;
+	let name = ident.to_string();
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	Ok(quote! {
+		impl #impl_generics #types::TypeDefinition for #ident #ty_generics #where_clause {
+			fn type_definition() -> (&'static str, #types::Definition) {
+				(#name, #definition)
+			}
+		}
+	})
+}
+
+/// The path of the `types` module, from the module itself or a crate depending on it.
+fn types_access() -> Result<TokenStream2> {
+	if std::env::var("CARGO_PKG_NAME").ok().as_ref().map(String::as_str) == Some(TYPES_CRATE) {
+		return Ok(quote!( crate::types ));
+	}
+	let name = crate_name(TYPES_CRATE).map_err(|error| Error::new(Span::call_site(), error))?;
+	let name = Ident::new(&name, Span::call_site());
+	Ok(quote!( ::#name::types ))
+}
+
+/// The type as it is written, like the metadata writes the types of calls and storage.
+fn type_string(ty: &Type) -> String {
+	clean_type_string(&ty.to_token_stream().to_string())
+}
@@ -0,0 +1,32 @@
+// Writes `hypercore-feed`, the feed of the `hashing` test vectors of dat_verify, with hypercore
+// itself, for the `reads_a_feed_written_by_hypercore` test to check the proof builder against:
+//
+//     npm install hypercore@9 sodium-universal && node make-feed.js
+//
+// The chunks are appended one at a time, so hypercore signs the roots at every length.
+
+const path = require('path')
+const hypercore = require('hypercore')
+const sodium = require('sodium-universal')
+
+const CHUNKS = ['hello', 'world', 'this is', 'a small', 'hypercore']
+
+const publicKey = Buffer.alloc(sodium.crypto_sign_PUBLICKEYBYTES)
+const secretKey = Buffer.alloc(sodium.crypto_sign_SECRETKEYBYTES)
+sodium.crypto_sign_seed_keypair(publicKey, secretKey, Buffer.alloc(32, 7))
+
+const feed = hypercore(path.join(__dirname, 'hypercore-feed'), publicKey, { secretKey, storeSecretKey: true })
+
+function append (i) {
+  if (i === CHUNKS.length) return feed.close()
+  feed.append(Buffer.from(CHUNKS[i]), function (err) {
+    if (err) throw err
+    append(i + 1)
+  })
+}
+
+feed.ready(function (err) {
+  if (err) throw err
+  if (feed.length) throw new Error('hypercore-feed was written already')
+  append(0)
+})
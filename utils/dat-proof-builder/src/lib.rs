@@ -0,0 +1,438 @@
+//! Building the arguments of the `dat_verify` extrinsics from a hypercore feed on disk.
+//!
+//! A feed is read from the files hypercore writes in its directory: `tree` holds every node of
+//! the Merkle tree at its flat-tree index, after a 32 byte header, as its hash followed by the
+//! big-endian number of data bytes under it; `signatures` holds, after the same header, the
+//! signature of the roots of the tree for every length the feed had; `data` holds the chunks
+//! one after the other, `key` the public key of the feed and `secret_key` its secret key, the
+//! seed of the key followed by the key.
+//!
+//! The arguments are made of the runtime's own types, hashed by its `hashing` module, so they
+//! can be encoded into a call as they are:
+//!
+//! ```ignore
+//! let feed = Feed::open("my-feed")?;
+//! let register = feed.register_data(feed.length()?, None)?.call::<Runtime>();
+//! let proof = feed.prove(&leaves, feed.length()?)?;
+//! let prove = SubmitProof { proofs: vec![proof] }.call::<Runtime>(challenge.index);
+//! ```
+//!
+//! A feed is registered privately, by its registration key and with roots signed by that, with
+//! `feed.register_private_data(feed.length()?, None)?.call::<Runtime>()`. Only feeds with their
+//! secret key at hand are.
+//!
+//! A hyperdrive is a directory holding its two feeds, `metadata` and `content`, and is
+//! registered as one archive with `register_drive`:
+//!
+//! ```ignore
+//! let drive = Drive::open("my-drive")?;
+//! let register = drive.register_drive(drive.metadata().length()?, drive.content().length()?)?
+//! 	.call::<Runtime>();
+//! ```
+
+#![warn(missing_docs)]
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+use std::{fs::{self, File}, io::{self, Read, Seek, SeekFrom}, path::{Path, PathBuf}};
+use sp_core::{H256, H512, Pair, ed25519};
+use pallet_dat_verify::{
+	Trait, Call, Public, Signature, Node, Proof, ParentHashInRoot, RootHashPayload, ConsistencyProof,
+	hashing::{self, ROOT_TYPE}, hyperdrive, offchain::proof_nodes,
+};
+
+// the header of the `tree` and `signatures` files.
+const HEADER_SIZE: u64 = 32;
+// a node is its hash followed by its big-endian size.
+const NODE_SIZE: u64 = 40;
+const SIGNATURE_SIZE: u64 = 64;
+// the seed of the key followed by the key.
+const SECRET_KEY_SIZE: usize = 64;
+
+/// A hypercore feed stored in a directory.
+pub struct Feed {
+	dir: PathBuf,
+	key: Public,
+}
+
+/// The arguments of `register_data`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RegisterData {
+	/// The key of the feed, its roots and their signature.
+	pub merkle_root: (Public, RootHashPayload, H512),
+	/// The proof that the tree extends the registered one, for updates.
+	pub consistency_proof: Option<ConsistencyProof>,
+}
+
+impl RegisterData {
+	/// The `register_data` call.
+	pub fn call<T: Trait>(self) -> Call<T> {
+		Call::register_data(self.merkle_root, self.consistency_proof)
+	}
+}
+
+/// The arguments of `register_private_data`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RegisterPrivateData {
+	/// The registration key of the feed, its roots and their signature by that key.
+	pub merkle_root: (Public, RootHashPayload, H512),
+	/// The proof that the tree extends the registered one, for updates.
+	pub consistency_proof: Option<ConsistencyProof>,
+}
+
+impl RegisterPrivateData {
+	/// The `register_private_data` call.
+	pub fn call<T: Trait>(self) -> Call<T> {
+		Call::register_private_data(self.merkle_root, self.consistency_proof)
+	}
+}
+
+/// The arguments of `register_drive`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RegisterDrive {
+	/// The key of the metadata feed, its roots and their signature.
+	pub metadata: (Public, RootHashPayload, H512),
+	/// The key of the content feed, its roots and their signature.
+	pub content: (Public, RootHashPayload, H512),
+	/// The proof of the header of the metadata feed, its first chunk.
+	pub header_proof: Proof,
+}
+
+impl RegisterDrive {
+	/// The `register_drive` call.
+	pub fn call<T: Trait>(self) -> Call<T> {
+		Call::register_drive(self.metadata, self.content, self.header_proof)
+	}
+}
+
+/// The arguments of `submit_proof`, but for the index of the challenge.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SubmitProof {
+	/// The proof of every challenged archive, in the order of `Module::challenged_archives`.
+	pub proofs: Vec<Proof>,
+}
+
+impl SubmitProof {
+	/// The `submit_proof` call answering the challenge at `challenge_index`.
+	pub fn call<T: Trait>(self, challenge_index: u64) -> Call<T> {
+		Call::submit_proof(challenge_index, self.proofs)
+	}
+}
+
+impl Feed {
+	/// The feed in `dir`, with the key in its `key` file.
+	pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+		let dir = dir.into();
+		let key = fs::read(dir.join("key"))?;
+		if key.len() != 32 {
+			return Err(invalid_data("the key file does not hold a 32 byte key"));
+		}
+		let mut raw = [0u8; 32];
+		raw.copy_from_slice(&key);
+		Ok(Feed { dir, key: Public::from_raw(raw) })
+	}
+
+	/// The feed of `key` in `dir`.
+	pub fn new(dir: impl Into<PathBuf>, key: Public) -> Self {
+		Feed { dir: dir.into(), key }
+	}
+
+	/// Write a feed of `chunks` signed by `pair` to `dir`, as hypercore does: the nodes of every
+	/// complete subtree, and the signature of the roots of the tree for every length of the feed.
+	pub fn create(
+		dir: impl Into<PathBuf>,
+		pair: &ed25519::Pair,
+		chunks: &[impl AsRef<[u8]>],
+	) -> io::Result<Self> {
+		if chunks.is_empty() {
+			return Err(invalid_input("a feed has at least one chunk"));
+		}
+		let dir = dir.into();
+		fs::create_dir_all(&dir)?;
+		fs::write(dir.join("key"), pair.public().0)?;
+		fs::write(dir.join("secret_key"), [&pair.seed()[..], &pair.public().0[..]].concat())?;
+		let data: Vec<u8> = chunks.iter().flat_map(|chunk| chunk.as_ref().iter().cloned()).collect();
+		fs::write(dir.join("data"), data)?;
+
+		// (hash, size) of every node, `None` while incomplete.
+		let mut nodes: Vec<Option<(H256, u64)>> = vec![None; 2 * chunks.len() - 1];
+		let mut signatures = vec![0u8; HEADER_SIZE as usize];
+		for (i, chunk) in chunks.iter().enumerate() {
+			let chunk = chunk.as_ref();
+			let leaf = 2 * i as u64;
+			nodes[leaf as usize] = Some((hashing::leaf(chunk), chunk.len() as u64));
+			// every right child completes its parent, the left children came first.
+			let mut current = leaf;
+			while flat_tree::sibling(current) < current {
+				let left = nodes[flat_tree::sibling(current) as usize].expect("left subtrees are complete");
+				let right = nodes[current as usize].expect("the node was just completed");
+				let size = left.1 + right.1;
+				current = flat_tree::parent(current);
+				nodes[current as usize] = Some((hashing::parent(&left.0, &right.0, size), size));
+			}
+			let roots: Vec<ParentHashInRoot> = flat_tree::full_roots(leaf + 2).into_iter().map(|index| {
+				let (hash, size) = nodes[index as usize].expect("roots are complete");
+				ParentHashInRoot { hash, hash_number: index, total_length: size }
+			}).collect();
+			signatures.extend_from_slice(&pair.sign(hashing::tree(&roots).as_bytes()).0);
+		}
+		fs::write(dir.join("signatures"), signatures)?;
+
+		let mut tree = vec![0u8; HEADER_SIZE as usize];
+		for node in nodes {
+			let (hash, size) = node.unwrap_or_default();
+			tree.extend_from_slice(hash.as_bytes());
+			tree.extend_from_slice(&size.to_be_bytes());
+		}
+		fs::write(dir.join("tree"), tree)?;
+		Ok(Feed { dir, key: pair.public() })
+	}
+
+	/// The key of the feed.
+	pub fn key(&self) -> Public {
+		self.key
+	}
+
+	/// The discovery key of the feed, which its seeders find it by.
+	pub fn discovery_key(&self) -> Public {
+		hashing::discovery_key(&self.key)
+	}
+
+	/// The registration key of the feed, which its private registration goes by, derived from
+	/// the secret key in its `secret_key` file.
+	pub fn registration_pair(&self) -> io::Result<ed25519::Pair> {
+		let secret_key = fs::read(self.dir.join("secret_key"))?;
+		if secret_key.len() != SECRET_KEY_SIZE || secret_key[32..] != self.key.0[..] {
+			return Err(invalid_data("the secret_key file does not hold the secret key of the feed"));
+		}
+		let mut seed = [0u8; 32];
+		seed.copy_from_slice(&secret_key[..32]);
+		Ok(ed25519::Pair::from_seed(&hashing::registration_seed(&seed)))
+	}
+
+	/// The number of chunks of the feed, as far as it was signed.
+	pub fn length(&self) -> io::Result<u64> {
+		let size = fs::metadata(self.dir.join("signatures"))?.len();
+		Ok(size.saturating_sub(HEADER_SIZE) / SIGNATURE_SIZE)
+	}
+
+	/// The node at `index`.
+	pub fn node(&self, index: u64) -> io::Result<Node> {
+		let mut entry = [0u8; NODE_SIZE as usize];
+		read_at(&self.dir.join("tree"), HEADER_SIZE + index * NODE_SIZE, &mut entry)?;
+		let mut size = [0u8; 8];
+		size.copy_from_slice(&entry[32..]);
+		Ok(Node {
+			index,
+			hash: H256::from_slice(&entry[..32]),
+			size: u64::from_be_bytes(size),
+		})
+	}
+
+	/// The chunk of the leaf at `index`.
+	pub fn chunk(&self, index: u64) -> io::Result<Vec<u8>> {
+		let leaf = self.node(index)?;
+		// the chunk follows the data of the trees left of it.
+		let offset = flat_tree::full_roots(index).into_iter()
+			.map(|root| self.node(root).map(|root| root.size))
+			.sum::<io::Result<u64>>()?;
+		let mut chunk = vec![0u8; leaf.size as usize];
+		read_at(&self.dir.join("data"), offset, &mut chunk)?;
+		Ok(chunk)
+	}
+
+	/// The signature of the roots of the feed when it had `length` chunks.
+	pub fn signature(&self, length: u64) -> io::Result<Signature> {
+		if length == 0 {
+			return Err(invalid_input("an empty feed has no signature"));
+		}
+		let mut signature = [0u8; SIGNATURE_SIZE as usize];
+		let offset = HEADER_SIZE + (length - 1) * SIGNATURE_SIZE;
+		read_at(&self.dir.join("signatures"), offset, &mut signature)?;
+		Ok(Signature::from_raw(signature))
+	}
+
+	/// The roots of the feed when it had `length` chunks, in ascending flat-tree index order.
+	pub fn roots(&self, length: u64) -> io::Result<Vec<ParentHashInRoot>> {
+		flat_tree::full_roots(2 * length).into_iter()
+			.map(|index| self.node(index).map(|root| ParentHashInRoot {
+				hash: root.hash,
+				hash_number: root.index,
+				total_length: root.size,
+			}))
+			.collect()
+	}
+
+	/// The arguments registering the feed as it was at `length` chunks, extending the tree
+	/// registered at `registered_length` chunks, if any.
+	pub fn register_data(&self, length: u64, registered_length: Option<u64>) -> io::Result<RegisterData> {
+		self.ensure_signed(length)?;
+		let consistency_proof = match registered_length {
+			Some(old_length) => Some(self.consistency_proof(old_length, length)?),
+			None => None,
+		};
+		Ok(RegisterData {
+			merkle_root: (
+				self.key,
+				RootHashPayload { hash_type: ROOT_TYPE, children: self.roots(length)? },
+				H512::from(self.signature(length)?),
+			),
+			consistency_proof,
+		})
+	}
+
+	/// The arguments registering the feed privately as it was at `length` chunks, extending the
+	/// tree registered at `registered_length` chunks, if any.
+	pub fn register_private_data(
+		&self,
+		length: u64,
+		registered_length: Option<u64>,
+	) -> io::Result<RegisterPrivateData> {
+		self.ensure_signed(length)?;
+		let consistency_proof = match registered_length {
+			Some(old_length) => Some(self.consistency_proof(old_length, length)?),
+			None => None,
+		};
+		let registration = self.registration_pair()?;
+		let roots = self.roots(length)?;
+		let signature = registration.sign(hashing::tree(&roots).as_bytes());
+		Ok(RegisterPrivateData {
+			merkle_root: (
+				registration.public(),
+				RootHashPayload { hash_type: ROOT_TYPE, children: roots },
+				H512::from(signature),
+			),
+			consistency_proof,
+		})
+	}
+
+	/// The proof of the chunks of the `leaves`, in ascending order, against the tree registered
+	/// at `length` chunks. This is what a challenge asks for of every archive it names.
+	pub fn prove(&self, leaves: &[u64], length: u64) -> io::Result<Proof> {
+		self.ensure_signed(length)?;
+		if leaves.iter().any(|leaf| leaf % 2 != 0 || *leaf >= 2 * length) {
+			return Err(invalid_input("only leaves of the registered tree can be proved"));
+		}
+		Ok(Proof {
+			chunks: leaves.iter().map(|leaf| self.chunk(*leaf)).collect::<io::Result<_>>()?,
+			nodes: proof_nodes(leaves, length).into_iter()
+				.map(|index| self.node(index))
+				.collect::<io::Result<_>>()?,
+		})
+	}
+
+	/// The proof that the tree at `new_length` chunks extends the one at `old_length`: the
+	/// siblings on the path from every old root up to a new root.
+	fn consistency_proof(&self, old_length: u64, new_length: u64) -> io::Result<ConsistencyProof> {
+		self.ensure_signed(old_length)?;
+		if old_length > new_length {
+			return Err(invalid_input("the registered tree is longer than the update"));
+		}
+		let old_roots = self.roots(old_length)?;
+		let new_roots = flat_tree::full_roots(2 * new_length);
+		let mut indeces = Vec::new();
+		for root in old_roots.iter() {
+			let mut current = root.hash_number;
+			while !new_roots.contains(&current) {
+				indeces.push(flat_tree::sibling(current));
+				current = flat_tree::parent(current);
+			}
+		}
+		indeces.sort_unstable();
+		indeces.dedup();
+		Ok(ConsistencyProof {
+			old_roots,
+			nodes: indeces.into_iter().map(|index| self.node(index)).collect::<io::Result<_>>()?,
+		})
+	}
+
+	/// Fail unless the feed was signed when it had `length` chunks.
+	fn ensure_signed(&self, length: u64) -> io::Result<()> {
+		if length == 0 || length > self.length()? {
+			return Err(invalid_input("the feed was never signed at this length"));
+		}
+		Ok(())
+	}
+}
+
+/// A hyperdrive stored in a directory: its metadata feed in `metadata`, starting with the
+/// header naming its content feed, in `content`.
+pub struct Drive {
+	metadata: Feed,
+	content: Feed,
+}
+
+impl Drive {
+	/// Whether `dir` holds a drive rather than a single feed.
+	pub fn is_drive(dir: &Path) -> bool {
+		dir.join("metadata").is_dir()
+	}
+
+	/// The drive in `dir`, whose metadata header names the content feed in it.
+	pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+		let dir = dir.into();
+		let metadata = Feed::open(dir.join("metadata"))?;
+		let content = Feed::open(dir.join("content"))?;
+		metadata.ensure_signed(1)?;
+		if hyperdrive::content_key(&metadata.chunk(0)?) != Some(content.key()) {
+			return Err(invalid_data("the metadata header does not name the content feed"));
+		}
+		Ok(Drive { metadata, content })
+	}
+
+	/// Write a drive to `dir`, as hyperdrive does: a metadata feed signed by `metadata_pair`
+	/// holding the header and the `entries`, and a content feed of `chunks` signed by
+	/// `content_pair`.
+	pub fn create(
+		dir: impl Into<PathBuf>,
+		metadata_pair: &ed25519::Pair,
+		content_pair: &ed25519::Pair,
+		entries: &[impl AsRef<[u8]>],
+		chunks: &[impl AsRef<[u8]>],
+	) -> io::Result<Self> {
+		let dir = dir.into();
+		let content = Feed::create(dir.join("content"), content_pair, chunks)?;
+		let mut metadata_chunks = vec![hyperdrive::header(&content.key())];
+		metadata_chunks.extend(entries.iter().map(|entry| entry.as_ref().to_vec()));
+		let metadata = Feed::create(dir.join("metadata"), metadata_pair, &metadata_chunks[..])?;
+		Ok(Drive { metadata, content })
+	}
+
+	/// The metadata feed of the drive.
+	pub fn metadata(&self) -> &Feed {
+		&self.metadata
+	}
+
+	/// The content feed of the drive.
+	pub fn content(&self) -> &Feed {
+		&self.content
+	}
+
+	/// The arguments registering the drive as it was at `metadata_length` and `content_length`
+	/// chunks. Its feeds are updated one by one, with `Feed::register_data`.
+	pub fn register_drive(&self, metadata_length: u64, content_length: u64) -> io::Result<RegisterDrive> {
+		Ok(RegisterDrive {
+			metadata: self.metadata.register_data(metadata_length, None)?.merkle_root,
+			content: self.content.register_data(content_length, None)?.merkle_root,
+			header_proof: self.metadata.prove(&[0], metadata_length)?,
+		})
+	}
+}
+
+fn read_at(path: &Path, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+	let mut file = File::open(path)?;
+	file.seek(SeekFrom::Start(offset))?;
+	file.read_exact(buf)
+}
+
+fn invalid_input(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
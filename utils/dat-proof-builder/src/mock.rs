@@ -0,0 +1,240 @@
+//! Test utilities
+
+use frame_support::{
+	impl_outer_origin, impl_outer_event, parameter_types, ord_parameter_types,
+	StorageMap, StorageLinkedMap, StorageDoubleMap,
+	weights::Weight,
+};
+use sp_core::{H256, Pair, ed25519, sr25519};
+use sp_runtime::{
+	Perbill,
+	traits::{BlakeTwo256, IdentityLookup, ConvertInto, IdentifyAccount},
+	testing::{Header, UintAuthorityId, TestXt},
+};
+use frame_system::{EnsureSignedBy, offchain::{CreateTransaction, Signer, TransactionSubmitter}};
+use pallet_dat_verify::{
+	ChallengeMap, SelectedUsers, SelectedUserIndex, SelectedChallenges, ChallengeExpiries, DatHosters,
+	Public, offchain::crypto::SeederId,
+};
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		pallet_balances<T>,
+		pallet_session,
+		pallet_dat_verify<T>,
+	}
+}
+
+// For testing the proofs against the module, we construct most of a mock runtime.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = ();
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const CreationFee: u64 = 0;
+}
+
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type OnReapAccount = System;
+	type OnNewAccount = ();
+	type Event = TestEvent;
+	type TransferPayment = ();
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type CreationFee = CreationFee;
+}
+
+parameter_types! {
+	pub const Period: u64 = 1;
+	pub const Offset: u64 = 0;
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(33);
+}
+
+impl pallet_session::Trait for Test {
+	type Event = TestEvent;
+	type ValidatorId = u64;
+	type ValidatorIdOf = ConvertInto;
+	type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
+	type SessionManager = ();
+	type SessionHandler = pallet_session::TestSessionHandler;
+	type Keys = UintAuthorityId;
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+}
+
+impl pallet_session::historical::Trait for Test {
+	type FullIdentification = u64;
+	type FullIdentificationOf = ConvertInto;
+}
+
+/// An extrinsic type used for tests.
+pub type Extrinsic = TestXt<pallet_dat_verify::Call<Test>, ()>;
+type SubmitTransaction = TransactionSubmitter<SeederId, Test, Extrinsic>;
+
+/// A seeder key, which stands for no account as offchain workers are not run in these tests.
+#[derive(Clone)]
+pub struct SeederKey(sr25519::Public);
+
+impl From<sr25519::Public> for SeederKey {
+	fn from(public: sr25519::Public) -> Self {
+		SeederKey(public)
+	}
+}
+
+impl From<SeederKey> for sr25519::Public {
+	fn from(key: SeederKey) -> Self {
+		key.0
+	}
+}
+
+impl IdentifyAccount for SeederKey {
+	type AccountId = u64;
+
+	fn into_account(self) -> u64 {
+		0
+	}
+}
+
+impl CreateTransaction<Test, Extrinsic> for Test {
+	type Public = SeederKey;
+	type Signature = sr25519::Signature;
+
+	fn create_transaction<F: Signer<Self::Public, Self::Signature>>(
+		_call: pallet_dat_verify::Call<Test>,
+		_public: Self::Public,
+		_account: u64,
+		_index: u64,
+	) -> Option<(pallet_dat_verify::Call<Test>, (u64, ()))> {
+		None
+	}
+}
+
+ord_parameter_types! {
+	pub const ForceAccount: u64 = 100;
+}
+
+parameter_types! {
+	pub const SeederBond: u64 = 10;
+	pub const BondPerGigabyte: u64 = 2;
+	pub const ChallengeSlash: Perbill = Perbill::from_percent(50);
+	pub const ChallengeReward: u64 = 5;
+	pub const ChallengesPerBlock: u32 = 1;
+	pub const DetectableLoss: Perbill = Perbill::from_percent(50);
+	pub const DetectionProbability: Perbill = Perbill::from_percent(50);
+	pub const MaxChunksPerChallenge: u32 = 8;
+	pub const MaxChunkSize: u32 = 1024;
+	pub const MaxProofNodes: u32 = 32;
+	pub const MaxRootChildren: u32 = 8;
+	pub const AttestorsPerChallenge: u32 = 3;
+	pub const AttestationQuorum: u32 = 2;
+	pub const AttestationReward: u64 = 3;
+	pub const ProofReputation: u32 = 10;
+	pub const FailureReputation: u32 = 30;
+	pub const AttestationReputation: u32 = 5;
+	pub const MaxReputation: u32 = 100;
+	pub const ReputationDecay: Perbill = Perbill::from_percent(10);
+	pub const ReputationPeriod: u64 = 10;
+	pub const ReputationsDecayedPerBlock: u32 = 2;
+	pub const HostingsReleasedPerBlock: u32 = 2;
+}
+
+impl pallet_dat_verify::Trait for Test {
+	type Event = TestEvent;
+	type Randomness = ();
+	type ForceOrigin = EnsureSignedBy<ForceAccount, u64>;
+	type SeederMembership = ();
+	type UserMembership = ();
+	type Currency = Balances;
+	type SeederBond = SeederBond;
+	type BondPerGigabyte = BondPerGigabyte;
+	type ChallengeSlash = ChallengeSlash;
+	type ChallengeReward = ChallengeReward;
+	type ChallengesPerBlock = ChallengesPerBlock;
+	type DetectableLoss = DetectableLoss;
+	type DetectionProbability = DetectionProbability;
+	type MaxChunksPerChallenge = MaxChunksPerChallenge;
+	type MaxChunkSize = MaxChunkSize;
+	type MaxProofNodes = MaxProofNodes;
+	type MaxRootChildren = MaxRootChildren;
+	type AttestorsPerChallenge = AttestorsPerChallenge;
+	type AttestationQuorum = AttestationQuorum;
+	type AttestationReward = AttestationReward;
+	type ProofReputation = ProofReputation;
+	type FailureReputation = FailureReputation;
+	type AttestationReputation = AttestationReputation;
+	type MaxReputation = MaxReputation;
+	type ReputationDecay = ReputationDecay;
+	type ReputationPeriod = ReputationPeriod;
+	type ReputationsDecayedPerBlock = ReputationsDecayedPerBlock;
+	type HostingsReleasedPerBlock = HostingsReleasedPerBlock;
+	type TrustedSeeders = ();
+	type ReportChallengeFailure = ();
+	type StorageDeals = ();
+	type Call = pallet_dat_verify::Call<Test>;
+	type SubmitTransaction = SubmitTransaction;
+}
+
+pub type System = frame_system::Module<Test>;
+pub type Balances = pallet_balances::Module<Test>;
+
+/// The account publishing archives in the tests.
+pub const PUBLISHER: u64 = 1;
+/// The account seeding archives in the tests.
+pub const SEEDER: u64 = 2;
+
+/// The chunks of the feed written by the tests: leaves 0, 2, 4, 6 and 8.
+pub const CHUNKS: [&[u8]; 5] = [b"hello", b"world", b"this is", b"a small", b"hypercore"];
+
+/// The keypair of the feed written by the tests.
+pub fn feed_pair() -> ed25519::Pair {
+	ed25519::Pair::from_seed(&[7; 32])
+}
+
+/// Have `SEEDER` host the archives of `chunks`, and challenge it to prove them as challenge 0.
+pub fn challenge_seeder(chunks: &[(Public, u64)]) {
+	for (dat, _) in chunks {
+		<DatHosters<Test>>::insert(dat, SEEDER, 0);
+	}
+	<ChallengeMap>::insert(0, 0);
+	<SelectedUsers<Test>>::insert(0, SEEDER);
+	<SelectedUserIndex<Test>>::insert(SEEDER, (0, 1));
+	<SelectedChallenges<Test>>::insert(0, (chunks.to_vec(), 10));
+	<ChallengeExpiries<Test>>::insert(10, vec![0]);
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext: sp_io::TestExternalities = t.into();
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
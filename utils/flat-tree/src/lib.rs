@@ -0,0 +1,313 @@
+//! Index arithmetic for the flat tree layout used by hypercore.
+//!
+//! A hypercore feed stores its Merkle tree as one flat list of nodes ordered in-order: the
+//! chunks of data are the leaves at the even indeces, so the `n`th chunk is node `2 * n`, and
+//! every parent sits at the odd index between the two subtrees it covers.
+//!
+//! ```text
+//!       3
+//!   1       5
+//! 0   2   4   6   8
+//! ```
+//!
+//! A node is also addressed by its `depth`, the distance from the leaves, and its `offset`, its
+//! position among the nodes of the same depth. Every function here is pure index math, so the
+//! same crate is used by the runtime when verifying proofs and by off-chain tooling when
+//! building them.
+//!
+//! Indeces are supported up to `2^63 - 2`, beyond which the tree has no room for a parent.
+//! See <https://datprotocol.github.io/how-dat-works/#flat-trees>.
+
+#![warn(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+
+/// The index of the node at `depth` and `offset`.
+pub fn index(depth: u64, offset: u64) -> u64 {
+	((2 * offset + 1) << depth) - 1
+}
+
+/// The depth of the node at `index`, `0` for leaves.
+pub fn depth(index: u64) -> u64 {
+	(!index).trailing_zeros() as u64
+}
+
+/// The position of the node at `index` among the nodes of the same depth.
+pub fn offset(index: u64) -> u64 {
+	index.checked_shr(depth(index) as u32 + 1).unwrap_or(0)
+}
+
+/// The index of the parent of the node at `index`.
+pub fn parent(index: u64) -> u64 {
+	self::index(depth(index) + 1, offset(index) / 2)
+}
+
+/// The index of the other child of the parent of the node at `index`.
+pub fn sibling(index: u64) -> u64 {
+	self::index(depth(index), offset(index) ^ 1)
+}
+
+/// The index of the sibling of the parent of the node at `index`.
+pub fn uncle(index: u64) -> u64 {
+	sibling(parent(index))
+}
+
+/// The indeces of the left and right children of the node at `index`, `None` for leaves.
+pub fn children(index: u64) -> Option<(u64, u64)> {
+	match depth(index) {
+		0 => None,
+		depth => {
+			let offset = offset(index) * 2;
+			Some((self::index(depth - 1, offset), self::index(depth - 1, offset + 1)))
+		}
+	}
+}
+
+/// The index of the left child of the node at `index`, `None` for leaves.
+pub fn left_child(index: u64) -> Option<u64> {
+	children(index).map(|(left, _)| left)
+}
+
+/// The index of the right child of the node at `index`, `None` for leaves.
+pub fn right_child(index: u64) -> Option<u64> {
+	children(index).map(|(_, right)| right)
+}
+
+/// The index of the leftmost leaf under the node at `index`.
+pub fn left_span(index: u64) -> u64 {
+	match depth(index) {
+		0 => index,
+		depth => offset(index) << (depth + 1),
+	}
+}
+
+/// The index of the rightmost leaf under the node at `index`.
+pub fn right_span(index: u64) -> u64 {
+	match depth(index) {
+		0 => index,
+		depth => ((offset(index) + 1) << (depth + 1)) - 2,
+	}
+}
+
+/// The indeces of the leftmost and rightmost leaves under the node at `index`.
+pub fn spans(index: u64) -> (u64, u64) {
+	(left_span(index), right_span(index))
+}
+
+/// The number of nodes in the subtree rooted at `index`, including itself.
+pub fn count(index: u64) -> u64 {
+	(2 << depth(index)) - 1
+}
+
+/// The roots of the complete subtrees covering every node below `index`, left to right.
+///
+/// These are the roots a feed of `index / 2` chunks signs, so a feed whose last chunk is the
+/// leaf `2 * n` has the roots `full_roots(2 * n + 2)`. An odd `index` is rounded down.
+pub fn full_roots(index: u64) -> Vec<u64> {
+	let mut roots = Vec::new();
+	let mut remaining_leaves = index / 2;
+	let mut first_leaf = 0;
+	while remaining_leaves > 0 {
+		let mut subtree_leaves = 1;
+		while subtree_leaves * 2 <= remaining_leaves {
+			subtree_leaves *= 2;
+		}
+		roots.push(2 * first_leaf + subtree_leaves - 1);
+		first_leaf += subtree_leaves;
+		remaining_leaves -= subtree_leaves;
+	}
+	roots
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::BTreeMap;
+	use quickcheck::{quickcheck, TestResult};
+
+	/// A node of the naive tree, with its relations stored rather than computed.
+	struct NaiveNode {
+		depth: u64,
+		offset: u64,
+		parent: Option<u64>,
+		children: Option<(u64, u64)>,
+		spans: (u64, u64),
+		count: u64,
+	}
+
+	/// A complete binary tree built node by node and numbered by an in-order walk.
+	struct NaiveTree {
+		nodes: BTreeMap<u64, NaiveNode>,
+	}
+
+	impl NaiveTree {
+		/// The tree with `2^depth` leaves.
+		fn new(depth: u64) -> Self {
+			let mut tree = NaiveTree { nodes: BTreeMap::new() };
+			let mut offsets = vec![0; depth as usize + 1];
+			let mut next = 0;
+			tree.build(depth, &mut next, &mut offsets);
+			tree
+		}
+
+		fn build(&mut self, depth: u64, next: &mut u64, offsets: &mut [u64]) -> u64 {
+			let left = if depth > 0 { Some(self.build(depth - 1, next, offsets)) } else { None };
+			let index = *next;
+			*next += 1;
+			let right = if depth > 0 { Some(self.build(depth - 1, next, offsets)) } else { None };
+
+			let offset = offsets[depth as usize];
+			offsets[depth as usize] += 1;
+			let children = match (left, right) {
+				(Some(left), Some(right)) => Some((left, right)),
+				_ => None,
+			};
+			let (spans, count) = match children {
+				Some((left, right)) => {
+					self.nodes.get_mut(&left).unwrap().parent = Some(index);
+					self.nodes.get_mut(&right).unwrap().parent = Some(index);
+					let (left, right) = (&self.nodes[&left], &self.nodes[&right]);
+					((left.spans.0, right.spans.1), left.count + right.count + 1)
+				}
+				None => ((index, index), 1),
+			};
+			self.nodes.insert(index, NaiveNode { depth, offset, parent: None, children, spans, count });
+			index
+		}
+
+		fn node(&self, index: u64) -> &NaiveNode {
+			&self.nodes[&index]
+		}
+
+		fn sibling(&self, index: u64) -> Option<u64> {
+			let (left, right) = self.node(self.node(index).parent?).children?;
+			Some(if left == index { right } else { left })
+		}
+
+		/// The highest nodes whose leaves are all among the first `leaves` leaves.
+		fn full_roots(&self, leaves: u64) -> Vec<u64> {
+			self.nodes.iter()
+				.filter(|(_, node)| node.spans.1 < 2 * leaves)
+				.filter(|(_, node)| match node.parent {
+					Some(parent) => self.node(parent).spans.1 >= 2 * leaves,
+					None => true,
+				})
+				.map(|(index, _)| *index)
+				.collect()
+		}
+	}
+
+	// every `u8` index has its parent and uncle in a tree of depth 10.
+	const NAIVE_DEPTH: u64 = 10;
+
+	quickcheck! {
+		fn depth_and_offset_match_naive_tree(index: u8) -> bool {
+			let tree = NaiveTree::new(NAIVE_DEPTH);
+			let node = tree.node(index as u64);
+			depth(index as u64) == node.depth
+				&& offset(index as u64) == node.offset
+				&& self::index(node.depth, node.offset) == index as u64
+		}
+
+		fn relatives_match_naive_tree(index: u8) -> bool {
+			let tree = NaiveTree::new(NAIVE_DEPTH);
+			let index = index as u64;
+			let node = tree.node(index);
+			let parent_index = node.parent.unwrap();
+			Some(parent(index)) == node.parent
+				&& Some(sibling(index)) == tree.sibling(index)
+				&& Some(uncle(index)) == tree.sibling(parent_index)
+				&& children(index) == node.children
+				&& left_child(index) == node.children.map(|(left, _)| left)
+				&& right_child(index) == node.children.map(|(_, right)| right)
+		}
+
+		fn spans_and_count_match_naive_tree(index: u8) -> bool {
+			let tree = NaiveTree::new(NAIVE_DEPTH);
+			let node = tree.node(index as u64);
+			spans(index as u64) == node.spans
+				&& left_span(index as u64) == node.spans.0
+				&& right_span(index as u64) == node.spans.1
+				&& count(index as u64) == node.count
+		}
+
+		fn full_roots_match_naive_tree(leaves: u8) -> bool {
+			// 256 leaves, enough for any `u8` number of them.
+			let tree = NaiveTree::new(8);
+			let leaves = leaves as u64;
+			full_roots(2 * leaves) == tree.full_roots(leaves)
+				&& full_roots(2 * leaves + 1) == tree.full_roots(leaves)
+		}
+
+		fn children_of_parent_contain_index(index: u32) -> bool {
+			let index = index as u64;
+			match children(parent(index)) {
+				Some((left, right)) => (left == index) != (right == index)
+					&& (left == sibling(index) || right == sibling(index)),
+				None => false,
+			}
+		}
+
+		fn spans_cover_children(index: u32) -> TestResult {
+			let index = index as u64;
+			match children(index) {
+				Some((left, right)) => TestResult::from_bool(
+					left_span(index) == left_span(left)
+						&& right_span(index) == right_span(right)
+						&& count(index) == count(left) + count(right) + 1
+						&& left_span(index) <= index && index <= right_span(index)
+				),
+				None => TestResult::discard(),
+			}
+		}
+
+		fn full_roots_cover_every_leaf_once(leaves: u32) -> bool {
+			let leaves = leaves as u64 % 100_000;
+			let mut next_leaf = 0;
+			for root in full_roots(2 * leaves) {
+				if left_span(root) != next_leaf {
+					return false;
+				}
+				next_leaf = right_span(root) + 2;
+			}
+			next_leaf == 2 * leaves
+		}
+	}
+
+	#[test]
+	fn follows_the_hypercore_layout() {
+		assert_eq!(index(0, 0), 0);
+		assert_eq!(index(0, 1), 2);
+		assert_eq!(index(1, 0), 1);
+		assert_eq!(index(1, 1), 5);
+		assert_eq!(index(2, 0), 3);
+		assert_eq!(depth(5), 1);
+		assert_eq!(offset(5), 1);
+		assert_eq!(parent(0), 1);
+		assert_eq!(parent(4), 5);
+		assert_eq!(parent(5), 3);
+		assert_eq!(parent(3), 7);
+		assert_eq!(sibling(0), 2);
+		assert_eq!(sibling(1), 5);
+		assert_eq!(sibling(3), 11);
+		assert_eq!(uncle(0), 5);
+		assert_eq!(children(3), Some((1, 5)));
+		assert_eq!(children(8), None);
+		assert_eq!(spans(3), (0, 6));
+		assert_eq!(spans(19), (16, 22));
+		assert_eq!(count(7), 15);
+	}
+
+	#[test]
+	fn full_roots_of_small_feeds() {
+		assert_eq!(full_roots(0), Vec::<u64>::new());
+		assert_eq!(full_roots(2), vec![0]);
+		assert_eq!(full_roots(4), vec![1]);
+		assert_eq!(full_roots(6), vec![1, 4]);
+		assert_eq!(full_roots(8), vec![3]);
+		assert_eq!(full_roots(10), vec![3, 8]);
+		assert_eq!(full_roots(14), vec![3, 9, 12]);
+		assert_eq!(full_roots(26), vec![7, 19, 24]);
+	}
+}